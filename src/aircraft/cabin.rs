@@ -0,0 +1,348 @@
+//! Holds the descriptive parts of an aircraft that aren't stored in its tiles,
+//! such as its name, seat labels and cabin classes.
+
+use serde::{Deserialize, Serialize};
+
+use super::Aircraft;
+use super::tile::Variant;
+
+/// Row numbers left out when rows are numbered automatically.
+const SKIPPED_ROWS: [u16; 1] = [13];
+
+/// A cabin class, ordered from the first to board to the last.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone,
+         Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Class {
+    First,
+    Business,
+    PremiumEconomy,
+    Economy,
+}
+
+impl Class {
+    /// Whether passengers in this class board before economy.
+    pub fn is_premium(self) -> bool {
+        self != Class::Economy
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Class::First => "first",
+            Class::Business => "business",
+            Class::PremiumEconomy => "premium_economy",
+            Class::Economy => "economy",
+        }
+    }
+}
+
+/// Converts a string, such as "business" or "premium economy", into a cabin
+/// class.
+pub fn str_to_class(class: &str) -> Option<Class> {
+    match class.trim().to_lowercase().replace(' ', "_").as_str() {
+        "first" => Some(Class::First),
+        "business" => Some(Class::Business),
+        "premium_economy" | "premium" => Some(Class::PremiumEconomy),
+        "economy" => Some(Class::Economy),
+        _ => None,
+    }
+}
+
+/// How long the passengers of one cabin class took to be seated.
+///
+/// `class` is `None` for seats on rows without a class. `seated` counts the
+/// passengers who reached their seats, `mean_step` is the average step at
+/// which they did so and `last_step` is the step the last of them sat down.
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct ClassMetrics {
+    pub class: Option<Class>,
+    pub passengers: usize,
+    pub seated: usize,
+    pub mean_step: Option<f32>,
+    pub last_step: Option<u16>,
+}
+
+/// Information about a single row of the aircraft.
+///
+/// `number` is the row number printed on the aircraft, used when labelling
+/// seats, `bin_capacity` is how many bags the overhead bins above the row can
+/// hold. `aisle_width` sets the width of every aisle on the row, in place of
+/// the width of its column. Exit rows are marked by their `ExitRow` tiles.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct Row {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<Class>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bin_capacity: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aisle_width: Option<u16>,
+}
+
+/// A named exit, such as "L1".
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Exit {
+    pub x: u16,
+    pub y: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// The width of the aisle running down column `x`, in passengers.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Aisle {
+    pub x: u16,
+    pub width: u16,
+}
+
+/// Everything known about an aircraft besides its tiles.
+///
+/// `columns` holds the letter given to the seats in each column, with a space
+/// for columns without one, such as aisles. `rows` is indexed by `y`. Any of
+/// these may be left empty, as they are for aircraft read from csv layouts, in
+/// which case `SeatMap` numbers the rows and letters the columns itself.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Cabin {
+    pub name: Option<String>,
+    pub columns: String,
+    pub rows: Vec<Row>,
+    pub exits: Vec<Exit>,
+    pub aisles: Vec<Aisle>,
+}
+
+impl Cabin {
+    /// Returns the cabin class of row `y`, if it has one.
+    pub fn get_class(&self, y: u16) -> Option<Class> {
+        self.rows.get(y as usize)?.class
+    }
+
+    /// Returns the width of the aisle at `x`,`y`, set either by its row or by
+    /// its column, which is 1 unless set otherwise.
+    pub fn aisle_width(&self, x: u16, y: u16) -> u16 {
+        self.rows.get(y as usize)
+            .and_then(|row| row.aisle_width)
+            .or_else(|| self.aisles.iter()
+                     .find(|aisle| aisle.x == x)
+                     .map(|aisle| aisle.width))
+            .unwrap_or(1)
+    }
+}
+
+/// Maps seat labels, such as "23C", to the tiles they belong to.
+///
+/// Rows and columns are labelled from the aircraft's `Cabin` where it gives
+/// row numbers or column letters. Otherwise every row holding a seat is
+/// numbered from the front, starting at 1 and skipping row 13, and every
+/// column holding a seat is lettered from the left, skipping the aisles.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SeatMap {
+    rows: Vec<Option<u16>>,
+    columns: Vec<Option<char>>,
+}
+
+impl SeatMap {
+    /// Constructor labelling the seats of `aircraft`.
+    pub fn new(aircraft: &Aircraft) -> SeatMap {
+        let (size_x, size_y) = aircraft.get_size();
+        let cabin = aircraft.get_cabin();
+        let is_seat = |x: u16, y: u16| {
+            aircraft.get_tile_variant(x, y) == Variant::Seat
+        };
+
+        let rows = if cabin.rows.iter().any(|row| row.number.is_some()) {
+            (0..size_y as usize)
+                .map(|y| cabin.rows.get(y).and_then(|row| row.number))
+                .collect()
+        } else {
+            let mut number = 0;
+            (0..size_y)
+                .map(|y| {
+                    if !(0..size_x).any(|x| is_seat(x, y)) {
+                        return None;
+                    }
+                    number += 1;
+                    while SKIPPED_ROWS.contains(&number) {
+                        number += 1;
+                    }
+                    Some(number)
+                })
+                .collect()
+        };
+
+        let columns = if !cabin.columns.is_empty() {
+            let mut letters = cabin.columns.chars();
+            (0..size_x)
+                .map(|_| letters.next().filter(|c| !c.is_whitespace()))
+                .collect()
+        } else {
+            let mut letters = 'A'..='Z';
+            (0..size_x)
+                .map(|x| if (0..size_y).any(|y| is_seat(x, y)) {
+                    letters.next()
+                } else {
+                    None
+                })
+                .collect()
+        };
+
+        SeatMap { rows, columns }
+    }
+
+    /// Returns the label of the tile at `x`,`y`, if it has both a row number
+    /// and a column letter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aircraft_sim::aircraft::Aircraft;
+    /// # use aircraft_sim::aircraft::cabin::SeatMap;
+    /// # use aircraft_sim::aircraft::tile::Variant;
+    /// let mut aircraft = Aircraft::new(3,1);
+    /// aircraft.set_tile(0, 0, Variant::Seat);
+    /// aircraft.set_tile(2, 0, Variant::Seat);
+    ///
+    /// assert_eq!(SeatMap::new(&aircraft).label(2, 0), Some("1B".to_string()));
+    /// ```
+    pub fn label(&self, x: u16, y: u16) -> Option<String> {
+        let number = self.row(y)?;
+        let letter = self.column(x)?;
+        Some(format!("{}{}", number, letter))
+    }
+
+    /// Returns the number of row `y`, if it has one.
+    pub fn row(&self, y: u16) -> Option<u16> {
+        self.rows.get(y as usize).copied().flatten()
+    }
+
+    /// Returns the letter of column `x`, if it has one.
+    pub fn column(&self, x: u16) -> Option<char> {
+        self.columns.get(x as usize).copied().flatten()
+    }
+
+    /// Returns the coordinates of the tile labelled `label`, ignoring case and
+    /// surrounding whitespace.
+    pub fn find(&self, label: &str) -> Option<(u16, u16)> {
+        let label = label.trim().to_uppercase();
+        let split = label.find(|c: char| !c.is_ascii_digit())?;
+        let number: u16 = label[..split].parse().ok()?;
+        let mut letters = label[split..].chars();
+        let letter = letters.next().filter(|_| letters.next().is_none())?;
+
+        let y = self.rows.iter().position(|row| *row == Some(number))?;
+        let x = self.columns.iter()
+            .position(|column| column.map(|c| c.to_ascii_uppercase())
+                      == Some(letter))?;
+        Some((x as u16, y as u16))
+    }
+}
+
+impl Aircraft {
+    /// Returns the label of the seat at `x`,`y`, such as "12A", or `None` if
+    /// the tile isn't a seat.
+    pub fn seat_label(&self, x: u16, y: u16) -> Option<String> {
+        if self.get_tile_variant(x, y) != Variant::Seat {
+            return None;
+        }
+        SeatMap::new(self).label(x, y)
+    }
+
+    /// Returns the coordinates of the seat labelled `label`, if there is one.
+    pub fn find_seat(&self, label: &str) -> Option<(u16, u16)> {
+        SeatMap::new(self).find(label)
+            .filter(|(x, y)| self.get_tile_variant(*x, *y) == Variant::Seat)
+    }
+
+    /// Describes the seat at `x`,`y` by its label, or by its coordinates if it
+    /// doesn't have one.
+    pub fn describe_seat(&self, x: u16, y: u16) -> String {
+        self.seat_label(x, y).unwrap_or_else(|| format!("{},{}", x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an aircraft with seats either side of an aisle in column 3.
+    fn single_aisle(y: u16) -> Aircraft {
+        let mut aircraft = Aircraft::new(7, y);
+        for x in [0, 1, 2, 4, 5, 6] {
+            for y in 0..y {
+                aircraft.set_tile(x, y, Variant::Seat);
+            }
+        }
+        aircraft
+    }
+
+    #[test]
+    fn labels() {
+        let mut aircraft = single_aisle(2);
+        let mut cabin = Cabin {
+            columns: "ABC DEF".to_string(),
+            rows: vec![Row { number: Some(1),
+                             class: Some(Class::Business),
+                             bin_capacity: Some(4),
+                             aisle_width: None },
+                       Row::default()],
+            ..Cabin::default()
+        };
+        aircraft.set_cabin(cabin.clone());
+
+        assert_eq!(aircraft.seat_label(0, 0), Some("1A".to_string()));
+        assert_eq!(aircraft.seat_label(6, 0), Some("1F".to_string()));
+        assert_eq!(aircraft.seat_label(3, 0), None, "Aisle was labelled");
+        assert_eq!(aircraft.seat_label(0, 1), None,
+                   "Unnumbered row was labelled");
+        assert_eq!(aircraft.describe_seat(0, 1), "0,1");
+        assert_eq!(cabin.get_class(0), Some(Class::Business));
+        assert_eq!(cabin.get_class(1), None);
+
+        cabin.aisles.push(Aisle { x: 3, width: 2 });
+        assert_eq!(cabin.aisle_width(3, 0), 2);
+        assert_eq!(cabin.aisle_width(0, 0), 1);
+        cabin.rows[1].aisle_width = Some(3);
+        assert_eq!(cabin.aisle_width(3, 1), 3);
+    }
+
+    #[test]
+    fn automatic_labels() {
+        let mut aircraft = single_aisle(15);
+        aircraft.set_tile(3, 14, Variant::Entrance);
+        for x in 0..7 {
+            aircraft.set_tile(x, 2, Variant::Aisle);
+        }
+
+        assert_eq!(aircraft.seat_label(0, 0), Some("1A".to_string()));
+        assert_eq!(aircraft.seat_label(4, 1), Some("2D".to_string()));
+        assert_eq!(aircraft.seat_label(6, 3), Some("3F".to_string()),
+                   "Rows without seats weren't skipped");
+        assert_eq!(aircraft.seat_label(2, 12), Some("12C".to_string()));
+        assert_eq!(aircraft.seat_label(2, 13), Some("14C".to_string()),
+                   "Row 13 wasn't skipped");
+
+        assert_eq!(aircraft.find_seat("14C"), Some((2, 13)));
+        assert_eq!(aircraft.find_seat(" 2d "), Some((4, 1)));
+        assert_eq!(aircraft.find_seat("13A"), None);
+        assert_eq!(aircraft.find_seat("1G"), None);
+        assert_eq!(aircraft.find_seat("1AB"), None);
+        assert_eq!(aircraft.find_seat("C"), None);
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(str_to_class("business"), Some(Class::Business));
+        assert_eq!(str_to_class(" Premium Economy"),
+                   Some(Class::PremiumEconomy));
+        assert_eq!(str_to_class("premium_economy"),
+                   Some(Class::PremiumEconomy));
+        assert_eq!(str_to_class("steerage"), None);
+
+        assert!(Class::First < Class::Business);
+        assert!(Class::PremiumEconomy < Class::Economy);
+        assert!(Class::PremiumEconomy.is_premium());
+        assert!(!Class::Economy.is_premium());
+        assert_eq!(str_to_class(Class::PremiumEconomy.as_str()),
+                   Some(Class::PremiumEconomy));
+    }
+}
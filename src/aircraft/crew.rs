@@ -0,0 +1,119 @@
+//! Holds the duties of cabin crew, who share the aisle with passengers but
+//! follow a script instead of heading for a seat.
+//!
+//! Crew are placed straight onto a tile rather than boarding through the gate,
+//! and move by the same rules as passengers: they can't walk through walls or
+//! into somebody standing in an aisle, and they squeeze past others on open
+//! floor and seats with the same delay.
+
+/// A crew member's scripted behaviour.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Duty {
+    /// Walks back and forth between two tiles, such as the ends of an aisle.
+    Patrol((u16, u16), (u16, u16)),
+    /// Heads for the nearest passenger standing in the aisle with baggage, and
+    /// stows it for them once beside them so that they can sit straight down.
+    Assist,
+    /// Walks to a tile, such as one beside a door, and stays there.
+    Stand((u16, u16)),
+}
+
+/// A crew member's duty, and for a patrol, which end they are heading for.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Crew {
+    duty: Duty,
+    returning: bool,
+}
+
+impl Crew {
+    /// Constructor
+    ///
+    /// Patrols start by heading for their second tile.
+    pub fn new(duty: Duty) -> Crew {
+        Crew {
+            duty,
+            returning: false,
+        }
+    }
+
+    pub fn get_duty(&self) -> Duty {
+        self.duty
+    }
+
+    /// Returns the tile this crew member is heading for, if their duty takes
+    /// them to a fixed tile.
+    pub fn target(&self) -> Option<(u16, u16)> {
+        match self.duty {
+            Duty::Patrol(start, _) if self.returning => Some(start),
+            Duty::Patrol(_, end) => Some(end),
+            Duty::Stand(tile) => Some(tile),
+            Duty::Assist => None,
+        }
+    }
+
+    /// Turns a patrolling crew member around to head for the other end of
+    /// their patrol.
+    pub fn turn(&mut self) {
+        if let Duty::Patrol(..) = self.duty {
+            self.returning = !self.returning;
+        }
+    }
+}
+
+/// Reads a crew member's starting tile and duty from a description such as
+/// `"patrol 3,0 3,20"`, `"assist 3,0"` or `"stand 3,1"`.
+///
+/// Every crew member starts on the first tile given. Patrols walk between it
+/// and the second, and standing crew stay on it.
+pub fn parse_crew(spec: &str) -> Result<((u16, u16), Duty), &'static str> {
+    let mut words = spec.split_whitespace();
+    let duty = words.next().ok_or("Crew need a duty")?;
+    let tiles = words
+        .map(|word| {
+            let mut parts = word.split(',').map(|part| part.parse::<u16>());
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+                _ => Err("Crew tiles must be given as X,Y"),
+            }
+        })
+        .collect::<Result<Vec<(u16, u16)>, &'static str>>()?;
+
+    match (duty.to_lowercase().as_str(), tiles.as_slice()) {
+        ("patrol", [start, end]) => Ok((*start, Duty::Patrol(*start, *end))),
+        ("patrol", _) => Err("Patrols need a starting tile and an end tile"),
+        ("assist", [start]) => Ok((*start, Duty::Assist)),
+        ("stand", [tile]) => Ok((*tile, Duty::Stand(*tile))),
+        ("assist", _) | ("stand", _) => Err("Crew need a single starting tile"),
+        _ => Err("Unknown crew duty"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patrol() {
+        let mut crew = Crew::new(Duty::Patrol((3, 0), (3, 9)));
+        assert_eq!(crew.target(), Some((3, 9)));
+        crew.turn();
+        assert_eq!(crew.target(), Some((3, 0)));
+
+        let mut standing = Crew::new(Duty::Stand((1, 1)));
+        standing.turn();
+        assert_eq!(standing.target(), Some((1, 1)));
+        assert_eq!(Crew::new(Duty::Assist).target(), None);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_crew("patrol 3,0 3,20"),
+                   Ok(((3, 0), Duty::Patrol((3, 0), (3, 20)))));
+        assert_eq!(parse_crew(" Assist  3,4 "), Ok(((3, 4), Duty::Assist)));
+        assert_eq!(parse_crew("stand 1,2"), Ok(((1, 2), Duty::Stand((1, 2)))));
+        assert!(parse_crew("patrol 3,0").is_err());
+        assert!(parse_crew("stand 1;2").is_err());
+        assert!(parse_crew("dance 1,2").is_err());
+        assert!(parse_crew("").is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! Holds structures and methods for the gate and jet bridge that passengers
+//! pass through on their way to an `Entrance`.
+
+use std::collections::VecDeque;
+use super::person::Person;
+
+/// The boarding gate and the jet bridge behind it.
+///
+/// The gate agent checks one passenger in every `service_time` steps. Checked
+/// in passengers then spend `walk_time` steps walking down a jet bridge that
+/// can hold at most `bridge_length` passengers. A `bridge_length` of 0 means
+/// there is no bridge and passengers step straight from the gate onto the
+/// aircraft, and a `service_time` of 0 means the gate agent never holds anybody
+/// up. `party` is the party of the last passenger checked in, so that the rest
+/// of that party can follow straight after.
+pub struct Gate {
+    service_time: u16,
+    bridge_length: usize,
+    walk_time: u16,
+    next_service: u16,
+    bridge: VecDeque<(Person, u16)>,
+    party: Option<u16>,
+}
+
+impl Gate {
+    /// Constructor
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aircraft_sim::aircraft::gate::Gate;
+    /// let gate = Gate::new(2, 10, 5);
+    ///
+    /// assert_eq!(gate.get_bridge_count(), 0);
+    /// ```
+    pub fn new(service_time: u16, bridge_length: usize, walk_time: u16) -> Gate {
+        Gate {
+            service_time,
+            bridge_length,
+            walk_time,
+            next_service: 0,
+            bridge: VecDeque::<(Person, u16)>::new(),
+            party: None,
+        }
+    }
+
+    /// Constructor for a gate that never delays anybody, with passengers
+    /// queueing right at the door.
+    pub fn instant() -> Gate {
+        Gate::new(0, 0, 0)
+    }
+
+    /// Checks passengers in from `queue` and sends them down the jet bridge
+    /// while there is room on it.
+    ///
+    /// Does nothing when there is no jet bridge, as passengers are instead
+    /// checked in when an `Entrance` is free.
+    pub fn admit(&mut self, step: u16, queue: &mut Vec<Person>) {
+        while self.bridge_length > 0
+            && self.bridge.len() < self.bridge_length
+            && step >= self.next_service {
+            match self.next_arrival(queue, step) {
+                Some(i) => {
+                    let person = self.check_in(queue, i);
+                    let ready = step.saturating_add(self.walk_time);
+                    self.bridge.push_back((person, ready));
+                    self.next_service = step.saturating_add(self.service_time);
+                    log::debug!("Passenger entered the jet bridge");
+                },
+                None => break,
+            }
+            if self.service_time > 0 {
+                break;
+            }
+        }
+    }
+
+    /// Returns the next passenger ready to step onto the aircraft, if any.
+    pub fn next(&mut self, step: u16, queue: &mut Vec<Person>)
+        -> Option<Person> {
+        if self.bridge_length > 0 {
+            match self.bridge.front() {
+                Some((_, ready)) if *ready <= step => {
+                    self.bridge.pop_front().map(|(person, _)| person)
+                },
+                _ => None,
+            }
+        } else if step >= self.next_service {
+            let i = self.next_arrival(queue, step)?;
+            self.next_service = step.saturating_add(self.service_time);
+            Some(self.check_in(queue, i))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the passenger `next` would return, without removing them.
+    pub fn peek<'a>(&'a self, step: u16, queue: &'a [Person])
+        -> Option<&'a Person> {
+        if self.bridge_length > 0 {
+            match self.bridge.front() {
+                Some((person, ready)) if *ready <= step => Some(person),
+                _ => None,
+            }
+        } else if step >= self.next_service {
+            self.next_arrival(queue, step).map(|i| &queue[i])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_service_time(&self) -> u16 {
+        self.service_time
+    }
+
+    pub fn get_bridge_length(&self) -> usize {
+        self.bridge_length
+    }
+
+    pub fn get_walk_time(&self) -> u16 {
+        self.walk_time
+    }
+
+    /// Returns the number of passengers currently on the jet bridge.
+    pub fn get_bridge_count(&self) -> usize {
+        self.bridge.len()
+    }
+
+    /// Finds the next passenger in the queue who is ready to board.
+    ///
+    /// Passengers board from the back of `queue`; anyone who hasn't arrived
+    /// yet is skipped over until they do. Members of a party wait until the
+    /// whole party has arrived, and then board one after the other.
+    fn next_arrival(&self, queue: &[Person], step: u16) -> Option<usize> {
+        if self.party.is_some() {
+            let i = queue.iter().rposition(|p| p.get_party() == self.party);
+            if i.is_some() {
+                return i;
+            }
+        }
+
+        queue.iter().rposition(|p| {
+            p.get_arrival() <= step && match p.get_party() {
+                Some(_) => queue.iter()
+                    .filter(|q| q.get_party() == p.get_party())
+                    .all(|q| q.get_arrival() <= step),
+                None => true,
+            }
+        })
+    }
+
+    /// Removes a passenger from the queue, remembering their party.
+    fn check_in(&mut self, queue: &mut Vec<Person>, i: usize) -> Person {
+        let person = queue.remove(i);
+        self.party = person.get_party();
+        person
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(n: usize) -> Vec<Person> {
+        (0..n).map(|i| Person::new(&i.to_string())).collect()
+    }
+
+    #[test]
+    fn instant() {
+        let mut gate = Gate::instant();
+        let mut queue = queue(2);
+
+        gate.admit(0, &mut queue);
+        assert_eq!(gate.get_bridge_count(), 0);
+        assert_eq!(gate.peek(0, &queue).unwrap().get_name(), "1");
+        assert_eq!(gate.next(0, &mut queue).unwrap().get_name(), "1");
+        assert_eq!(gate.next(0, &mut queue).unwrap().get_name(), "0");
+        assert!(gate.next(0, &mut queue).is_none());
+    }
+
+    #[test]
+    fn service_time() {
+        let mut gate = Gate::new(3, 0, 0);
+        let mut queue = queue(2);
+
+        assert!(gate.next(0, &mut queue).is_some());
+        assert!(gate.next(1, &mut queue).is_none());
+        assert!(gate.next(2, &mut queue).is_none());
+        assert!(gate.next(3, &mut queue).is_some());
+    }
+
+    #[test]
+    fn parties() {
+        let mut gate = Gate::instant();
+        let mut queue = queue(4);
+        queue[1].set_party(Some(0));
+        queue[3].set_party(Some(0));
+        queue[3].set_arrival(2);
+
+        // The party waits for its late member, letting others go first.
+        assert_eq!(gate.next(0, &mut queue).unwrap().get_name(), "2");
+        assert_eq!(gate.next(1, &mut queue).unwrap().get_name(), "0");
+        assert!(gate.next(1, &mut queue).is_none());
+
+        assert_eq!(gate.next(2, &mut queue).unwrap().get_name(), "3");
+        assert_eq!(gate.next(2, &mut queue).unwrap().get_name(), "1");
+    }
+
+    #[test]
+    fn jet_bridge() {
+        let mut gate = Gate::new(0, 2, 4);
+        let mut queue = queue(3);
+
+        gate.admit(0, &mut queue);
+        assert_eq!(gate.get_bridge_count(), 2, "Bridge capacity ignored");
+        assert_eq!(queue.len(), 1);
+
+        assert!(gate.next(3, &mut queue).is_none(),
+                "Passenger walked the bridge too quickly");
+        assert_eq!(gate.next(4, &mut queue).unwrap().get_name(), "2");
+
+        gate.admit(4, &mut queue);
+        assert_eq!(gate.get_bridge_count(), 2);
+        assert!(queue.is_empty());
+    }
+}
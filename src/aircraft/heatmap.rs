@@ -0,0 +1,176 @@
+//! Holds the per-tile counters used to find where passengers lose time.
+
+/// The counters an `Aircraft` keeps for every tile while it is simulated.
+///
+/// `occupied` counts the steps each tile held a passenger, counting twice
+/// when one is making their way past another. `waited` counts the steps a
+/// passenger on the tile wanted to move but couldn't, and `passes` counts how
+/// many times a passenger squeezed past another into the tile. Each grid is
+/// indexed by column then row, like the aircraft's layout.
+///
+/// Counts are kept as floats so that heatmaps from many simulations can be
+/// averaged together with `average`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Heatmap {
+    occupied: Vec<Vec<f32>>,
+    waited: Vec<Vec<f32>>,
+    passes: Vec<Vec<f32>>,
+}
+
+/// The counters that can be read from a `Heatmap`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Counter {
+    Occupied,
+    Waited,
+    Passes,
+}
+
+const COUNTERS: [Counter; 3] = [Counter::Occupied,
+                                Counter::Waited,
+                                Counter::Passes];
+
+/// Converts a string into an associated heatmap counter.
+pub fn str_to_counter(counter: &str) -> Option<Counter> {
+    match counter {
+        "occupied" => Some(Counter::Occupied),
+        "waited" => Some(Counter::Waited),
+        "passes" => Some(Counter::Passes),
+        _ => None,
+    }
+}
+
+impl Heatmap {
+    /// Constructor with every counter at zero.
+    pub fn new(x: u16, y: u16) -> Heatmap {
+        let grid = vec![vec![0.0; y as usize]; x as usize];
+        Heatmap {
+            occupied: grid.clone(),
+            waited: grid.clone(),
+            passes: grid,
+        }
+    }
+
+    /// Averages the heatmaps of many simulations of the same size.
+    ///
+    /// Returns `None` if there are no heatmaps or their sizes differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aircraft_sim::aircraft::heatmap::{Counter, Heatmap};
+    /// let mut a = Heatmap::new(1,1);
+    /// a.wait(0,0);
+    /// let average = Heatmap::average(&[a, Heatmap::new(1,1)]).unwrap();
+    ///
+    /// assert_eq!(average.get(Counter::Waited), &vec![vec![0.5]]);
+    /// ```
+    pub fn average(heatmaps: &[Heatmap]) -> Option<Heatmap> {
+        let first = heatmaps.first()?;
+        let (size_x, size_y) = first.get_size();
+        if heatmaps.iter().any(|h| h.get_size() != (size_x, size_y)) {
+            return None;
+        }
+
+        let mut average = Heatmap::new(size_x, size_y);
+        let count = heatmaps.len() as f32;
+        for heatmap in heatmaps {
+            for counter in COUNTERS {
+                let totals = average.get_mut(counter);
+                for (total, values) in totals.iter_mut()
+                    .zip(heatmap.get(counter)) {
+                    for (t, v) in total.iter_mut().zip(values) {
+                        *t += v / count;
+                    }
+                }
+            }
+        }
+        Some(average)
+    }
+
+    /// Records that the tile at `x`,`y` held a passenger for a step.
+    pub fn occupy(&mut self, x: usize, y: usize) {
+        self.occupied[x][y] += 1.0;
+    }
+
+    /// Records that a passenger on the tile at `x`,`y` was held up for a step.
+    pub fn wait(&mut self, x: usize, y: usize) {
+        self.waited[x][y] += 1.0;
+    }
+
+    /// Records that a passenger squeezed past another into the tile at `x`,`y`.
+    pub fn pass(&mut self, x: usize, y: usize) {
+        self.passes[x][y] += 1.0;
+    }
+
+    pub fn get(&self, counter: Counter) -> &Vec<Vec<f32>> {
+        match counter {
+            Counter::Occupied => &self.occupied,
+            Counter::Waited => &self.waited,
+            Counter::Passes => &self.passes,
+        }
+    }
+
+    fn get_mut(&mut self, counter: Counter) -> &mut Vec<Vec<f32>> {
+        match counter {
+            Counter::Occupied => &mut self.occupied,
+            Counter::Waited => &mut self.waited,
+            Counter::Passes => &mut self.passes,
+        }
+    }
+
+    /// Returns a counter scaled so that its largest value is 1, for drawing.
+    pub fn normalised(&self, counter: Counter) -> Vec<Vec<f32>> {
+        let grid = self.get(counter);
+        let max = grid.iter().flatten().cloned().fold(0.0, f32::max);
+        grid.iter()
+            .map(|column| column.iter()
+                 .map(|v| if max > 0.0 { v / max } else { 0.0 })
+                 .collect())
+            .collect()
+    }
+
+    pub fn get_size(&self) -> (u16, u16) {
+        (self.occupied.len() as u16,
+         self.occupied.first().map_or(0, |column| column.len()) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters() {
+        let mut heatmap = Heatmap::new(2, 3);
+        heatmap.occupy(1, 2);
+        heatmap.occupy(1, 2);
+        heatmap.wait(0, 1);
+        heatmap.pass(1, 0);
+
+        assert_eq!(heatmap.get_size(), (2, 3));
+        assert_eq!(heatmap.get(Counter::Occupied)[1][2], 2.0);
+        assert_eq!(heatmap.get(Counter::Waited)[0][1], 1.0);
+        assert_eq!(heatmap.get(Counter::Passes)[1][0], 1.0);
+        assert_eq!(heatmap.normalised(Counter::Occupied),
+                   vec![vec![0.0, 0.0, 0.0], vec![0.0, 0.0, 1.0]]);
+        assert_eq!(str_to_counter("waited"), Some(Counter::Waited));
+        assert_eq!(str_to_counter("queued"), None);
+    }
+
+    #[test]
+    fn average() {
+        let mut a = Heatmap::new(2, 2);
+        a.wait(0, 0);
+        a.wait(0, 0);
+        let mut b = Heatmap::new(2, 2);
+        b.pass(1, 1);
+
+        let average = Heatmap::average(&[a, b]).unwrap();
+        assert_eq!(average.get(Counter::Waited)[0][0], 1.0);
+        assert_eq!(average.get(Counter::Passes)[1][1], 0.5);
+
+        assert!(Heatmap::average(&[]).is_none());
+        assert!(Heatmap::average(&[Heatmap::new(2, 2), Heatmap::new(2, 3)])
+                .is_none(), "Heatmaps of different sizes were averaged");
+    }
+}
@@ -6,25 +6,118 @@
 
 pub mod tile;
 pub mod person;
+pub mod gate;
+pub mod heatmap;
+pub mod render;
+pub mod cabin;
+pub mod crew;
+pub mod policy;
+
+use std::sync::Arc;
 
 use simple_logger::SimpleLogger;
 
-use tile::{Tile, Variant};
+use tile::{Legroom, Tile, Variant};
 use person::{Person, Behaviour};
+use gate::Gate;
+use heatmap::Heatmap;
+use cabin::{Cabin, Class, ClassMetrics};
+use crew::Duty;
+use policy::{Agent, AgentPolicy, Greedy, Neighbourhood};
+
+pub const MAX_ITERATIONS: u16 = 1000;
+
+/// Whether passengers are boarding the aircraft or leaving it.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Mode {
+    Boarding,
+    Deplaning,
+}
+
+/// The order in which seated passengers are allowed to stand up and leave
+/// when deplaning.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Deplaning {
+    /// Rows leave one at a time, starting with the row closest to an exit.
+    RowByRow,
+    /// Everybody leaves as soon as they can.
+    FreeForAll,
+    /// Aisle seats leave first, then middle seats, then window seats.
+    AisleFirst,
+}
+
+/// Who passengers may overtake in an aisle, by squeezing past them.
+///
+/// Passengers can always step around each other on open floor such as galleys
+/// and cross-aisles, so these rules only cover aisles.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Overtaking {
+    /// Nobody overtakes in an aisle, however wide it is.
+    Never,
+    /// Passengers overtake anybody standing where the aisle is wide enough
+    /// for two.
+    Wide,
+}
 
-const MAX_ITERATIONS: u16 = 1000;
+impl Overtaking {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Overtaking::Never => "never",
+            Overtaking::Wide => "wide",
+        }
+    }
+}
+
+/// A passenger found sitting in somebody else's seat while boarding.
+///
+/// `seat` is the seat they took by mistake and `own_seat` the one they were
+/// sent on to, and `label` the label of `seat`, if it has one. `found` is the
+/// step by which the mistake came to light, and `resolved` the step by which
+/// they sat down in their own seat, if they have.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Conflict {
+    pub seat: (u16, u16),
+    pub own_seat: (u16, u16),
+    pub label: Option<String>,
+    pub found: u16,
+    pub resolved: Option<u16>,
+}
 
 /// A struct representing a simulated aircraft.
 ///
 /// This object simulates an aircraft, consisting of `layout`, a 2d vector of
 /// `Tile` objects representing each space onboard and its occupant(s) (or lack
-/// thereof), a list of passengers waiting to board, the aircraft's size as a
-/// convenient tuple, and a list of seats which should eventually be filled when
-/// the aircraft has been fully boarded.
+/// thereof), a list of passengers waiting to board, the gate they board
+/// through, the aircraft's size as a convenient tuple, a list of seats which
+/// should eventually be filled when the aircraft has been fully boarded, and
+/// the number of steps simulated so far. `heatmap` counts where passengers
+/// spend their time and get held up along the way, and `cabin` holds any
+/// descriptive information read from an aircraft definition. `manifest` keeps
+/// a copy of every passenger in the order they were added, so that the
+/// boarding order can be saved and run again. `seated_at` holds the step at
+/// which each of the `targeted_seats` was first taken by its passenger, and
+/// `conflicts` every passenger found sitting in the wrong seat.
+///
+/// Crew members are placed straight onto the `layout`, where they carry out
+/// their duties alongside the passengers without ever boarding or leaving.
+///
+/// Passengers board through whichever `Entrance` in `exits` is free, or with
+/// `nearest_door` set, only through the one nearest their seat, and
+/// `overtaking` sets who they may squeeze past in an aisle, whose width is
+/// given by `cabin`. With `yield_steps` set, a passenger held up in the aisle
+/// steps into an empty seat beside them to let somebody behind them reach
+/// their row, and stays there for that many steps.
+///
+/// Everybody decides how to move by their own `AgentPolicy`, or otherwise by
+/// `policy`.
+/// When deplaning, they instead start in their seats and make their way to the
+/// nearest `Entrance`, with only the passengers in zones up to `released`
+/// allowed to stand up.
 ///
 /// # Examples
 ///
 /// ```
+/// # use aircraft_sim::aircraft::Aircraft;
 /// let aircraft = Aircraft::new(7,10);
 ///
 /// assert_eq!((7,10), aircraft.get_size());
@@ -33,7 +126,21 @@ pub struct Aircraft {
     size: (u16, u16),
     layout: Vec<Vec<Tile>>,
     passengers: Vec<Person>,
+    gate: Gate,
     targeted_seats: Vec<(u16,u16)>,
+    seated_at: Vec<Option<u16>>,
+    step: u16,
+    mode: Mode,
+    exits: Vec<(u16,u16)>,
+    released: u16,
+    heatmap: Heatmap,
+    cabin: Cabin,
+    manifest: Vec<Person>,
+    conflicts: Vec<Conflict>,
+    overtaking: Overtaking,
+    yield_steps: Option<u8>,
+    nearest_door: bool,
+    policy: Arc<dyn AgentPolicy>,
 }
 
 impl Aircraft {
@@ -45,7 +152,21 @@ impl Aircraft {
             size: (x, y),
             layout: Vec::<Vec<Tile>>::new(),
             passengers: Vec::<Person>::new(),
+            gate: Gate::instant(),
             targeted_seats: Vec::<(u16,u16)>::new(),
+            seated_at: Vec::<Option<u16>>::new(),
+            step: 0,
+            mode: Mode::Boarding,
+            exits: Vec::<(u16,u16)>::new(),
+            released: 0,
+            heatmap: Heatmap::new(x, y),
+            cabin: Cabin::default(),
+            manifest: Vec::<Person>::new(),
+            conflicts: Vec::<Conflict>::new(),
+            overtaking: Overtaking::Wide,
+            yield_steps: None,
+            nearest_door: false,
+            policy: Arc::new(Greedy),
         };
         aircraft.clear();
         return aircraft;
@@ -63,164 +184,92 @@ impl Aircraft {
     }
     
     /// Adds a passenger to the aircraft
-    pub fn add_passenger(&mut self, p: Person) {
+    ///
+    /// A passenger whose seat was given by its label is sent to the seat with
+    /// that label, if the aircraft has one, and likewise for a wrong seat.
+    pub fn add_passenger(&mut self, mut p: Person) {
+        if p.get_seat().is_none() {
+            if let Some(label) = p.get_seat_label() {
+                match self.find_seat(label) {
+                    Some((x, y)) => p.target_seat(x, y),
+                    None => log::error!("No seat labelled {}", label),
+                }
+            }
+        }
+        if p.get_wrong_seat().is_none() {
+            if let Some(label) = p.get_wrong_label() {
+                match self.find_seat(label) {
+                    Some((x, y)) => p.mistake_seat(x, y),
+                    None => log::error!("No seat labelled {}", label),
+                }
+            }
+        }
         let seat = p.get_seat();
         if seat.is_some() {
             self.targeted_seats.push(seat.unwrap());
+            self.seated_at.push(None);
         }
+        self.manifest.push(p.clone());
         self.passengers.push(p);
     }
 
-    /// For debug purposes: prints the aircraft's layout to stdout using ASCII
-    /// characters to represent passengers and different types of aisle.
-    pub fn ascii_render(&self) {
-        print!("   ");
-        for i in 0..self.size.0 {
-            print!("{}", i);
-        }
-        print!("\n");
-        for j in 0..self.size.1 as usize {
-            print!("{:>3}", j);
-            for i in 0..self.size.0 as usize {
-                if self.layout[i][j].is_occupied() {
-                    print!("@");
-                } else {
-                    print!("{}", match self.layout[i][j].get_variant() {
-                        Variant::Aisle => "*",
-                        Variant::Seat => "#",
-                        Variant::Entrance => "*",
-                        Variant::None => "?",
-                    });
-                }
-            }
-            print!("\n");
+    /// Adds a passenger to the aircraft like `add_passenger`, failing instead
+    /// if their seat, or the seat they head for by mistake, was given by a
+    /// label that no seat on the aircraft has.
+    pub fn try_add_passenger(&mut self, p: Person) -> Result<(), &'static str> {
+        let missing = |label: Option<&str>| {
+            label.is_some_and(|label| self.find_seat(label).is_none())
+        };
+        if p.get_seat().is_none() && missing(p.get_seat_label()) {
+            return Err("No seat has the passenger's seat label");
         }
-    }
-    
-    /// Determines the best move for a passenger.
-    ///
-    /// Takes into account the passenger's current location, their target
-    /// location, whether they are currently holding baggage, and their
-    /// surroundings to determine the best possible move at any given time. This
-    /// is returned as a `Behaviour` enum.
-    fn determine_move(&self,
-                      i: usize,
-                      j: usize,
-                      target_x: u16,
-                      target_y: u16,
-                      baggage: bool) -> (Behaviour, f32) {
-        let mut current_move = (Behaviour::Wait, 1000.0);
-        let target_seat = (target_x, target_y);
-        // let (i, j) = (pos_x as usize, pos_y as usize);
-        
-        if self.layout[i][j].get_variant() == Variant::Aisle
-        || self.layout[i][j].get_variant() == Variant::Entrance {
-            if target_seat.1 == j as u16 && baggage {
-                current_move = (Behaviour::Stow, 0.0);
-            } else {
-                // Decide movement based on other tile
-                for potential_move in &[
-                    (Behaviour::Wait, (0.0, 0.0)),
-                    (Behaviour::Move_North, (0.0, -1.0)),
-                    (Behaviour::Move_South, (0.0, 1.0)),
-                    (Behaviour::Move_East, (1.0, 0.0)),
-                    (Behaviour::Move_West, (-1.0, 0.0)),
-                ] {
-                    let (dest_x, dest_y)
-                        = (i as f32 + (potential_move.1).0,
-                           j as f32 + (potential_move.1).1);
-                    let (dest_x, dest_y)
-                        = (dest_x as usize,
-                           dest_y as usize);
-
-                    // Calculates the distance from the potential tile to the
-                    // target tile using the pythagorean theorum.
-                    let new_distance
-                        = ((target_seat.0 as f32 - dest_x as f32).abs()
-                           + (target_seat.1 as f32 - dest_y as f32).abs());
-
-                    if new_distance < current_move.1 {
-                        // Check whether the movement would place this passenger
-                        // on the wrong row and, if so, wait instead.
-                        if !(potential_move.0 == Behaviour::Move_East
-                             && dest_y != target_seat.1 as usize)
-                            && !(potential_move.0 == Behaviour::Move_West
-                                 && dest_y != target_seat.1 as usize) {
-                            // Check whether desired seat is occupied
-                            if !self.layout[dest_x][dest_y].is_occupied()
-                                || (dest_x, dest_y) == (i, j) {
-                                current_move = (potential_move.0, new_distance);
-                                log::debug!("NEW MOVE: {:?} x {}",
-                                            current_move.0,
-                                            current_move.1);
-                            } else if !self.layout[dest_x][dest_y].is_allowing()
-                                && self.layout[dest_x][dest_y]
-                                    .get_variant() == Variant::Seat {
-                                // This movement is possible even though the
-                                // position is occupied, as two passengers can
-                                // temporarily share a space albeit with a
-                                // delay.
-                                current_move = (potential_move.0, new_distance);
-                                log::debug!("NEW MOVE: {:?} x {}",
-                                            current_move.0,
-                                            current_move.1);
-                            } else {
-                                log::debug!("No room to get past");
-                                log::debug!("REJECTED: {:?} x {}",
-                                            potential_move.0,
-                                            new_distance);
-                            }
-                        }
-                    } else {
-                        log::debug!("REJECTED: {:?} x {}",
-                                    potential_move.0,
-                                    new_distance);
-                    }
-                }
-            }
-        } else if self.layout[i][j].get_variant() == Variant::Seat {
-            // Decide movement based on other tile
-            for potential_move in &[
-                (Behaviour::Wait, (0.0, 0.0)),
-                (Behaviour::Move_East, (1.0, 0.0)),
-                (Behaviour::Move_West, (-1.0, 0.0)),
-            ] {
-                let (dest_x, dest_y) = (i as f32 + (potential_move.1).0,
-                                        j as f32 + (potential_move.1).1);
-                // Calculates the distance from the potential tile to the target
-                // tile using the pythagorean theorum.
-                let new_distance = ((target_seat.0 as f32 - dest_x as f32).abs()
-                                    + (target_seat.1 as f32 - dest_y as f32)
-                                        .abs());
-
-                if new_distance < current_move.1 {
-                    current_move = (potential_move.0, new_distance);
-                    log::debug!("NEW MOVE: {:?} x {}",
-                                current_move.0,
-                                current_move.1);
-                } else {
-                    log::debug!("REJECTED: {:?} x {}",
-                                potential_move.0,
-                                new_distance);
-                }
-            }
+        if p.get_wrong_seat().is_none() && missing(p.get_wrong_label()) {
+            return Err("No seat has the passenger's wrong seat label");
         }
+        self.add_passenger(p);
+        Ok(())
+    }
 
-        return current_move;
+    /// For debug purposes: prints the aircraft's layout to stdout using ASCII
+    /// characters to represent passengers and different types of aisle.
+    pub fn ascii_render(&self) {
+        print!("{}", self);
     }
     
     /// Updates every tile in the aircraft based on its current best move.
     ///
-    /// Runs `determine_move` on every occupied tile in the aircraft, and, where
+    /// Asks everybody in the aircraft for their move with `act`, and, where
     /// possible, executes this move.
     pub fn update(&mut self) {
+        self.gate.admit(self.step, &mut self.passengers);
+        if self.mode == Mode::Deplaning {
+            self.release();
+        }
+
         for x in 0..self.size.0 as usize {
             for y in 0..self.size.1 as usize {
                 // Check current tile variant
-                    if !self.layout[x][y].has_updated() && (
-                    self.layout[x][y].get_variant() == Variant::Entrance ||
-                    self.layout[x][y].get_variant() == Variant::Aisle ||
-                    self.layout[x][y].get_variant() == Variant::Seat ) {
+                    if !self.layout[x][y].has_updated()
+                    && self.layout[x][y].get_variant().is_passable() {
+                        // Passengers reaching an exit leave the aircraft
+                        if self.mode == Mode::Deplaning
+                        && self.layout[x][y].get_variant() == Variant::Entrance
+                        && self.layout[x][y].get_occupier()
+                            .is_some_and(|p| !p.is_crew()) {
+                            let back = self.layout[x][y].free()
+                                .and_then(|mut p| p.move_chair_back(None));
+                            if let Some((i, j)) = back {
+                                self.layout[i as usize][j as usize]
+                                    .set_chair(false);
+                            }
+                            log::info!("Passenger left the aircraft");
+                        }
+
+                        // Crew stow the baggage of passengers beside them
+                        if self.mode == Mode::Boarding {
+                            self.assist(x, y);
+                        }
+
                         // Check whether current tile is occupied
                         if self.layout[x][y].get_occupier().is_some() {
                             log::trace!("DEBUG: {:?}",
@@ -229,128 +278,670 @@ impl Aircraft {
                                             .unwrap()
                                             .has_baggage());
 
-                            // Choose movement
-                            let target = self.layout[x][y].get_occupier()
-                                .unwrap().get_seat().unwrap();
-                            let baggage = self.layout[x][y].get_occupier()
-                                .unwrap().has_baggage();
-                            let current_move =
-                                self.determine_move(x, y,
-                                                    target.0, target.1,
-                                                    baggage);
-
-                            if current_move.0 != Behaviour::Wait
-                            && current_move.0 != Behaviour::Stow {
-                                log::debug!("Passenger moved: {:?}",
-                                            current_move.0);
-
-                                let coords = match current_move.0 {
-                                    Behaviour::Move_North => (x, y - 1),
-                                    Behaviour::Move_South => (x, y + 1),
-                                    Behaviour::Move_East => (x + 1, y),
-                                    Behaviour::Move_West => (x - 1, y),
-                                    _ => {
-                                        // Invalid move; wait instead
-                                        log::warn!("Invalid move selected");
-                                        (x, y) // May be slightly buggy, however
-                                               // this branch shouldn't ever be
-                                               // called
-                                    },
-                                };
-
-                                if !self.layout[coords.0][coords.1]
-                                  .is_occupied() {
-                                    let person = self.layout[x][y].free();
-                                    self.layout[coords.0][coords.1]
-                                      .occupy(person.unwrap());
-                                } else if !self.layout[coords.0][coords.1]
-                                  .is_allowing() {
-                                    let person = self.layout[x][y].free();
-                                    self.layout[coords.0][coords.1]
-                                      .pass_in(person.unwrap());
-                                } else {
-                                    log::debug!("Passenger Waited");
+                            // Patrolling crew turn around at either end
+                            if let Some(crew) = self.layout[x][y]
+                                .get_occupier_as_mut()
+                                .and_then(Person::get_crew_as_mut) {
+                                if crew.target()
+                                    == Some((x as u16, y as u16)) {
+                                    crew.turn();
                                 }
-                            } else if current_move.0 == Behaviour::Stow {
-                                log::debug!("Passenger stowed");
-                                self.layout[x][y].get_occupier_as_mut()
-                                    .unwrap().remove_baggage();
-                            } else {
-                                log::debug!("Passenger waited");
                             }
+
+                            // Choose movement
+                            self.act(x, y, false);
                         }
                         
+                        // Passengers sort out who is in the wrong seat
+                        if self.mode == Mode::Boarding {
+                            self.resolve_conflict(x, y);
+                        }
+
+                        // A passer waits a few steps before they can move on
                         if self.layout[x][y].is_allowing()
-                        && self.layout[x][y].pass_count() {
-                            // Choose movement
-                            let target = self.layout[x][y]
-                                .get_passer().unwrap().get_seat().unwrap();
-                            let baggage = self.layout[x][y]
-                                .get_passer().unwrap().has_baggage();
-                            let current_move = 
-                                self.determine_move(x, y,
-                                                    target.0, target.1,
-                                                    baggage);
-                            
-
-                            if current_move.0 != Behaviour::Wait
-                            && current_move.0 != Behaviour::Stow {
-                                log::debug!("Passenger moved: {:?}",
-                                            current_move.0);
-                                    
-                                let coords = match current_move.0 {
-                                    Behaviour::Move_North => (x, y - 1),
-                                    Behaviour::Move_South => (x, y + 1),
-                                    Behaviour::Move_East => (x + 1, y),
-                                    Behaviour::Move_West => (x - 1, y),
-                                    _ => {
-                                        log::warn!("Impossible movement");
-                                        (x, y)
-                                    },
-                                };
-                                
-                                if !self.layout[coords.0][coords.1]
-                                  .is_occupied() {
-                                    let person = self.layout[x][y]
-                                      .pass_out();
-                                    self.layout[coords.0][coords.1]
-                                      .occupy(person);
-                                } else if !self.layout[coords.0][coords.1]
-                                  .is_allowing() {
-                                    let person =
-                                      self.layout[x][y].pass_out();
-                                    self.layout[coords.0][coords.1]
-                                      .pass_in(person);
-                                } else {
-                                    log::debug!("Passenger waited");
-                                }
-                                
-                            } else if current_move.0 == Behaviour::Stow {
-                                log::debug!("Stowed");
-                                self.layout[x][y]
-                                    .get_passer_as_mut()
-                                    .unwrap()
-                                    .remove_baggage();
-                                println!("DEBUG: STOWING");
-                            } else {
-                                log::debug!("Passenger waited");
-                            }
+                        && !self.layout[x][y].pass_count() {
+                            self.heatmap.wait(x, y);
+                        } else if self.layout[x][y].is_allowing() {
+                            self.act(x, y, true);
                         }
                         
-                        if self.layout[x][y].get_variant() == Variant::Entrance
-                        && self.passengers.len() > 0
-                        && !self.layout[x][y].is_occupied() {
-                            self.layout[x][y]
-                                .occupy(self.passengers.pop().unwrap());
-                            log::info!("Added passenger");
+                        if self.mode == Mode::Boarding
+                        && self.layout[x][y].get_variant() == Variant::Entrance
+                        && !self.layout[x][y].is_occupied()
+                        && !self.layout[x][y].has_chair()
+                        && self.boards_through(x as u16, y as u16) {
+                            if let Some(person) = self.gate
+                                .next(self.step, &mut self.passengers) {
+                                self.layout[x][y].occupy(person).unwrap();
+                                log::info!("Added passenger");
+                            }
                         }
                 }
             }
         }
 
+        for (x, column) in self.layout.iter().enumerate() {
+            for (y, tile) in column.iter().enumerate() {
+                if tile.is_occupied() {
+                    self.heatmap.occupy(x, y);
+                }
+                if tile.is_allowing() {
+                    self.heatmap.occupy(x, y);
+                }
+            }
+        }
+
+        if self.mode == Mode::Boarding {
+            self.record_seated();
+        }
+
         // Sets every tile's `updated` value to false, to ensure no inaccurate
         // behaviours on the next update.
         self.reset();
+        self.step = self.step.saturating_add(1);
+    }
+
+    /// Asks the occupier of the tile at `x`,`y`, or whoever is squeezing past
+    /// them if `passer` is set, for their move, and carries it out if they
+    /// can.
+    fn act(&mut self, x: usize, y: usize, passer: bool) {
+        let tile = &self.layout[x][y];
+        let person = if passer {
+            tile.get_passer()
+        } else {
+            tile.get_occupier()
+        }.unwrap();
+        let chair = person.uses_aisle_chair();
+        let (target, stow_row, held) = self.plan(person, x, y);
+        // Only somebody with a seat to themselves can be held in it
+        let held = held && !passer;
+        let behaviour = if held {
+            Behaviour::Wait
+        } else {
+            self.decide(x, y, Agent { person, target, stow_row })
+        };
+
+        match behaviour {
+            Behaviour::Stow => {
+                log::debug!("Passenger stowed");
+                let tile = &mut self.layout[x][y];
+                let person = if passer {
+                    tile.get_passer_as_mut()
+                } else {
+                    tile.get_occupier_as_mut()
+                };
+                person.unwrap().stow();
+            },
+            Behaviour::Wait => {
+                let seat = if passer { None } else { self.yield_seat(x, y) };
+                if let Some(i) = seat {
+                    log::debug!("Passenger stood aside");
+                    let mut person = self.layout[x][y].free().unwrap();
+                    person.stand_aside(x as u16, y as u16,
+                                       self.yield_steps.unwrap_or(0));
+                    self.layout[i][y].occupy(person).unwrap();
+                    return;
+                }
+
+                log::debug!("Passenger waited");
+                // Seated passengers and those held in their seats aren't being
+                // held up by anybody.
+                if passer || (!held && target != (x as u16, y as u16)) {
+                    self.heatmap.wait(x, y);
+                }
+                if held {
+                    self.layout[x][y].get_occupier_as_mut().unwrap()
+                        .wait_aside();
+                }
+            },
+            _ => {
+                let coords = match self.destination(x, y, behaviour) {
+                    Some(coords) => coords,
+                    None => {
+                        // Invalid move; wait instead
+                        log::warn!("Invalid move selected: {:?}", behaviour);
+                        self.heatmap.wait(x, y);
+                        return;
+                    },
+                };
+                log::debug!("Passenger moved: {:?}", behaviour);
+
+                let stepped_out = self.layout[x][y].get_variant().is_seating()
+                    && self.layout[coords.0][coords.1].get_variant()
+                    .is_walkable();
+                let passing = if !self.layout[coords.0][coords.1]
+                    .is_occupied() {
+                    let person = self.take(x, y, passer);
+                    self.layout[coords.0][coords.1].occupy(person).unwrap();
+                    false
+                } else if !self.layout[coords.0][coords.1].is_allowing() {
+                    let person = self.take(x, y, passer);
+                    self.layout[coords.0][coords.1].pass_in(person);
+                    self.heatmap.pass(coords.0, coords.1);
+                    true
+                } else {
+                    log::debug!("Passenger waited");
+                    self.heatmap.wait(x, y);
+                    return;
+                };
+                if stepped_out {
+                    let tile = &mut self.layout[coords.0][coords.1];
+                    let person = if passing {
+                        tile.get_passer_as_mut()
+                    } else {
+                        tile.get_occupier_as_mut()
+                    };
+                    person.unwrap().step_out();
+                }
+                if chair {
+                    self.move_chair((x, y), coords, passing);
+                }
+            },
+        }
+    }
+
+    /// Asks `agent`'s own policy, or otherwise the aircraft's, for their move
+    /// from `x`,`y`.
+    fn decide(&self, x: usize, y: usize, agent: Agent) -> Behaviour {
+        let policy = agent.person.get_policy().unwrap_or(&self.policy);
+        policy.decide(&Neighbourhood::new(self, x, y), &agent)
+    }
+
+    /// Finds the tile that `behaviour` would move somebody at `x`,`y` onto, if
+    /// it is on the aircraft and can be walked onto.
+    fn destination(&self, x: usize, y: usize, behaviour: Behaviour)
+        -> Option<(usize, usize)> {
+        let (x, y) = match behaviour {
+            Behaviour::Move_North => (x, y.checked_sub(1)?),
+            Behaviour::Move_South => (x, y + 1),
+            Behaviour::Move_East => (x + 1, y),
+            Behaviour::Move_West => (x.checked_sub(1)?, y),
+            _ => return None,
+        };
+        let tile = self.layout.get(x)?.get(y)?;
+        if tile.get_variant().is_passable() && !tile.has_chair() {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Takes the occupier off the tile at `x`,`y`, or whoever is squeezing
+    /// past them if `passer` is set.
+    fn take(&mut self, x: usize, y: usize, passer: bool) -> Person {
+        if passer {
+            self.layout[x][y].pass_out()
+        } else {
+            self.layout[x][y].free().unwrap()
+        }
+    }
+
+    /// Notes the step at which any passenger who has just reached their seat
+    /// sat down.
+    fn record_seated(&mut self) {
+        let step = self.step.saturating_add(1);
+        for (seat, seated_at) in self.targeted_seats.iter()
+            .zip(self.seated_at.iter_mut())
+            .filter(|(_, seated_at)| seated_at.is_none()) {
+            let seated = self.layout.get(seat.0 as usize)
+                .and_then(|column| column.get(seat.1 as usize))
+                .and_then(Tile::get_occupier)
+                .is_some_and(|p| p.belongs_in(*seat));
+            if seated {
+                *seated_at = Some(step);
+            }
+        }
+
+        for conflict in self.conflicts.iter_mut()
+            .filter(|conflict| conflict.resolved.is_none()) {
+            let seat = conflict.own_seat;
+            let seated = self.layout.get(seat.0 as usize)
+                .and_then(|column| column.get(seat.1 as usize))
+                .and_then(Tile::get_occupier)
+                .is_some_and(|p| p.belongs_in(seat));
+            if seated {
+                conflict.resolved = Some(step);
+            }
+        }
+    }
+
+    /// Sorts out a passenger squeezing into the seat at `x`,`y` to sit down
+    /// while somebody else is already sitting there.
+    ///
+    /// A rightful owner displaces whoever took their seat, and otherwise the
+    /// passenger arriving realises they're in the wrong seat. Either way, the
+    /// passenger in the wrong seat is left squeezing past the other on their
+    /// way to their own.
+    fn resolve_conflict(&mut self, x: usize, y: usize) {
+        let here = (x as u16, y as u16);
+        let tile = &mut self.layout[x][y];
+        let sitting = |person: Option<&Person>| {
+            person.is_some_and(|p| !p.is_crew() && p.get_target() == Some(here))
+        };
+        if tile.get_variant() != Variant::Seat
+            || !sitting(tile.get_occupier()) || !sitting(tile.get_passer()) {
+            return;
+        }
+
+        if tile.get_passer().and_then(Person::get_seat) == Some(here) {
+            let owner = tile.pass_out();
+            tile.occupy(owner).unwrap();
+        }
+        let person = tile.get_passer_as_mut().unwrap();
+        person.correct_seat();
+        match person.get_seat() {
+            Some(own_seat) => self.conflicts.push(Conflict {
+                seat: here,
+                own_seat,
+                label: self.seat_label(here.0, here.1),
+                found: self.step.saturating_add(1),
+                resolved: None,
+            }),
+            None => log::warn!("Passenger in the wrong seat has no seat"),
+        }
+    }
+
+    /// Works out where a passenger at `x`,`y` is heading, which row they need
+    /// to stop on to deal with their baggage, and whether they are being held
+    /// in their seat, either until their zone is released or while they stand
+    /// aside to let somebody by.
+    fn plan(&self, person: &Person, x: usize, y: usize)
+        -> ((u16, u16), Option<u16>, bool) {
+        if let Some(crew) = person.get_crew() {
+            let target = crew.target()
+                .or_else(|| self.needs_assistance(x, y))
+                .unwrap_or((x as u16, y as u16));
+            return (target, None, false);
+        }
+
+        // Passengers who took the wrong seat board as if it were their own
+        let seat = match self.mode {
+            Mode::Boarding => person.get_target().unwrap(),
+            Mode::Deplaning => person.get_seat().unwrap(),
+        };
+        let stow_row = if person.has_baggage() { Some(seat.1) } else { None };
+
+        match self.mode {
+            // Passengers standing aside in somebody else's row head back out
+            // to the aisle they came from rather than further along the row.
+            Mode::Boarding if seat.1 as usize != y
+                && self.layout[x][y].get_variant().is_seating() => {
+                let aisle = person.get_aside().map_or(x as u16, |tile| tile.0);
+                ((aisle, seat.1), stow_row, person.is_aside())
+            },
+            // Passengers in aisle chairs also stop beside their row to move
+            // into their seat.
+            Mode::Boarding if person.is_transferring() => {
+                (seat, Some(seat.1), false)
+            },
+            Mode::Boarding => (seat, stow_row, false),
+            Mode::Deplaning => {
+                let held = person.get_zone() > self.released
+                    && self.layout[x][y].get_variant() == Variant::Seat;
+                (self.nearest_exit(seat), stow_row, held)
+            },
+        }
+    }
+
+    /// Finds the nearest passenger to `x`,`y` who is standing beside their row
+    /// with baggage still to stow.
+    fn needs_assistance(&self, x: usize, y: usize) -> Option<(u16, u16)> {
+        let mut nearest: Option<((u16, u16), usize)> = None;
+        for (i, column) in self.layout.iter().enumerate() {
+            for (j, tile) in column.iter().enumerate() {
+                let waiting = tile.get_variant().is_walkable()
+                    && tile.get_occupier().is_some_and(|p| self.stowing(p, j));
+                let distance = i.abs_diff(x) + j.abs_diff(y);
+                if waiting && nearest.is_none_or(|(_, d)| distance < d) {
+                    nearest = Some(((i as u16, j as u16), distance));
+                }
+            }
+        }
+        nearest.map(|(tile, _)| tile)
+    }
+
+    /// Lets a crew member on assist duty stow the baggage of the passenger at
+    /// `x`,`y` if the passenger has reached their row and the crew member is
+    /// beside them, so that the passenger can sit straight down.
+    ///
+    /// The crew member spends the step stowing rather than moving.
+    fn assist(&mut self, x: usize, y: usize) {
+        let stowing = self.layout[x][y].get_variant().is_walkable()
+            && self.layout[x][y].get_occupier()
+                .is_some_and(|p| self.stowing(p, y));
+        if !stowing {
+            return;
+        }
+
+        let (i, j) = (x as i32, y as i32);
+        let helper = [(i - 1, j), (i + 1, j), (i, j - 1), (i, j + 1)].iter()
+            .filter(|(i, j)| *i >= 0 && *j >= 0
+                    && (*i as u16) < self.size.0 && (*j as u16) < self.size.1)
+            .map(|(i, j)| (*i as usize, *j as usize))
+            .find(|(i, j)| {
+                let tile = &self.layout[*i][*j];
+                !tile.has_updated() && tile.get_occupier()
+                    .and_then(Person::get_crew)
+                    .is_some_and(|crew| crew.get_duty() == Duty::Assist)
+            });
+        if let Some((i, j)) = helper {
+            log::debug!("Crew stowed baggage");
+            self.layout[x][y].get_occupier_as_mut().unwrap().remove_baggage();
+            self.layout[i][j].set_updated(true);
+        }
+    }
+
+    /// Checks whether `person`, standing on row `y`, has reached their row with
+    /// baggage still to stow.
+    fn stowing(&self, person: &Person, y: usize) -> bool {
+        !person.is_crew() && person.has_baggage()
+            && person.get_target().is_some_and(|seat| seat.1 as usize == y)
+    }
+
+    /// Places a crew member on the walkable tile at `x`,`y` to carry out
+    /// `duty`, as long as every tile their duty takes them to is walkable too.
+    pub fn add_crew(&mut self, x: u16, y: u16, duty: Duty)
+        -> Result<(), &'static str> {
+        let duty_tiles = match duty {
+            Duty::Patrol(start, end) => vec![start, end],
+            Duty::Stand(tile) => vec![tile],
+            Duty::Assist => Vec::new(),
+        };
+        for (i, j) in duty_tiles {
+            let tile = self.layout.get(i as usize)
+                .and_then(|column| column.get(j as usize))
+                .ok_or("Crew duties must stay inside the aircraft")?;
+            if !tile.get_variant().is_walkable() {
+                return Err("Crew duties must stay on aisles or open floor");
+            }
+        }
+
+        let tile = self.layout.get_mut(x as usize)
+            .and_then(|column| column.get_mut(y as usize))
+            .ok_or("Crew must start inside the aircraft")?;
+        if !tile.get_variant().is_walkable() {
+            return Err("Crew must start on an aisle or open floor");
+        }
+        if tile.is_occupied() {
+            return Err("Crew must start on an empty tile");
+        }
+        tile.occupy(Person::crew(duty)).unwrap();
+        tile.set_updated(false);
+        Ok(())
+    }
+
+    /// Checks whether somebody, in an aisle chair if `chair` is set, can
+    /// squeeze past whoever is at `x`,`y`.
+    ///
+    /// Crew step aside to let anybody past, even in an aisle. Aisle chairs are
+    /// too wide for anybody else to squeeze past on open floor, though seated
+    /// passengers are passed as usual. Otherwise, who can be overtaken in an
+    /// aisle depends on the `overtaking` rules.
+    fn can_pass(&self, x: usize, y: usize, chair: bool) -> bool {
+        let tile = &self.layout[x][y];
+        let occupier = tile.get_occupier();
+        if occupier.is_some_and(Person::is_crew) {
+            return true;
+        }
+        let variant = tile.get_variant();
+        if variant.is_walkable()
+            && (chair || occupier.is_some_and(Person::uses_aisle_chair)) {
+            return false;
+        }
+        if variant != Variant::Aisle {
+            return variant.allows_passing();
+        }
+
+        let wide = self.cabin.aisle_width(x as u16, y as u16) > 1;
+        match self.overtaking {
+            Overtaking::Never => false,
+            Overtaking::Wide => wide,
+        }
+    }
+
+    /// Lists the columns of the empty seats on either side of `x`,`y`.
+    fn empty_seats_beside(&self, x: usize, y: usize) -> Vec<usize> {
+        [x.checked_sub(1), Some(x + 1)].iter()
+            .flatten()
+            .copied()
+            .filter(|i| self.layout.get(*i).is_some_and(|column| {
+                let tile = &column[y];
+                tile.get_variant() == Variant::Seat
+                    && !tile.is_occupied() && !tile.is_allowing()
+                    && !tile.has_chair()
+            }))
+            .collect()
+    }
+
+    /// Finds an empty seat beside the aisle at `x`,`y` for whoever is held up
+    /// there to step into, if `yield_steps` is set and somebody right behind
+    /// them is heading for this row.
+    ///
+    /// They won't step into the seat the passenger they are letting by is
+    /// heading for, and nobody steps aside on their own row or in an aisle
+    /// chair.
+    fn yield_seat(&self, x: usize, y: usize) -> Option<usize> {
+        let tile = &self.layout[x][y];
+        let person = tile.get_occupier()?;
+        if self.yield_steps.is_none() || self.mode != Mode::Boarding
+            || !tile.get_variant().is_walkable() || tile.is_allowing()
+            || person.is_crew() || person.uses_aisle_chair()
+            || person.get_target()?.1 as usize == y {
+            return None;
+        }
+
+        let behind = [y.checked_sub(1), Some(y + 1)].iter()
+            .flatten()
+            .filter_map(|j| self.layout[x].get(*j))
+            .filter(|tile| tile.get_variant().is_walkable())
+            .filter_map(|tile| tile.get_occupier())
+            .filter(|p| !p.is_crew())
+            .filter_map(|p| p.get_target())
+            .find(|seat| seat.1 as usize == y)?;
+
+        self.empty_seats_beside(x, y).into_iter()
+            .find(|i| *i as u16 != behind.0)
+    }
+
+    /// Moves the back of an aisle chair along behind its passenger as they
+    /// move from `from` to `to`, or takes it away once they reach their seat.
+    /// `passing` is set if they are squeezing past whoever is on `to`.
+    fn move_chair(&mut self, from: (usize, usize), to: (usize, usize),
+                  passing: bool) {
+        // The back of the chair is on the tile the passenger last came from,
+        // as long as they are both in the aisle
+        let back = if self.layout[to.0][to.1].get_variant().is_walkable()
+            && self.layout[from.0][from.1].get_variant().is_walkable() {
+            Some((from.0 as u16, from.1 as u16))
+        } else {
+            None
+        };
+        let tile = &mut self.layout[to.0][to.1];
+        let person = if passing {
+            tile.get_passer_as_mut()
+        } else {
+            tile.get_occupier_as_mut()
+        };
+        let previous = person.unwrap().move_chair_back(back);
+
+        if let Some((i, j)) = previous {
+            self.layout[i as usize][j as usize].set_chair(false);
+        }
+        if let Some((i, j)) = back {
+            self.layout[i as usize][j as usize].set_chair(true);
+        }
+    }
+
+    /// Checks whether the next passenger to board should do so through the
+    /// `Entrance` at `x`,`y`.
+    ///
+    /// With `nearest_door` set and several doors, passengers board through the
+    /// one nearest their seat so that they don't meet others coming the
+    /// opposite way down the aisle.
+    fn boards_through(&self, x: u16, y: u16) -> bool {
+        if !self.nearest_door || self.exits.len() < 2 {
+            return true;
+        }
+        self.gate.peek(self.step, &self.passengers)
+            .and_then(Person::get_seat)
+            .is_none_or(|seat| self.nearest_exit(seat) == (x, y))
+    }
+
+    /// Finds the `Entrance` closest to the given seat.
+    pub(crate) fn nearest_exit(&self, seat: (u16, u16)) -> (u16, u16) {
+        *self.exits.iter()
+            .min_by_key(|e| (e.0 as i32 - seat.0 as i32).abs()
+                        + (e.1 as i32 - seat.1 as i32).abs())
+            .unwrap_or(&seat)
+    }
+
+    /// Releases the next deplaning zone once every passenger in earlier zones
+    /// has left their seat.
+    fn release(&mut self) {
+        let mut lowest: Option<u16> = None;
+        for column in &self.layout {
+            for tile in column {
+                if tile.get_variant() == Variant::Seat {
+                    if let Some(person) = tile.get_occupier() {
+                        let zone = person.get_zone();
+                        lowest = Some(lowest.map_or(zone, |l| l.min(zone)));
+                    }
+                }
+            }
+        }
+        if let Some(zone) = lowest {
+            if zone > self.released {
+                log::info!("Releasing deplaning zone {}", zone);
+                self.released = zone;
+            }
+        }
+    }
+
+    /// Switches the aircraft into deplaning mode.
+    ///
+    /// Every passenger still waiting to board is placed directly into their
+    /// seat and assigned a zone according to `strategy`. Passengers with
+    /// baggage collect it from the aisle beside their row before heading for
+    /// the nearest `Entrance`, which acts as an exit.
+    pub fn deplane(&mut self, strategy: Deplaning) {
+        log::info!("Deplaning aircraft {:?}", strategy);
+        self.mode = Mode::Deplaning;
+        self.released = 0;
+        self.targeted_seats.clear();
+        self.seated_at.clear();
+
+        self.exits.clear();
+        for x in 0..self.size.0 {
+            for y in 0..self.size.1 {
+                self.layout[x as usize][y as usize].set_chair(false);
+                if self.get_tile_variant(x, y) == Variant::Entrance {
+                    self.exits.push((x, y));
+                }
+            }
+        }
+
+        // Anybody left without room in their seat stays off the aircraft, so
+        // that deplaning never completes without them
+        let mut refused = Vec::<Person>::new();
+        while let Some(mut person) = self.passengers.pop() {
+            let seat = match person.get_seat() {
+                Some(seat) => seat,
+                None => continue,
+            };
+            let zone = match strategy {
+                Deplaning::FreeForAll => 0,
+                Deplaning::RowByRow => {
+                    (self.nearest_exit(seat).1 as i32 - seat.1 as i32)
+                        .unsigned_abs() as u16
+                },
+                Deplaning::AisleFirst => self.seats_from_aisle(seat),
+            };
+            person.set_zone(zone);
+            if let Err(person) = self.layout[seat.0 as usize][seat.1 as usize]
+                .occupy(person) {
+                log::error!("No room for a passenger in their seat");
+                refused.push(*person);
+            }
+        }
+        self.passengers = refused;
+        self.reset();
+    }
+
+    /// Counts the seats between the given seat and the nearest tile on the
+    /// same row that isn't part of a seat, counting the rest of a wide seat as
+    /// a seat of its own.
+    pub(crate) fn seats_from_aisle(&self, seat: (u16, u16)) -> u16 {
+        let row = seat.1 as usize;
+        let mut distance: u16 = 0;
+        loop {
+            let left = seat.0.checked_sub(distance + 1);
+            let right = seat.0 + distance + 1;
+            if left.is_some_and(|x| !self.layout[x as usize][row]
+                                .get_variant().is_seating())
+            || (right < self.size.0 && !self.layout[right as usize][row]
+                .get_variant().is_seating())
+            || (left.is_none() && right >= self.size.0) {
+                return distance;
+            }
+            distance += 1;
+        }
+    }
+
+    pub fn get_overtaking(&self) -> Overtaking {
+        self.overtaking
+    }
+
+    pub fn set_overtaking(&mut self, overtaking: Overtaking) {
+        self.overtaking = overtaking;
+    }
+
+    pub fn get_yield_steps(&self) -> Option<u8> {
+        self.yield_steps
+    }
+
+    pub fn get_nearest_door(&self) -> bool {
+        self.nearest_door
+    }
+
+    pub fn get_gate(&self) -> &Gate {
+        &self.gate
+    }
+
+    /// Sets whether passengers board only through the door nearest their seat
+    /// rather than through whichever door is free.
+    pub fn set_nearest_door(&mut self, nearest_door: bool) {
+        self.nearest_door = nearest_door;
+    }
+
+    /// Sets the policy followed by everybody who hasn't been given their own.
+    pub fn set_policy(&mut self, policy: Arc<dyn AgentPolicy>) {
+        self.policy = policy;
+    }
+
+    /// Lets passengers held up in the aisle step into an empty seat to let
+    /// somebody by, staying there for `steps` steps, or with `None`, stops
+    /// them.
+    pub fn set_yield_steps(&mut self, steps: Option<u8>) {
+        self.yield_steps = steps;
+    }
+
+    /// Sets the width of every aisle on row `y`, or with `None`, leaves it to
+    /// the width of each aisle's column.
+    pub fn set_aisle_width(&mut self, y: u16, width: Option<u16>)
+        -> Result<(), &'static str> {
+        if y >= self.size.1 {
+            return Err("Row is outside the aircraft");
+        }
+        let rows = &mut self.cabin.rows;
+        if rows.len() <= y as usize {
+            rows.resize(y as usize + 1, cabin::Row::default());
+        }
+        rows[y as usize].aisle_width = width;
+        Ok(())
+    }
+
+    pub fn get_mode(&self) -> Mode {
+        self.mode
     }
 
     /// Repeatedly updates an aircraft until either all passengers are seated or
@@ -372,12 +963,29 @@ impl Aircraft {
         }
     }
     
-    /// Checks whether all passengers are seated in their desired seats.
+    /// Replaces the gate passengers board through, allowing check-in time and
+    /// a jet bridge to be modelled.
+    pub fn set_gate(&mut self, gate: Gate) {
+        self.gate = gate;
+    }
+
+    /// Checks whether all passengers are seated in their own seats, with no
+    /// mistakes left to discover, or when deplaning, whether every passenger
+    /// has left the aircraft.
     pub fn is_complete(&self) -> bool {
+        if self.mode == Mode::Deplaning {
+            // Crew stay aboard once the passengers have left
+            return self.passengers.is_empty()
+                && self.layout.iter().flatten()
+                    .all(|t| t.get_occupier().is_none_or(Person::is_crew)
+                         && t.get_passer().is_none_or(Person::is_crew));
+        }
+
         let mut complete: bool = true;
         for targeted_seat in &self.targeted_seats {
             let (x, y) = targeted_seat;
-            if !self.layout[*x as usize][*y as usize].is_occupied() {
+            if !self.layout[*x as usize][*y as usize].get_occupier()
+                .is_some_and(|p| p.belongs_in(*targeted_seat)) {
                 complete = false;
             }
         }
@@ -401,12 +1009,15 @@ impl Aircraft {
     /// Changes the `variant` member of a tile to another `Variant` enum, which
     /// affects the way passengers will move when on that tile.
     pub fn set_tile(&mut self, x: u16, y: u16, var: Variant) {
-        self.layout[x as usize][y as usize] = match var {
-            Variant::Aisle => Tile::aisle(),
-            Variant::Seat => Tile::seat(),
-            Variant::Entrance => Tile::entrance(),
-            Variant::None => Tile::none(),
-        };
+        self.layout[x as usize][y as usize] = Tile::new(var);
+        self.exits.retain(|exit| *exit != (x, y));
+        if var == Variant::Entrance {
+            self.exits.push((x, y));
+        }
+    }
+
+    pub fn get_step(&self) -> u16 {
+        self.step
     }
 
     pub fn get_size(&self) -> (u16, u16) {
@@ -417,6 +1028,16 @@ impl Aircraft {
         self.layout[x as usize][y as usize].get_variant()
     }
 
+    pub fn get_legroom(&self, x: u16, y: u16) -> Legroom {
+        self.layout[x as usize][y as usize].get_legroom()
+    }
+
+    /// Sets the legroom of the seat at `x`,`y`, which is reset to
+    /// `Legroom::Standard` whenever the tile is replaced by `set_tile`.
+    pub fn set_legroom(&mut self, x: u16, y: u16, legroom: Legroom) {
+        self.layout[x as usize][y as usize].set_legroom(legroom);
+    }
+
     pub fn check_if_occupied(&self, x: u16, y: u16) -> bool {
         self.layout[x as usize][y as usize].is_occupied()
     }
@@ -424,6 +1045,100 @@ impl Aircraft {
     pub fn check_if_allowing(&self, x: u16, y: u16) -> bool {
         self.layout[x as usize][y as usize].is_allowing()
     }
+
+    pub fn get_occupier(&self, x: u16, y: u16) -> Option<&Person> {
+        self.layout[x as usize][y as usize].get_occupier()
+    }
+
+    pub fn get_passer(&self, x: u16, y: u16) -> Option<&Person> {
+        self.layout[x as usize][y as usize].get_passer()
+    }
+
+    pub fn get_heatmap(&self) -> &Heatmap {
+        &self.heatmap
+    }
+
+    /// Returns every passenger added to the aircraft, in the order they were
+    /// added.
+    pub fn get_manifest(&self) -> &[Person] {
+        &self.manifest
+    }
+
+    /// Returns every passenger found sitting in the wrong seat so far, in the
+    /// order they were found.
+    pub fn get_conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    pub fn get_cabin(&self) -> &Cabin {
+        &self.cabin
+    }
+
+    pub fn set_cabin(&mut self, cabin: Cabin) {
+        self.cabin = cabin;
+    }
+
+    /// Returns the cabin class of the seat at `x`,`y`, or `None` if the tile
+    /// isn't a seat or its row has no class.
+    pub fn get_class(&self, x: u16, y: u16) -> Option<Class> {
+        if self.get_tile_variant(x, y) != Variant::Seat {
+            return None;
+        }
+        self.cabin.get_class(y)
+    }
+
+    /// Sets the cabin class of every seat on row `y`.
+    pub fn set_class(&mut self, y: u16, class: Option<Class>) {
+        let rows = &mut self.cabin.rows;
+        if rows.len() <= y as usize {
+            rows.resize(y as usize + 1, cabin::Row::default());
+        }
+        rows[y as usize].class = class;
+    }
+
+    /// Summarises how long the passengers of each cabin class took to reach
+    /// their seats, from the first class to board to the last, followed by
+    /// any passengers seated on rows without a class.
+    pub fn class_metrics(&self) -> Vec<ClassMetrics> {
+        let mut metrics = Vec::<ClassMetrics>::new();
+        let mut totals = Vec::<u32>::new();
+        for (seat, seated_at) in self.targeted_seats.iter()
+            .zip(self.seated_at.iter()) {
+            let class = if seat.0 < self.size.0 && seat.1 < self.size.1 {
+                self.get_class(seat.0, seat.1)
+            } else {
+                None
+            };
+            let i = match metrics.iter().position(|m| m.class == class) {
+                Some(i) => i,
+                None => {
+                    metrics.push(ClassMetrics {
+                        class,
+                        passengers: 0,
+                        seated: 0,
+                        mean_step: None,
+                        last_step: None,
+                    });
+                    totals.push(0);
+                    metrics.len() - 1
+                },
+            };
+            metrics[i].passengers += 1;
+            if let Some(step) = seated_at {
+                metrics[i].seated += 1;
+                metrics[i].last_step = metrics[i].last_step.max(Some(*step));
+                totals[i] += *step as u32;
+            }
+        }
+
+        for (m, total) in metrics.iter_mut().zip(totals) {
+            if m.seated > 0 {
+                m.mean_step = Some(total as f32 / m.seated as f32);
+            }
+        }
+        metrics.sort_by_key(|m| (m.class.is_none(), m.class));
+        metrics
+    }
 }
 
 #[cfg(test)]
@@ -436,7 +1151,21 @@ mod tests {
             size: (5, 5),
             layout: Vec::<Vec<Tile>>::new(),
             passengers: Vec::<Person>::new(),
+            gate: Gate::instant(),
             targeted_seats: Vec::<(u16,u16)>::new(),
+            seated_at: Vec::<Option<u16>>::new(),
+            step: 0,
+            mode: Mode::Boarding,
+            exits: Vec::<(u16,u16)>::new(),
+            released: 0,
+            heatmap: Heatmap::new(5, 5),
+            cabin: Cabin::default(),
+            manifest: Vec::<Person>::new(),
+            conflicts: Vec::<Conflict>::new(),
+            overtaking: Overtaking::Wide,
+            yield_steps: None,
+            nearest_door: false,
+            policy: Arc::new(Greedy),
         };
 
         aircraft.clear();
@@ -723,6 +1452,203 @@ mod tests {
                 "Not all passengers reached their seats");
     }
 
+    #[test]
+    fn late_arrival() {
+        let mut aircraft = Aircraft::new(3,5);
+        aircraft.layout[1][4] = Tile::entrance();
+        for i in 0..5 {
+            aircraft.layout[0][i] = Tile::seat();
+        }
+
+        let mut late = Person::new("Late");
+        late.target_seat(0,0);
+        late.set_arrival(3);
+        aircraft.add_passenger(late);
+
+        let mut punctual = Person::new("Punctual");
+        punctual.target_seat(0,1);
+        aircraft.add_passenger(punctual);
+
+        aircraft.update();
+        assert_eq!(aircraft.passengers.len(), 1);
+        assert_eq!(aircraft.passengers[0].get_name(), "Late");
+
+        aircraft.update();
+        aircraft.update();
+        assert_eq!(aircraft.passengers.len(), 1,
+                   "Late passenger boarded before arriving");
+
+        aircraft.update();
+        assert_eq!(aircraft.passengers.len(), 0);
+        assert!(aircraft.run_to_completion().is_ok());
+    }
+
+    #[test]
+    fn gate_throughput() {
+        let mut aircraft = Aircraft::new(3,5);
+        aircraft.layout[1][4] = Tile::entrance();
+        aircraft.set_gate(Gate::new(4, 0, 0));
+        for i in 0..5 {
+            aircraft.layout[0][i] = Tile::seat();
+            let mut passenger = Person::new("DEFAULT");
+            passenger.target_seat(0, i as u16);
+            aircraft.add_passenger(passenger);
+        }
+
+        aircraft.update();
+        assert_eq!(aircraft.passengers.len(), 4);
+        for _ in 0..3 {
+            aircraft.update();
+        }
+        assert_eq!(aircraft.passengers.len(), 4,
+                   "Gate agent checked passengers in too quickly");
+        aircraft.update();
+        assert_eq!(aircraft.passengers.len(), 3);
+    }
+
+    #[test]
+    fn manifest() {
+        let mut aircraft = Aircraft::new(2, 3);
+        for y in 0..2 {
+            aircraft.set_tile(0, y, Variant::Seat);
+        }
+        aircraft.set_tile(1, 2, Variant::Entrance);
+        for (name, y) in [("FIRST", 0), ("SECOND", 1)] {
+            let mut person = Person::new(name);
+            person.target_seat(0, y);
+            aircraft.add_passenger(person);
+        }
+
+        assert!(aircraft.run_to_completion().is_ok());
+        let names: Vec<String> = aircraft.get_manifest().iter()
+            .map(Person::get_name)
+            .collect();
+        assert_eq!(names, vec!["FIRST", "SECOND"]);
+    }
+
+    #[test]
+    fn nearest_door() {
+        let board = |nearest_door| {
+            let mut aircraft = Aircraft::new(2, 6);
+            for y in 1..5 {
+                aircraft.set_tile(0, y, Variant::Seat);
+            }
+            aircraft.set_tile(1, 0, Variant::Entrance);
+            aircraft.set_tile(1, 5, Variant::Entrance);
+            aircraft.set_nearest_door(nearest_door);
+            let mut person = Person::new("DEFAULT");
+            person.target_seat(0, 4);
+            aircraft.add_passenger(person);
+            aircraft.update();
+            aircraft
+        };
+
+        // By default passengers board through the first free door
+        assert!(board(false).get_occupier(1, 0).is_some());
+
+        let aircraft = board(true);
+        assert!(aircraft.get_occupier(1, 0).is_none(),
+                "Passenger boarded through the farther door");
+        assert!(aircraft.get_occupier(1, 5).is_some());
+    }
+
+    #[test]
+    fn heatmap() {
+        let mut aircraft = Aircraft::new(3,5);
+        aircraft.layout[2][4] = Tile::entrance();
+        for i in 0..5 {
+            aircraft.layout[0][i] = Tile::seat();
+            aircraft.layout[1][i] = Tile::seat();
+        }
+
+        // The window passenger boards second and has to squeeze past
+        let mut window = Person::new("Window");
+        window.target_seat(0,0);
+        aircraft.add_passenger(window);
+        let mut aisle = Person::new("Aisle");
+        aisle.target_seat(1,0);
+        aircraft.add_passenger(aisle);
+
+        assert!(aircraft.run_to_completion().is_ok());
+        let heatmap = aircraft.get_heatmap().clone();
+        assert_eq!(heatmap.get_size(), (3, 5));
+        assert_eq!(heatmap.get(heatmap::Counter::Passes)[1][0], 1.0);
+        assert!(heatmap.get(heatmap::Counter::Waited)[1][0] > 0.0,
+                "Passing passenger didn't wait");
+        assert!(heatmap.get(heatmap::Counter::Occupied)[2][4] >= 2.0);
+
+        // Seated passengers aren't held up by anybody
+        aircraft.update();
+        assert_eq!(aircraft.get_heatmap().get(heatmap::Counter::Waited),
+                   heatmap.get(heatmap::Counter::Waited));
+    }
+
+    #[test]
+    fn deplaning() {
+        for strategy in &[Deplaning::RowByRow,
+                          Deplaning::FreeForAll,
+                          Deplaning::AisleFirst] {
+            let mut aircraft = Aircraft::new(5,5);
+            for i in 0..5 {
+                for j in &[0,1,3,4] {
+                    aircraft.layout[*j][i] = Tile::seat();
+                    let mut passenger = Person::new("DEFAULT");
+                    passenger.target_seat(*j as u16, i as u16);
+                    passenger.set_baggage(true);
+                    aircraft.add_passenger(passenger);
+                }
+            }
+            aircraft.layout[2][4] = Tile::entrance();
+
+            aircraft.deplane(*strategy);
+            assert!(aircraft.passengers.is_empty());
+            assert!(aircraft.layout[0][0].is_occupied(),
+                    "Passengers weren't seated before deplaning");
+            assert!(!aircraft.is_complete());
+
+            assert!(aircraft.run_to_completion().is_ok(),
+                    "Aircraft didn't empty using {:?}", strategy);
+        }
+
+        // Only two passengers fit in one seat, and nobody is lost
+        let mut aircraft = Aircraft::new(2, 1);
+        aircraft.set_tile(0, 0, Variant::Seat);
+        aircraft.set_tile(1, 0, Variant::Entrance);
+        for _ in 0..3 {
+            let mut passenger = Person::new("DEFAULT");
+            passenger.target_seat(0, 0);
+            aircraft.add_passenger(passenger);
+        }
+        aircraft.deplane(Deplaning::FreeForAll);
+        assert_eq!(aircraft.passengers.len(), 1);
+        assert!(aircraft.layout[0][0].is_allowing());
+        assert!(aircraft.run_to_completion().is_err());
+    }
+
+    #[test]
+    fn deplaning_zones() {
+        let mut aircraft = Aircraft::new(5,3);
+        for i in 0..3 {
+            for j in &[0,1,3,4] {
+                aircraft.layout[*j][i] = Tile::seat();
+            }
+        }
+        aircraft.layout[2][2] = Tile::entrance();
+
+        for seat in &[(0, 0), (1, 0), (3, 2)] {
+            let mut passenger = Person::new("DEFAULT");
+            passenger.target_seat(seat.0, seat.1);
+            aircraft.add_passenger(passenger);
+        }
+        aircraft.deplane(Deplaning::AisleFirst);
+
+        // The window passenger can't leave until the aisle passengers have.
+        aircraft.update();
+        assert!(aircraft.layout[0][0].is_occupied());
+        assert!(!aircraft.layout[1][0].is_occupied());
+        assert!(!aircraft.layout[3][2].is_occupied());
+    }
+
     #[test]
     fn advanced_stowing() {
         let mut aircraft = Aircraft::new(5,5);
@@ -750,4 +1676,432 @@ mod tests {
         assert!(aircraft.is_complete(),
                 "Not all passengers reached their seats(?)");
     }
+
+    #[test]
+    fn class_metrics() {
+        let mut aircraft = Aircraft::new(2, 4);
+        for y in 0..4 {
+            aircraft.set_tile(0, y, Variant::Seat);
+        }
+        aircraft.set_tile(1, 3, Variant::Entrance);
+        aircraft.set_class(3, Some(Class::Business));
+        assert_eq!(aircraft.get_class(0, 3), Some(Class::Business));
+        assert_eq!(aircraft.get_class(1, 3), None);
+        assert_eq!(aircraft.get_class(0, 0), None);
+
+        // Business boards first, as the last passenger added
+        for y in 0..4 {
+            let mut person = Person::new("DEFAULT");
+            person.target_seat(0, y);
+            aircraft.add_passenger(person);
+        }
+        let metrics = aircraft.class_metrics();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].seated, 0);
+        assert_eq!(metrics[0].last_step, None);
+
+        let steps = aircraft.run_to_completion().unwrap();
+        let metrics = aircraft.class_metrics();
+        assert_eq!(metrics[0].class, Some(Class::Business));
+        assert_eq!((metrics[0].passengers, metrics[0].seated), (1, 1));
+        assert_eq!(metrics[1].class, None);
+        assert_eq!((metrics[1].passengers, metrics[1].seated), (3, 3));
+        assert_eq!(metrics[1].last_step, Some(steps));
+        assert!(metrics[0].last_step < metrics[1].last_step);
+        assert!(metrics[1].mean_step.unwrap() < steps as f32);
+    }
+
+    #[test]
+    fn legroom() {
+        // The window passenger has to squeeze past the aisle passenger
+        let steps = |legroom: Legroom| {
+            let mut aircraft = Aircraft::new(3, 2);
+            aircraft.set_tile(0, 0, Variant::Seat);
+            aircraft.set_tile(1, 0, Variant::Seat);
+            aircraft.set_tile(0, 1, Variant::None);
+            aircraft.set_tile(1, 1, Variant::None);
+            aircraft.set_tile(2, 1, Variant::Entrance);
+            aircraft.set_legroom(1, 0, legroom);
+            assert_eq!(aircraft.get_legroom(1, 0), legroom);
+            for x in 0..2 {
+                let mut person = Person::new("DEFAULT");
+                person.target_seat(x, 0);
+                aircraft.add_passenger(person);
+            }
+            aircraft.run_to_completion().unwrap()
+        };
+
+        let standard = steps(Legroom::Standard);
+        assert!(steps(Legroom::Premium) < standard);
+        assert!(steps(Legroom::Tight) > standard);
+    }
+
+    #[test]
+    fn wide_seats() {
+        // A seat `width` tiles wide, with the aisle beside its last tile
+        let board = |width: u16| {
+            let mut aircraft = Aircraft::new(width + 1, 2);
+            aircraft.set_tile(0, 0, Variant::Seat);
+            for x in 1..width {
+                aircraft.set_tile(x, 0, Variant::SeatSpan);
+            }
+            for x in 0..width {
+                aircraft.set_tile(x, 1, Variant::None);
+            }
+            aircraft.set_tile(width, 1, Variant::Entrance);
+            let mut person = Person::new("DEFAULT");
+            person.target_seat(0, 0);
+            aircraft.add_passenger(person);
+            let steps = aircraft.run_to_completion().unwrap();
+            assert!(aircraft.get_occupier(0, 0).is_some());
+            (aircraft, steps)
+        };
+
+        let (narrow, narrow_steps) = board(1);
+        let (wide, wide_steps) = board(2);
+        assert_eq!(wide_steps, narrow_steps + 1);
+        assert_eq!(wide.seat_label(0, 0), narrow.seat_label(0, 0));
+        assert_eq!(wide.seat_label(1, 0), None);
+    }
+
+    #[test]
+    fn walls() {
+        // Galleys and exit rows are walked along like aisles
+        let mut aircraft = Aircraft::new(3, 4);
+        for y in 0..3 {
+            aircraft.set_tile(0, y, Variant::Seat);
+            aircraft.set_tile(2, y, Variant::Bulkhead);
+        }
+        aircraft.set_tile(1, 1, Variant::ExitRow);
+        aircraft.set_tile(1, 2, Variant::Galley);
+        aircraft.set_tile(0, 3, Variant::Lavatory);
+        aircraft.set_tile(1, 3, Variant::Entrance);
+        aircraft.set_tile(2, 3, Variant::Bulkhead);
+        for y in 0..3 {
+            let mut person = Person::new("DEFAULT");
+            person.target_seat(0, y);
+            aircraft.add_passenger(person);
+        }
+        assert!(aircraft.run_to_completion().is_ok());
+
+        // Nobody walks through a lavatory, even to reach their seat
+        let mut aircraft = Aircraft::new(3, 2);
+        aircraft.set_tile(0, 0, Variant::Seat);
+        aircraft.set_tile(1, 0, Variant::Lavatory);
+        aircraft.set_tile(0, 1, Variant::None);
+        aircraft.set_tile(1, 1, Variant::None);
+        aircraft.set_tile(2, 1, Variant::Entrance);
+        let mut person = Person::new("DEFAULT");
+        person.target_seat(0, 0);
+        aircraft.add_passenger(person);
+        for _ in 0..10 {
+            aircraft.update();
+        }
+        assert!(aircraft.get_occupier(1, 0).is_none(),
+                "Passenger walked into a lavatory");
+        assert!(aircraft.get_occupier(2, 0).is_some());
+    }
+
+    #[test]
+    fn aisle_chair() {
+        let board = |chair: bool| {
+            let mut aircraft = Aircraft::new(3, 6);
+            for y in 0..6 {
+                aircraft.set_tile(0, y, Variant::Seat);
+                aircraft.set_tile(2, y, Variant::None);
+            }
+            aircraft.set_tile(1, 5, Variant::Entrance);
+            // Passengers board from the back of the list
+            for (y, chair) in [(1, false), (0, chair)] {
+                let mut person = Person::new("DEFAULT");
+                person.target_seat(0, y);
+                person.set_aisle_chair(chair);
+                aircraft.add_passenger(person);
+            }
+            aircraft
+        };
+
+        let mut aircraft = board(true);
+        aircraft.update();
+        aircraft.update();
+        assert!(aircraft.get_occupier(1, 4).unwrap().uses_aisle_chair());
+        assert!(aircraft.layout[1][5].has_chair());
+        assert!(aircraft.get_occupier(1, 5).is_none(),
+                "Passenger boarded into the back of the aisle chair");
+        assert!(!aircraft.can_pass(1, 4, false));
+        assert!(aircraft.can_pass(0, 4, true));
+
+        let steps = aircraft.run_to_completion().unwrap() + 2;
+        assert!(aircraft.layout.iter().flatten().all(|t| !t.has_chair()),
+                "Aisle chair was left behind");
+        let walking = board(false).run_to_completion().unwrap();
+        assert!(steps >= walking + person::TRANSFER_STEPS as u16,
+                "{} steps with an aisle chair, {} without", steps, walking);
+
+        // Two aisle chairs one behind the other each keep their own back
+        let mut aircraft = Aircraft::new(2, 8);
+        for y in 0..8 {
+            aircraft.set_tile(0, y, Variant::Seat);
+        }
+        aircraft.set_tile(1, 7, Variant::Entrance);
+        for y in [3, 0] {
+            let mut person = Person::new("DEFAULT");
+            person.target_seat(0, y);
+            person.set_aisle_chair(true);
+            aircraft.add_passenger(person);
+        }
+        while !aircraft.is_complete() && aircraft.get_step() < 100 {
+            aircraft.update();
+            let mut backs: Vec<(u16, u16)> = aircraft.layout.iter().flatten()
+                .filter_map(|t| t.get_occupier()
+                            .and_then(Person::get_chair_back))
+                .collect();
+            backs.sort();
+            let mut chairs: Vec<(u16, u16)> = (0..2)
+                .flat_map(|x| (0..8).map(move |y| (x, y)))
+                .filter(|(x, y)| aircraft.layout[*x as usize][*y as usize]
+                        .has_chair())
+                .collect();
+            chairs.sort();
+            assert_eq!(backs, chairs, "Step {}", aircraft.get_step());
+        }
+        assert!(aircraft.is_complete());
+    }
+
+    #[test]
+    fn crew() {
+        // A single aisle with seats down one side
+        let build = |with_baggage: bool| {
+            let mut aircraft = Aircraft::new(2, 5);
+            for y in 0..4 {
+                aircraft.set_tile(0, y, Variant::Seat);
+            }
+            aircraft.set_tile(1, 4, Variant::Entrance);
+            let mut person = Person::new("DEFAULT");
+            person.target_seat(0, 2);
+            person.set_baggage(with_baggage);
+            aircraft.add_passenger(person);
+            aircraft
+        };
+
+        // Crew stow the bags of passengers beside them
+        let unassisted = build(true).run_to_completion().unwrap();
+        let mut aircraft = build(true);
+        aircraft.add_crew(1, 1, Duty::Assist).unwrap();
+        assert!(aircraft.run_to_completion().unwrap() < unassisted);
+        assert!(aircraft.get_occupier(1, 1).unwrap().is_crew());
+        assert!(aircraft.add_crew(1, 1, Duty::Assist).is_err());
+        assert!(aircraft.add_crew(0, 3, Duty::Assist).is_err());
+        assert_eq!(aircraft.add_crew(1, 2, Duty::Stand((0, 2))),
+                   Err("Crew duties must stay on aisles or open floor"));
+        assert_eq!(aircraft.add_crew(1, 2, Duty::Patrol((1, 2), (1, 40))),
+                   Err("Crew duties must stay inside the aircraft"));
+        assert!(aircraft.get_occupier(1, 2).is_none());
+
+        // Passengers squeeze past crew standing in their way, and leave with
+        // the crew still aboard
+        let mut aircraft = build(false);
+        aircraft.add_crew(1, 3, Duty::Stand((1, 3))).unwrap();
+        assert!(aircraft.run_to_completion().is_ok());
+        let mut aircraft = build(false);
+        aircraft.add_crew(1, 3, Duty::Stand((1, 3))).unwrap();
+        aircraft.deplane(Deplaning::FreeForAll);
+        assert!(aircraft.run_to_completion().is_ok());
+        assert!(aircraft.get_occupier(1, 3).unwrap().is_crew());
+
+        // Patrols turn around at either end
+        let mut aircraft = build(false);
+        aircraft.add_crew(1, 0, Duty::Patrol((1, 0), (1, 2))).unwrap();
+        aircraft.update();
+        aircraft.update();
+        assert!(aircraft.get_occupier(1, 2).is_some_and(Person::is_crew));
+        aircraft.update();
+        aircraft.update();
+        assert!(aircraft.get_occupier(1, 0).is_some_and(Person::is_crew));
+    }
+
+    #[test]
+    fn wrong_seat() {
+        // A window and an aisle seat on each row, with one passenger taking
+        // the window seat belonging to another
+        let board = |owner_first: bool| {
+            let mut aircraft = Aircraft::new(3, 4);
+            for y in 0..4 {
+                aircraft.set_tile(0, y, Variant::Seat);
+                aircraft.set_tile(1, y, Variant::Seat);
+            }
+            aircraft.set_tile(2, 3, Variant::Entrance);
+            let mut owner = Person::new("OWNER");
+            owner.target_seat(0, 1);
+            let mut mistaken = Person::new("MISTAKEN");
+            mistaken.target_seat(1, 1);
+            mistaken.mistake_seat(0, 1);
+            // Passengers board from the back of the list
+            if owner_first {
+                aircraft.add_passenger(mistaken);
+                aircraft.add_passenger(owner);
+            } else {
+                aircraft.add_passenger(owner);
+                aircraft.add_passenger(mistaken);
+            }
+            aircraft
+        };
+
+        for owner_first in [false, true] {
+            let mut aircraft = board(owner_first);
+            assert!(aircraft.run_to_completion().is_ok());
+            assert_eq!(aircraft.get_occupier(0, 1).unwrap().get_name(),
+                       "OWNER");
+            assert_eq!(aircraft.get_occupier(1, 1).unwrap().get_name(),
+                       "MISTAKEN");
+            let conflicts = aircraft.get_conflicts();
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!((conflicts[0].seat, conflicts[0].own_seat),
+                       ((0, 1), (1, 1)));
+            assert!(conflicts[0].resolved.unwrap() > conflicts[0].found);
+        }
+
+        // Boarding isn't complete while somebody sits in the wrong seat
+        let mut aircraft = board(false);
+        while aircraft.get_occupier(0, 1).is_none() {
+            aircraft.update();
+        }
+        aircraft.layout[1][1].occupy(Person::new("OWNER")).unwrap();
+        assert!(!aircraft.is_complete());
+    }
+
+    #[test]
+    fn overtaking() {
+        // A passenger waits in the aisle behind somebody moving from an aisle
+        // chair into their seat, while another passenger heads for the row
+        // the first is waiting on
+        let board = |overtaking: Overtaking, width: u16| {
+            let mut aircraft = Aircraft::new(2, 7);
+            for y in 0..6 {
+                aircraft.set_tile(0, y, Variant::Seat);
+                aircraft.set_aisle_width(y, Some(width)).unwrap();
+            }
+            aircraft.set_tile(1, 6, Variant::Entrance);
+            aircraft.set_overtaking(overtaking);
+            // Passengers board from the back of the list
+            for (y, chair) in [(3, false), (0, false), (1, true)] {
+                let mut person = Person::new("DEFAULT");
+                person.target_seat(0, y);
+                person.set_aisle_chair(chair);
+                aircraft.add_passenger(person);
+            }
+            aircraft
+        };
+        // The step at which the passenger sitting on row 3 sat down
+        let seated = |mut aircraft: Aircraft| {
+            aircraft.run_to_completion().unwrap();
+            aircraft.seated_at[0].unwrap()
+        };
+
+        let single_file = seated(board(Overtaking::Wide, 1));
+        assert!(seated(board(Overtaking::Wide, 2)) < single_file);
+        assert_eq!(seated(board(Overtaking::Never, 2)), single_file);
+
+        let mut aircraft = board(Overtaking::Wide, 1);
+        assert!(aircraft.set_aisle_width(7, Some(2)).is_err());
+    }
+
+    #[test]
+    fn yielding() {
+        // A passenger waits in the aisle behind somebody moving from an aisle
+        // chair into their seat, in the way of another passenger heading for
+        // the row they are waiting on
+        let board = |yield_steps: Option<u8>| {
+            let mut aircraft = Aircraft::new(3, 7);
+            for y in 0..6 {
+                aircraft.set_tile(0, y, Variant::Seat);
+                aircraft.set_tile(2, y, Variant::Seat);
+            }
+            aircraft.set_tile(1, 6, Variant::Entrance);
+            aircraft.set_yield_steps(yield_steps);
+            // Passengers board from the back of the list
+            for (y, chair) in [(3, false), (0, false), (1, true)] {
+                let mut person = Person::new("DEFAULT");
+                person.target_seat(0, y);
+                person.set_aisle_chair(chair);
+                aircraft.add_passenger(person);
+            }
+            aircraft
+        };
+
+        let mut aircraft = board(Some(2));
+        while aircraft.get_occupier(2, 3).is_none()
+            && aircraft.get_step() < 20 {
+            aircraft.update();
+        }
+        // They step into the seat across the aisle from the other's, who
+        // takes their place
+        assert!(aircraft.get_occupier(2, 3).is_some_and(Person::is_aside));
+        assert_eq!(aircraft.get_occupier(1, 3).and_then(Person::get_seat),
+                   Some((0, 3)));
+        aircraft.run_to_completion().unwrap();
+        let yielded = aircraft.seated_at[0].unwrap();
+        // Having stepped back out, they no longer remember standing aside
+        assert_eq!(aircraft.get_occupier(0, 0).and_then(Person::get_aside),
+                   None);
+
+        let mut aircraft = board(None);
+        aircraft.run_to_completion().unwrap();
+        assert!(yielded < aircraft.seated_at[0].unwrap());
+    }
+
+    #[test]
+    fn policy() {
+        // Stays put whatever happens
+        struct Stay;
+        impl AgentPolicy for Stay {
+            fn decide(&self, _: &Neighbourhood, _: &Agent) -> Behaviour {
+                Behaviour::Wait
+            }
+        }
+        // Tries to walk through the side of the aircraft
+        struct Wander;
+        impl AgentPolicy for Wander {
+            fn decide(&self, _: &Neighbourhood, _: &Agent) -> Behaviour {
+                Behaviour::Move_West
+            }
+        }
+
+        let board = |policy: Option<Arc<dyn AgentPolicy>>| {
+            let mut aircraft = Aircraft::new(2, 4);
+            for y in 0..3 {
+                aircraft.set_tile(0, y, Variant::Seat);
+            }
+            aircraft.set_tile(0, 3, Variant::None);
+            aircraft.set_tile(1, 3, Variant::Entrance);
+            for y in 0..3 {
+                let mut person = Person::new("DEFAULT");
+                person.target_seat(0, y);
+                if let Some(policy) = &policy {
+                    person.set_policy(policy.clone());
+                }
+                aircraft.add_passenger(person);
+            }
+            aircraft
+        };
+
+        assert!(board(None).run_to_completion().is_ok());
+        assert!(board(Some(Arc::new(Stay))).run_to_completion().is_err());
+
+        // Moves through walls or off the aircraft are treated as waiting
+        let mut aircraft = board(Some(Arc::new(Wander)));
+        for _ in 0..5 {
+            aircraft.update();
+        }
+        assert!(aircraft.get_occupier(1, 3).is_some());
+        assert!(!aircraft.check_if_occupied(0, 3));
+
+        // Anybody without a policy of their own follows the aircraft's
+        let mut aircraft = board(None);
+        aircraft.set_policy(Arc::new(Stay));
+        assert!(aircraft.run_to_completion().is_err());
+        let mut aircraft = board(Some(Arc::new(Greedy)));
+        aircraft.set_policy(Arc::new(Stay));
+        assert!(aircraft.run_to_completion().is_ok());
+    }
 }
@@ -4,7 +4,13 @@
 //! to determine this passenger's optimal move.
 
 use std::fmt;
+use std::sync::Arc;
 // use super::tile::{Variant, Tile, SimpleTile};
+use super::crew::{Crew, Duty};
+use super::policy::AgentPolicy;
+
+pub const TRANSFER_STEPS: u8 = 10; // The steps taken to move from an aisle
+                                   // chair into a seat
 
 /// This enum represents a possible move for a pasenger during an update.
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -13,18 +19,55 @@ pub enum Behaviour {
     Move_South,
     Move_East,
     Move_West,
-    Stow, // stow baggage
+    Stow, // stow baggage, or collect it when deplaning
     Wait,
 }
 
 /// A single passenger
 ///
-/// `seat` represents a passenger's assigned seat, and baggage stores whether
-/// the passenger still has their carry-on luggage or not.
+/// `seat` represents a passenger's assigned seat, or `seat_label` its label
+/// until the passenger is added to an aircraft, and baggage stores whether
+/// the passenger still has their carry-on luggage or not. `arrival` is the
+/// earliest step at which the passenger is at the door and able to board,
+/// `party` identifies the group of passengers they are travelling with, if
+/// any, and `zone` is the boarding zone or deplaning wave they belong to.
+///
+/// Passengers with reduced mobility are pushed down the aisle in an
+/// `aisle_chair`, whose back is on the tile `chair_back` behind them, and
+/// `transfer` counts the steps they still need to move from it into their
+/// seat once they reach their row.
+///
+/// Passengers who misread their boarding pass head for `wrong_seat`, or the
+/// seat labelled `wrong_label`, instead of their own until they find its
+/// rightful owner there or are displaced by them.
+///
+/// Passengers who step out of the aisle into an empty seat to let somebody by
+/// stay there for another `aside_steps` steps before stepping back out to the
+/// tile they stepped `aside` from.
+///
+/// Crew members are also represented by a `Person`, with a `crew` duty in
+/// place of a seat.
+///
+/// Anybody given their own `policy` decides how to move by it rather than by
+/// their aircraft's.
+#[derive(Clone)]
 pub struct Person {
     name: String,
     seat: Option<(u16, u16)>,
+    seat_label: Option<String>,
     baggage: bool,
+    arrival: u16,
+    party: Option<u16>,
+    zone: u16,
+    aisle_chair: bool,
+    chair_back: Option<(u16, u16)>,
+    transfer: u8,
+    wrong_seat: Option<(u16, u16)>,
+    wrong_label: Option<String>,
+    aside: Option<(u16, u16)>,
+    aside_steps: u8,
+    crew: Option<Crew>,
+    policy: Option<Arc<dyn AgentPolicy>>,
 }
 
 impl Person {
@@ -35,6 +78,7 @@ impl Person {
     /// # Examples
     ///
     /// ```
+    /// # use aircraft_sim::aircraft::person::Person;
     /// let person = Person::new("DEFAULT");
     ///
     /// assert_eq!(person.get_name(), "DEFAULT");
@@ -43,7 +87,28 @@ impl Person {
         Person {
             name: n.to_string(),
             seat: None,
+            seat_label: None,
             baggage: false,
+            arrival: 0,
+            party: None,
+            zone: 0,
+            aisle_chair: false,
+            chair_back: None,
+            transfer: 0,
+            wrong_seat: None,
+            wrong_label: None,
+            aside: None,
+            aside_steps: 0,
+            crew: None,
+            policy: None,
+        }
+    }
+
+    /// Constructor for a crew member carrying out `duty`.
+    pub fn crew(duty: Duty) -> Person {
+        Person {
+            crew: Some(Crew::new(duty)),
+            ..Person::new("CREW")
         }
     }
     
@@ -52,16 +117,23 @@ impl Person {
     /// # Examples
     ///
     /// ```
-    /// let person = Person::new("DEFAULT");
+    /// # use aircraft_sim::aircraft::person::Person;
+    /// let mut person = Person::new("DEFAULT");
     ///
     /// person.target_seat(2,2);
     ///
-    /// assert_eq!(person.get_seat(), (2,2));
+    /// assert_eq!(person.get_seat(), Some((2,2)));
     /// ```
     pub fn target_seat(&mut self, x: u16, y: u16) {
         self.seat = Some((x, y));
     }
 
+    /// Sets a passenger's target seat by its label, such as "23C", to be looked
+    /// up when they are added to an aircraft.
+    pub fn target_label(&mut self, label: &str) {
+        self.seat_label = Some(label.to_string());
+    }
+
     /// Sets a passenger's `baggage` value to false.
     pub fn remove_baggage(&mut self) {
         if self.baggage {
@@ -72,6 +144,19 @@ impl Person {
         }
     }
 
+    /// Spends a step stowing or collecting baggage, or once that's done,
+    /// moving from an aisle chair into a seat.
+    pub fn stow(&mut self) {
+        if self.baggage {
+            self.remove_baggage();
+        } else if self.transfer > 0 {
+            log::info!("Transferring from aisle chair");
+            self.transfer -= 1;
+        } else {
+            log::warn!("Invalid call to `stow()`");
+        }
+    }
+
     pub fn has_baggage(&self) -> bool {
         self.baggage
     }
@@ -84,6 +169,50 @@ impl Person {
         self.seat
     }
 
+    pub fn get_seat_label(&self) -> Option<&str> {
+        self.seat_label.as_deref()
+    }
+
+    /// Returns the seat a passenger is heading for, which is somebody else's
+    /// if they've taken the wrong one.
+    pub fn get_target(&self) -> Option<(u16, u16)> {
+        self.wrong_seat.or(self.seat)
+    }
+
+    pub fn get_wrong_seat(&self) -> Option<(u16, u16)> {
+        self.wrong_seat
+    }
+
+    pub fn get_wrong_label(&self) -> Option<&str> {
+        self.wrong_label.as_deref()
+    }
+
+    /// Sends a passenger to the seat at `x`,`y` by mistake.
+    pub fn mistake_seat(&mut self, x: u16, y: u16) {
+        self.wrong_seat = Some((x, y));
+    }
+
+    /// Sends a passenger to the seat with the given label by mistake, to be
+    /// looked up when they are added to an aircraft.
+    pub fn mistake_label(&mut self, label: &str) {
+        self.wrong_label = Some(label.to_string());
+    }
+
+    /// Checks whether `seat` is a passenger's own seat, and the one they are
+    /// heading for.
+    pub fn belongs_in(&self, seat: (u16, u16)) -> bool {
+        self.seat == Some(seat) && self.wrong_seat.is_none()
+    }
+
+    /// Sends a passenger who took the wrong seat on to their own.
+    pub fn correct_seat(&mut self) {
+        if self.wrong_seat.is_some() {
+            log::info!("Passenger found they were in the wrong seat");
+        }
+        self.wrong_seat = None;
+        self.wrong_label = None;
+    }
+
     pub fn set_name(&mut self, n: &str) {
         self.name = n.to_string();
     }
@@ -91,6 +220,111 @@ impl Person {
     pub fn set_baggage(&mut self, t: bool) {
         self.baggage = t;
     }
+
+    pub fn get_arrival(&self) -> u16 {
+        self.arrival
+    }
+
+    /// Delays a passenger so that they can't board before step `step`.
+    pub fn set_arrival(&mut self, step: u16) {
+        self.arrival = step;
+    }
+
+    pub fn get_party(&self) -> Option<u16> {
+        self.party
+    }
+
+    /// Marks a passenger as travelling with the other members of `party`.
+    pub fn set_party(&mut self, party: Option<u16>) {
+        self.party = party;
+    }
+
+    pub fn get_zone(&self) -> u16 {
+        self.zone
+    }
+
+    pub fn set_zone(&mut self, zone: u16) {
+        self.zone = zone;
+    }
+
+    pub fn uses_aisle_chair(&self) -> bool {
+        self.aisle_chair
+    }
+
+    /// Seats a passenger in an aisle chair to board, leaving them a full
+    /// transfer into their seat.
+    pub fn set_aisle_chair(&mut self, t: bool) {
+        self.aisle_chair = t;
+        self.transfer = if t { TRANSFER_STEPS } else { 0 };
+    }
+
+    pub fn get_chair_back(&self) -> Option<(u16, u16)> {
+        self.chair_back
+    }
+
+    /// Moves the back of a passenger's aisle chair onto the tile `back`, or
+    /// with `None`, takes it away, returning the tile it was on.
+    pub fn move_chair_back(&mut self, back: Option<(u16, u16)>)
+        -> Option<(u16, u16)> {
+        std::mem::replace(&mut self.chair_back, back)
+    }
+
+    pub fn is_crew(&self) -> bool {
+        self.crew.is_some()
+    }
+
+    pub fn get_crew(&self) -> Option<&Crew> {
+        self.crew.as_ref()
+    }
+
+    pub fn get_crew_as_mut(&mut self) -> Option<&mut Crew> {
+        self.crew.as_mut()
+    }
+
+    pub fn get_policy(&self) -> Option<&Arc<dyn AgentPolicy>> {
+        self.policy.as_ref()
+    }
+
+    /// Has a passenger decide how to move by `policy` rather than by their
+    /// aircraft's.
+    pub fn set_policy(&mut self, policy: Arc<dyn AgentPolicy>) {
+        self.policy = Some(policy);
+    }
+
+    /// Checks whether a passenger still has to move from their aisle chair
+    /// into their seat.
+    pub fn is_transferring(&self) -> bool {
+        self.transfer > 0
+    }
+
+    /// Has a passenger who has just stepped into an empty seat from the tile
+    /// at `x`,`y` stay there for `steps` steps.
+    pub fn stand_aside(&mut self, x: u16, y: u16, steps: u8) {
+        self.aside = Some((x, y));
+        self.aside_steps = steps;
+    }
+
+    /// Spends a step standing aside.
+    pub fn wait_aside(&mut self) {
+        self.aside_steps = self.aside_steps.saturating_sub(1);
+    }
+
+    /// Checks whether a passenger is still standing aside to let somebody by.
+    pub fn is_aside(&self) -> bool {
+        self.aside_steps > 0
+    }
+
+    /// Returns the tile a passenger last stepped aside from.
+    pub fn get_aside(&self) -> Option<(u16, u16)> {
+        self.aside
+    }
+
+    /// Forgets where a passenger stood aside once they step back out of the
+    /// row.
+    pub fn step_out(&mut self) {
+        self.aside = None;
+        self.aside_steps = 0;
+    }
 }
 
 /// Defines how Rust should display this object if it is passed to stdout via a
@@ -100,6 +334,9 @@ impl fmt::Debug for Person {
         f.debug_struct("Person")
             .field("name", &self.name)
             .field("seat", &self.seat)
+            .field("party", &self.party)
+            .field("aisle_chair", &self.aisle_chair)
+            .field("wrong_seat", &self.wrong_seat)
             .finish()
     }
 }
@@ -132,4 +369,51 @@ mod tests {
     //     assert_eq!(person.update((0, 1), grid), Behaviour::Move_East, "Didn't move as expected");
     //     assert_eq!(person.update((1, 0), grid), Behaviour::Move_South, "Didn't move as expected");
     // }
+
+    #[test]
+    fn aisle_chair() {
+        let mut person = Person::new("Dave");
+        person.set_baggage(true);
+        person.set_aisle_chair(true);
+        assert!(person.uses_aisle_chair());
+
+        // Baggage is stowed before moving into the seat
+        person.stow();
+        assert!(!person.has_baggage());
+        for _ in 0..TRANSFER_STEPS {
+            assert!(person.is_transferring());
+            person.stow();
+        }
+        assert!(!person.is_transferring());
+    }
+
+    #[test]
+    fn wrong_seat() {
+        let mut person = Person::new("Dave");
+        person.target_seat(2, 3);
+        assert_eq!(person.get_target(), Some((2, 3)));
+
+        person.mistake_seat(1, 3);
+        assert_eq!(person.get_target(), Some((1, 3)));
+        assert!(!person.belongs_in((2, 3)) && !person.belongs_in((1, 3)));
+        person.correct_seat();
+        assert_eq!(person.get_target(), Some((2, 3)));
+        assert!(person.belongs_in((2, 3)));
+        assert_eq!(person.get_wrong_seat(), None);
+    }
+
+    #[test]
+    fn stand_aside() {
+        let mut person = Person::new("Dave");
+        assert!(!person.is_aside());
+        person.stand_aside(3, 4, 2);
+        assert_eq!(person.get_aside(), Some((3, 4)));
+        person.wait_aside();
+        assert!(person.is_aside());
+        person.wait_aside();
+        person.wait_aside();
+        assert!(!person.is_aside());
+        person.step_out();
+        assert_eq!(person.get_aside(), None);
+    }
 }
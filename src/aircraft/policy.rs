@@ -0,0 +1,320 @@
+//! Holds the policies passengers and crew follow to decide how to move.
+//!
+//! Each step, an agent's `AgentPolicy` is shown the tiles around them through
+//! a `Neighbourhood`, along with where they are heading, and returns a
+//! `Behaviour`. Anybody not given a policy of their own follows their
+//! aircraft's, which is `Greedy` unless another is set, so other movement
+//! models can be plugged in without changing how the aircraft is updated.
+//!
+//! Policies registered by name with `register_policy` can also be chosen from
+//! passenger lists, the command line and Python. `Greedy` is registered as
+//! "greedy".
+
+use std::sync::{Arc, LazyLock, RwLock};
+
+use super::{Aircraft, Mode};
+use super::person::{Behaviour, Person};
+use super::tile::Tile;
+
+/// Decides how an agent moves each step.
+///
+/// Policies can be shared between passengers and by simulations running on
+/// other threads, so must be `Send` and `Sync`. The aircraft keeps anybody
+/// from walking through walls, off the aircraft or into a tile that already
+/// holds two people, treating such moves as waiting, but who may squeeze past
+/// whom is left to the policy.
+pub trait AgentPolicy: Send + Sync {
+    /// Chooses what `agent` does this step, given the tiles around them.
+    fn decide(&self, view: &Neighbourhood, agent: &Agent) -> Behaviour;
+}
+
+/// Policies paired with the names they were registered under.
+type Registry = Vec<(String, Arc<dyn AgentPolicy>)>;
+
+/// Every policy that can be chosen by name.
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(|| {
+    let greedy: Arc<dyn AgentPolicy> = Arc::new(Greedy);
+    RwLock::new(vec![("greedy".to_string(), greedy)])
+});
+
+/// Makes `policy` available as `name`, replacing any policy already registered
+/// under that name.
+pub fn register_policy(name: &str, policy: Arc<dyn AgentPolicy>) {
+    let mut registry = REGISTRY.write().unwrap();
+    match registry.iter_mut().find(|(n, _)| n == name) {
+        Some(entry) => entry.1 = policy,
+        None => registry.push((name.to_string(), policy)),
+    }
+}
+
+/// Finds the policy registered as `name`.
+pub fn named_policy(name: &str) -> Option<Arc<dyn AgentPolicy>> {
+    REGISTRY.read().unwrap().iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, policy)| policy.clone())
+}
+
+/// Finds the name `policy` was registered under, if it was.
+pub fn policy_name(policy: &Arc<dyn AgentPolicy>) -> Option<String> {
+    REGISTRY.read().unwrap().iter()
+        .find(|(_, p)| Arc::ptr_eq(p, policy))
+        .map(|(name, _)| name.clone())
+}
+
+/// What an agent knows about themselves when deciding how to move.
+///
+/// `target` is the tile they are heading for, which is their seat or an exit
+/// for passengers, and `stow_row` the row they need to stop on to deal with
+/// their baggage or move from an aisle chair into their seat, if any.
+pub struct Agent<'a> {
+    pub person: &'a Person,
+    pub target: (u16, u16),
+    pub stow_row: Option<u16>,
+}
+
+/// A read-only view of the aircraft around an agent.
+///
+/// Tiles are found by their offset from the agent, with `x` increasing to the
+/// east and `y` to the south.
+pub struct Neighbourhood<'a> {
+    aircraft: &'a Aircraft,
+    position: (usize, usize),
+}
+
+impl<'a> Neighbourhood<'a> {
+    /// Constructor for the view of an agent at `x`,`y`.
+    pub(crate) fn new(aircraft: &'a Aircraft, x: usize, y: usize)
+        -> Neighbourhood<'a> {
+        Neighbourhood {
+            aircraft,
+            position: (x, y),
+        }
+    }
+
+    pub fn get_position(&self) -> (u16, u16) {
+        (self.position.0 as u16, self.position.1 as u16)
+    }
+
+    pub fn get_mode(&self) -> Mode {
+        self.aircraft.mode
+    }
+
+    /// Returns the tile `dx`,`dy` away from the agent, or `None` if it is off
+    /// the aircraft.
+    pub fn get_tile(&self, dx: i32, dy: i32) -> Option<&'a Tile> {
+        let (x, y) = self.offset(dx, dy)?;
+        Some(&self.aircraft.layout[x][y])
+    }
+
+    /// Checks whether the agent, in an aisle chair if `chair` is set, is
+    /// allowed to squeeze past whoever is on the tile `dx`,`dy` away under the
+    /// aircraft's overtaking rules.
+    pub fn can_pass(&self, dx: i32, dy: i32, chair: bool) -> bool {
+        self.offset(dx, dy)
+            .is_some_and(|(x, y)| self.aircraft.can_pass(x, y, chair))
+    }
+
+    fn offset(&self, dx: i32, dy: i32) -> Option<(usize, usize)> {
+        let x = self.position.0 as i32 + dx;
+        let y = self.position.1 as i32 + dy;
+        let (width, height) = self.aircraft.size;
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            None
+        } else {
+            Some((x as usize, y as usize))
+        }
+    }
+}
+
+/// The default policy, which takes whichever step brings an agent closest to
+/// their target.
+///
+/// In an aisle or on open floor, agents stop to stow on their `stow_row`, only
+/// step sideways onto their target's row and only squeeze past somebody where
+/// the overtaking rules allow. In a seat they only move along the row, and
+/// can't squeeze past somebody standing in the aisle unless they stood aside
+/// in this row to let them into it. Nobody squeezes past a passenger in an
+/// aisle chair, nor can they squeeze past anybody else, until they are in
+/// their seat.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Greedy;
+
+impl AgentPolicy for Greedy {
+    fn decide(&self, view: &Neighbourhood, agent: &Agent) -> Behaviour {
+        let mut current_move = (Behaviour::Wait, u32::MAX);
+        let (i, j) = view.get_position();
+        let target = agent.target;
+        let chair = agent.person.uses_aisle_chair();
+        // Calculates the distance from a potential tile to the target tile
+        let distance = |dx: i32, dy: i32| {
+            ((target.0 as i32 - (i as i32 + dx)).abs()
+             + (target.1 as i32 - (j as i32 + dy)).abs()) as u32
+        };
+        let variant = match view.get_tile(0, 0) {
+            Some(tile) => tile.get_variant(),
+            None => return Behaviour::Wait,
+        };
+
+        if variant.is_walkable() {
+            if agent.stow_row == Some(j) {
+                return Behaviour::Stow;
+            }
+            // Decide movement based on other tile
+            for (potential_move, (dx, dy)) in [
+                (Behaviour::Wait, (0, 0)),
+                (Behaviour::Move_North, (0, -1)),
+                (Behaviour::Move_South, (0, 1)),
+                (Behaviour::Move_East, (1, 0)),
+                (Behaviour::Move_West, (-1, 0)),
+            ] {
+                let new_distance = distance(dx, dy);
+                let dest = match view.get_tile(dx, dy) {
+                    Some(dest) if new_distance < current_move.1 => dest,
+                    _ => {
+                        log::debug!("REJECTED: {:?} x {}",
+                                    potential_move, new_distance);
+                        continue;
+                    },
+                };
+
+                // Check whether the movement would place this passenger on the
+                // wrong row and, if so, wait instead.
+                if dx != 0 && j as i32 + dy != target.1 as i32 {
+                    continue;
+                }
+                // Check whether the tile can be entered and is free
+                if !dest.get_variant().is_passable() || dest.has_chair() {
+                    log::debug!("REJECTED: {:?} x {}",
+                                potential_move, new_distance);
+                } else if !dest.is_occupied() || (dx, dy) == (0, 0) {
+                    current_move = (potential_move, new_distance);
+                    log::debug!("NEW MOVE: {:?} x {}",
+                                current_move.0, current_move.1);
+                } else if !dest.is_allowing() && view.can_pass(dx, dy, chair) {
+                    // This movement is possible even though the position is
+                    // occupied, as two passengers can temporarily share a seat
+                    // or open floor albeit with a delay.
+                    current_move = (potential_move, new_distance);
+                    log::debug!("NEW MOVE: {:?} x {}",
+                                current_move.0, current_move.1);
+                } else {
+                    log::debug!("No room to get past");
+                    log::debug!("REJECTED: {:?} x {}",
+                                potential_move, new_distance);
+                }
+            }
+        } else if variant.is_seating() {
+            // Decide movement based on other tile
+            for (potential_move, dx) in [
+                (Behaviour::Wait, 0),
+                (Behaviour::Move_East, 1),
+                (Behaviour::Move_West, -1),
+            ] {
+                let new_distance = distance(dx, 0);
+
+                // Passengers can't leave the aircraft's bounds or walk through
+                // walls, and those leaving their row can't squeeze past
+                // somebody already standing in the aisle, unless they stood
+                // aside in this row to let them into it.
+                let blocked = match view.get_tile(dx, 0) {
+                    None => true,
+                    Some(dest) => {
+                        let aside = view.get_mode() == Mode::Boarding
+                            && target.1 != j
+                            && dest.get_occupier().is_some_and(|p| {
+                                !p.is_crew() && p.get_target()
+                                    .is_some_and(|seat| seat.1 == j)
+                            });
+                        potential_move != Behaviour::Wait
+                            && (!dest.get_variant().is_passable()
+                                || dest.has_chair()
+                                || (dest.is_occupied() && !aside
+                                    && !view.can_pass(dx, 0, chair)))
+                    },
+                };
+
+                if new_distance < current_move.1 && !blocked {
+                    current_move = (potential_move, new_distance);
+                    log::debug!("NEW MOVE: {:?} x {}",
+                                current_move.0, current_move.1);
+                } else {
+                    log::debug!("REJECTED: {:?} x {}",
+                                potential_move, new_distance);
+                }
+            }
+        }
+
+        current_move.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tile::Variant;
+
+    #[test]
+    fn neighbourhood() {
+        let mut aircraft = Aircraft::new(3, 4);
+        aircraft.set_tile(0, 1, Variant::Seat);
+        let view = Neighbourhood::new(&aircraft, 1, 1);
+        assert_eq!(view.get_position(), (1, 1));
+        assert_eq!(view.get_tile(-1, 0).map(Tile::get_variant),
+                   Some(Variant::Seat));
+        assert!(view.get_tile(-2, 0).is_none());
+        assert!(view.get_tile(0, 3).is_none());
+        assert!(view.get_tile(1, 2).is_some());
+    }
+
+    #[test]
+    fn greedy() {
+        let mut aircraft = Aircraft::new(3, 4);
+        for y in 0..4 {
+            aircraft.set_tile(0, y, Variant::Seat);
+        }
+        let mut person = Person::new("DEFAULT");
+        person.target_seat(0, 0);
+        let agent = |stow_row| Agent {
+            person: &person,
+            target: (0, 0),
+            stow_row,
+        };
+
+        // Passengers walk up the aisle, only stepping sideways onto their row
+        let view = Neighbourhood::new(&aircraft, 1, 2);
+        assert_eq!(Greedy.decide(&view, &agent(None)), Behaviour::Move_North);
+        let view = Neighbourhood::new(&aircraft, 1, 0);
+        assert_eq!(Greedy.decide(&view, &agent(None)), Behaviour::Move_West);
+        assert_eq!(Greedy.decide(&view, &agent(Some(0))), Behaviour::Stow);
+
+        // Nobody squeezes past somebody standing in a narrow aisle
+        aircraft.layout[1][1].occupy(Person::new("DEFAULT")).unwrap();
+        let view = Neighbourhood::new(&aircraft, 1, 2);
+        assert_eq!(Greedy.decide(&view, &agent(None)), Behaviour::Wait);
+    }
+
+    #[test]
+    fn registry() {
+        struct Stay;
+        impl AgentPolicy for Stay {
+            fn decide(&self, _: &Neighbourhood, _: &Agent) -> Behaviour {
+                Behaviour::Wait
+            }
+        }
+
+        let greedy = named_policy("greedy").unwrap();
+        assert_eq!(policy_name(&greedy).as_deref(), Some("greedy"));
+        assert!(named_policy("registry_test").is_none());
+
+        let stay: Arc<dyn AgentPolicy> = Arc::new(Stay);
+        register_policy("registry_test", stay.clone());
+        let found = named_policy("registry_test").unwrap();
+        assert!(Arc::ptr_eq(&found, &stay));
+        assert_eq!(policy_name(&stay).as_deref(), Some("registry_test"));
+
+        // Registering a name again replaces the policy it names
+        register_policy("registry_test", Arc::new(Greedy));
+        assert_eq!(policy_name(&stay), None);
+        assert!(!Arc::ptr_eq(&named_policy("registry_test").unwrap(), &stay));
+    }
+}
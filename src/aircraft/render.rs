@@ -0,0 +1,257 @@
+//! Draws an `Aircraft` as text, for debugging, tests, logs and the frontends.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use super::Aircraft;
+use super::cabin::SeatMap;
+use super::tile::{Tile, Variant, VARIANTS};
+
+/// The characters used to draw each kind of tile and passenger.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Glyphs {
+    pub aisle: char,
+    pub seat: char,
+    pub entrance: char,
+    pub none: char,
+    pub galley: char,
+    pub lavatory: char,
+    pub bulkhead: char,
+    pub exit_row: char,
+    pub cross_aisle: char,
+    pub seat_span: char,
+    /// A passenger who has stowed their baggage, or never had any.
+    pub passenger: char,
+    /// A passenger still carrying baggage.
+    pub baggage: char,
+    /// A tile holding two passengers, one making their way past the other.
+    pub passer: char,
+    /// The back of an aisle chair, behind the passenger using it.
+    pub chair: char,
+    /// A member of the cabin crew.
+    pub crew: char,
+}
+
+impl Default for Glyphs {
+    fn default() -> Glyphs {
+        Glyphs {
+            aisle: '.',
+            seat: '#',
+            entrance: 'E',
+            none: ' ',
+            galley: 'G',
+            lavatory: 'L',
+            bulkhead: '=',
+            exit_row: 'X',
+            cross_aisle: '-',
+            seat_span: '~',
+            passenger: 'o',
+            baggage: '@',
+            passer: '%',
+            chair: '+',
+            crew: 'C',
+        }
+    }
+}
+
+impl Glyphs {
+    /// Returns the character drawn for a tile and whoever is on it.
+    pub fn tile(&self, tile: &Tile) -> char {
+        if tile.get_passer().is_some() {
+            return self.passer;
+        }
+        match tile.get_occupier() {
+            Some(person) if person.is_crew() => self.crew,
+            Some(person) if person.has_baggage() => self.baggage,
+            Some(_) => self.passenger,
+            None if tile.has_chair() => self.chair,
+            None => self.empty(tile.get_variant()),
+        }
+    }
+
+    /// Returns the tile variant drawn as `c`, if any.
+    pub fn variant(&self, c: char) -> Option<Variant> {
+        VARIANTS.iter()
+            .find(|v| self.empty(**v) == c)
+            .copied()
+    }
+
+    /// Returns the character drawn for an empty tile of the given variant.
+    pub fn empty(&self, variant: Variant) -> char {
+        match variant {
+            Variant::Aisle => self.aisle,
+            Variant::Seat => self.seat,
+            Variant::Entrance => self.entrance,
+            Variant::None => self.none,
+            Variant::Galley => self.galley,
+            Variant::Lavatory => self.lavatory,
+            Variant::Bulkhead => self.bulkhead,
+            Variant::ExitRow => self.exit_row,
+            Variant::CrossAisle => self.cross_aisle,
+            Variant::SeatSpan => self.seat_span,
+        }
+    }
+}
+
+/// Options for `Aircraft::render`.
+///
+/// `axes` labels every column and row with its coordinate. Column labels
+/// wider than one digit are written downwards, one digit per line. With
+/// `labels` set, the axes instead give each row's number and each column's
+/// letter, as in seat labels such as `23C`, leaving out any without one.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub glyphs: Glyphs,
+    pub axes: bool,
+    pub labels: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            glyphs: Glyphs::default(),
+            axes: true,
+            labels: false,
+        }
+    }
+}
+
+impl Aircraft {
+    /// Draws the aircraft's layout and passengers to `out`, one row per line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use aircraft_sim::aircraft::Aircraft;
+    /// # use aircraft_sim::aircraft::render::RenderOptions;
+    /// let mut out = Vec::<u8>::new();
+    /// Aircraft::new(2,1).render(&mut out, &RenderOptions::default()).unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(out).unwrap(), "  01\n0 ..\n");
+    /// ```
+    pub fn render<W: Write>(&self, out: &mut W, options: &RenderOptions)
+        -> io::Result<()> {
+        let (size_x, size_y) = self.size;
+        let seat_map = if options.labels {
+            Some(SeatMap::new(self))
+        } else {
+            None
+        };
+        let row_label = |y: u16| match &seat_map {
+            Some(seat_map) => seat_map.row(y)
+                .map_or(String::new(), |n| n.to_string()),
+            None => y.to_string(),
+        };
+        let column_label = |x: u16| match &seat_map {
+            Some(seat_map) => seat_map.column(x)
+                .map_or(String::new(), |c| c.to_string()),
+            None => x.to_string(),
+        };
+        let row_width = (0..size_y).map(|y| row_label(y).len())
+            .max()
+            .unwrap_or(1);
+
+        if options.axes {
+            let column_width = (0..size_x).map(|x| column_label(x).len())
+                .max()
+                .unwrap_or(1);
+            for line in 0..column_width {
+                write!(out, "{:width$} ", "", width = row_width)?;
+                for x in 0..size_x {
+                    // Pad each label on the left so that units line up.
+                    let label = format!("{:>width$}", column_label(x),
+                                        width = column_width);
+                    write!(out, "{}", &label[line..line + 1])?;
+                }
+                writeln!(out)?;
+            }
+        }
+
+        for y in 0..size_y {
+            if options.axes {
+                write!(out, "{:>width$} ", row_label(y), width = row_width)?;
+            }
+            for column in &self.layout {
+                write!(out, "{}", options.glyphs.tile(&column[y as usize]))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Aircraft {
+    /// Draws the aircraft using the default `RenderOptions`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = Vec::<u8>::new();
+        self.render(&mut out, &RenderOptions::default())
+            .map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::person::Person;
+    use super::super::crew::Duty;
+
+    #[test]
+    fn axis_labels() {
+        let mut aircraft = Aircraft::new(12, 11);
+        aircraft.set_tile(3, 10, Variant::Entrance);
+        aircraft.set_tile(0, 0, Variant::Seat);
+        aircraft.set_tile(1, 0, Variant::Lavatory);
+
+        let text = aircraft.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 13);
+        assert_eq!(lines[0], "             11");
+        assert_eq!(lines[1], "   012345678901");
+        assert_eq!(lines[2], " 0 #L..........");
+        assert_eq!(lines[12], "10 ...E........");
+        assert_eq!(Glyphs::default().variant('L'), Some(Variant::Lavatory));
+    }
+
+    #[test]
+    fn passengers() {
+        let mut aircraft = Aircraft::new(5, 1);
+        let mut carrying = Person::new("CARRYING");
+        carrying.set_baggage(true);
+        aircraft.layout[0][0].occupy(carrying).unwrap();
+        aircraft.layout[1][0].occupy(Person::new("STOWED")).unwrap();
+        aircraft.layout[2][0].occupy(Person::new("SEATED")).unwrap();
+        aircraft.layout[2][0].pass_in(Person::new("PASSING"));
+        aircraft.layout[4][0].occupy(Person::crew(Duty::Assist)).unwrap();
+
+        let glyphs = Glyphs {
+            aisle: '*',
+            ..Glyphs::default()
+        };
+        let mut out = Vec::<u8>::new();
+        aircraft.render(&mut out, &RenderOptions {
+            glyphs,
+            axes: false,
+            ..RenderOptions::default()
+        }).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "@o%*C\n");
+    }
+
+    #[test]
+    fn seat_labels() {
+        let mut aircraft = Aircraft::new(3, 3);
+        for y in 1..3 {
+            aircraft.set_tile(0, y, Variant::Seat);
+            aircraft.set_tile(2, y, Variant::Seat);
+        }
+
+        let mut out = Vec::<u8>::new();
+        aircraft.render(&mut out, &RenderOptions {
+            labels: true,
+            ..RenderOptions::default()
+        }).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines, vec!["  A B", "  ...", "1 #.#", "2 #.#"]);
+    }
+}
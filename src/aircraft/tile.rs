@@ -1,10 +1,15 @@
 //! Holds structures, methods, and functions required for dealing with `Tile`s.
 
 use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
 use super::person::Person;
 
 const PASS_WAIT: u8 = 2; // The amount of additional steps taken when moving
                          // past an occupied seat
+const OPEN_PASS_WAIT: u8 = 1; // The same, for stepping around somebody on open
+                              // floor such as a galley
 
 /// An enum representing the various possible types of tile that an `Aircraft`
 /// may contain.
@@ -14,72 +19,183 @@ pub enum Variant {
     Seat,
     Entrance,
     None,
+    /// Open floor where food is prepared, usually beside the doors.
+    Galley,
+    /// A lavatory, which passengers can't walk through.
+    Lavatory,
+    /// A wall or bulkhead, which passengers can't walk through.
+    Bulkhead,
+    /// The clear floor in front of an emergency exit.
+    ExitRow,
+    /// An aisle running across the cabin, such as between the doors or the two
+    /// aisles of a widebody.
+    CrossAisle,
+    /// The rest of a seat wider than one tile, continuing the seat beside it.
+    /// Nobody sits on it, but passengers cross it to reach the seats beyond.
+    SeatSpan,
+}
+
+/// Every tile variant.
+pub const VARIANTS: [Variant; 10] = [Variant::Aisle,
+                                     Variant::Seat,
+                                     Variant::Entrance,
+                                     Variant::None,
+                                     Variant::Galley,
+                                     Variant::Lavatory,
+                                     Variant::Bulkhead,
+                                     Variant::ExitRow,
+                                     Variant::CrossAisle,
+                                     Variant::SeatSpan];
+
+impl Variant {
+    /// Whether passengers can stand on and walk along tiles of this variant,
+    /// as they do in aisles.
+    pub fn is_walkable(self) -> bool {
+        matches!(self, Variant::Aisle | Variant::Entrance | Variant::Galley
+                 | Variant::ExitRow | Variant::CrossAisle)
+    }
+
+    /// Whether passengers can enter tiles of this variant at all, either to
+    /// walk along them or to sit down.
+    pub fn is_passable(self) -> bool {
+        self.is_walkable() || self.is_seating()
+    }
+
+    /// Whether tiles of this variant are part of a seat, either the seat
+    /// itself or the rest of a wide one.
+    pub fn is_seating(self) -> bool {
+        matches!(self, Variant::Seat | Variant::SeatSpan)
+    }
+
+    /// Whether a passenger can squeeze past somebody already on a tile of this
+    /// variant, rather than waiting for them to move on.
+    ///
+    /// Aisles and entrances are only wide enough for one passenger, but seats
+    /// and open floor can be shared for a few steps.
+    pub fn allows_passing(self) -> bool {
+        matches!(self, Variant::Seat | Variant::SeatSpan | Variant::Galley
+                 | Variant::ExitRow | Variant::CrossAisle)
+    }
+}
+
+/// How much room a seat has in front of it, from the least to the most.
+///
+/// The more legroom a seat has, the less time it takes another passenger to
+/// squeeze past somebody sitting in it.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone,
+         Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Legroom {
+    Tight,
+    #[default]
+    Standard,
+    Extra,
+    Premium,
+}
+
+impl Legroom {
+    /// The additional steps taken to move past a passenger sitting in a seat
+    /// with this much legroom.
+    pub fn pass_wait(self) -> u8 {
+        match self {
+            Legroom::Tight => PASS_WAIT + 1,
+            Legroom::Standard => PASS_WAIT,
+            Legroom::Extra => PASS_WAIT - 1,
+            Legroom::Premium => 0,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Legroom::Tight => "tight",
+            Legroom::Standard => "standard",
+            Legroom::Extra => "extra",
+            Legroom::Premium => "premium",
+        }
+    }
+}
+
+/// Converts a string into an associated amount of legroom.
+pub fn str_to_legroom(legroom: &str) -> Option<Legroom> {
+    match legroom {
+        "tight" => Some(Legroom::Tight),
+        "standard" => Some(Legroom::Standard),
+        "extra" => Some(Legroom::Extra),
+        "premium" => Some(Legroom::Premium),
+        _ => None,
+    }
 }
 
 /// A single tile
 ///
 /// Must have a `variant`, may hold one or two passengers. Two passengers are
 /// only held when another passenger is making their way past on an aisle.
+/// `legroom` only matters for seats, setting how long it takes to get past
+/// their occupant. `chair` is set while the back of an aisle chair sits on the
+/// tile, behind the passenger using it, leaving no room for anybody else.
 pub struct Tile {
     pub(crate) variant: Variant,
     occupier: Option<Person>,
     updated: bool,
     pass_counter: u8,
     allowing: Option<Person>,
+    legroom: Legroom,
+    chair: bool,
 }
 
 impl Tile {
-    /// Constructor with `variant` of `Aisle`.
-    pub(crate) fn aisle() -> Tile {
+    /// Constructor
+    pub fn new(variant: Variant) -> Tile {
         Tile {
-            variant: Variant::Aisle,
+            variant,
             occupier: None,
             updated: false,
             pass_counter: 0,
             allowing: None,
+            legroom: Legroom::Standard,
+            chair: false,
         }
     }
 
+    /// Constructor with `variant` of `Aisle`.
+    pub(crate) fn aisle() -> Tile {
+        Tile::new(Variant::Aisle)
+    }
+
     /// Constructor with `variant` of `Seat`.
     pub fn seat() -> Tile {
-        Tile {
-            variant: Variant::Seat,
-            occupier: None,
-            updated: false,
-            pass_counter: 0,
-            allowing: None,
-        }
+        Tile::new(Variant::Seat)
     }
 
     /// Constructor with `variant` of `Entrance`.
     pub fn entrance() -> Tile {
-        Tile {
-            variant: Variant::Entrance,
-            occupier: None,
-            updated: false,
-            pass_counter: 0,
-            allowing: None,
-        }
+        Tile::new(Variant::Entrance)
     }
 
     /// Constructor with `variant` of `None`.
     pub fn none() -> Tile {
-        Tile {
-            variant: Variant::None,
-            occupier: None,
-            updated: false,
-            pass_counter: 0,
-            allowing: None,
-        }
+        Tile::new(Variant::None)
     }
 
     /// Places a passenger into this tile.
-    pub fn occupy(&mut self, p: Person) {
-        if self.is_occupied() {
-            log::warn!("A passenger is being displaced");
+    ///
+    /// Anybody already occupying the tile is displaced, and is left squeezing
+    /// past the new occupant to make their way out. If somebody is already
+    /// squeezing past, there is no room for them, so the tile is left as it
+    /// was and the new passenger is handed back, boxed, instead.
+    pub fn occupy(&mut self, p: Person) -> Result<(), Box<Person>> {
+        if self.occupier.is_some() && self.allowing.is_some() {
+            log::error!("No room for the displaced passenger");
+            return Err(Box::new(p));
+        }
+        if let Some(displaced) = self.occupier.take() {
+            log::info!("A passenger is being displaced");
+            self.pass_counter = 0;
+            self.allowing = Some(displaced);
         }
         self.occupier = Some(p);
         self.updated = true;
+        Ok(())
     }
 
     /// Allows a second passenger to temporarily occupy this space.
@@ -109,7 +225,11 @@ impl Tile {
 
     /// Checks whether second occupant has been delayed enough to pass onwards.
     pub fn pass_count(&mut self) -> bool {
-        if self.pass_counter >= PASS_WAIT {
+        let wait = match self.variant {
+            Variant::Seat | Variant::SeatSpan => self.legroom.pass_wait(),
+            _ => OPEN_PASS_WAIT,
+        };
+        if self.pass_counter >= wait {
             self.pass_counter = 0;
             return true;
         } else {
@@ -122,7 +242,7 @@ impl Tile {
         self.variant
     }
 
-    pub fn get_occupier(&mut self) -> Option<&Person> {
+    pub fn get_occupier(&self) -> Option<&Person> {
         return self.occupier.as_ref();
     }
 
@@ -138,6 +258,22 @@ impl Tile {
         return self.allowing.as_mut();
     }
 
+    pub fn get_legroom(&self) -> Legroom {
+        self.legroom
+    }
+
+    pub fn set_legroom(&mut self, legroom: Legroom) {
+        self.legroom = legroom;
+    }
+
+    pub fn has_chair(&self) -> bool {
+        self.chair
+    }
+
+    pub fn set_chair(&mut self, t: bool) {
+        self.chair = t;
+    }
+
     pub fn has_updated(&self) -> bool {
         self.updated
     }
@@ -194,7 +330,7 @@ mod tests {
     fn get_occupier() {
         let mut tile = Tile::aisle();
         let person = Person::new("Dave");
-        tile.occupy(person);
+        tile.occupy(person).unwrap();
         assert_eq!(tile.get_occupier().unwrap().get_name(), "Dave");
 
         // TODO: Fix this
@@ -209,7 +345,7 @@ mod tests {
         let mut person = Person::new("DEFAULT");
         person.target_seat(0,0);
 
-        tile0.occupy(person);
+        tile0.occupy(person).unwrap();
         assert!(
             tile0.is_occupied(),
             "Tile 0 was not occupied at initialisation");
@@ -220,8 +356,62 @@ mod tests {
         assert_eq!(tile0.is_occupied(), false, "Tile 0 was still occupied");
         assert!(tile1.is_allowing(), "Tile 1 was not allowing");
 
-        tile0.occupy(tile1.pass_out());
+        tile0.occupy(tile1.pass_out()).unwrap();
         assert!(tile0.is_occupied(), "Tile 0 was not occupied post pass");
         assert_eq!(tile1.is_allowing(), false, "Tile 1 was still allowing");
     }
+
+    #[test]
+    fn displace() {
+        let mut tile = Tile::seat();
+        tile.occupy(Person::new("Dave")).unwrap();
+        tile.occupy(Person::new("Bert")).unwrap();
+        assert_eq!(tile.get_occupier().unwrap().get_name(), "Bert");
+        assert_eq!(tile.get_passer().unwrap().get_name(), "Dave");
+
+        // With somebody already squeezing past, there's no room for anybody
+        let refused = tile.occupy(Person::new("Ernie")).unwrap_err();
+        assert_eq!(refused.get_name(), "Ernie");
+        assert_eq!(tile.get_occupier().unwrap().get_name(), "Bert");
+        assert_eq!(tile.get_passer().unwrap().get_name(), "Dave");
+    }
+
+    #[test]
+    fn legroom() {
+        // Counts the steps a passer is held up for on a seat
+        let held = |legroom: Legroom| {
+            let mut tile = Tile::seat();
+            tile.set_legroom(legroom);
+            (0..).take_while(|_| !tile.pass_count()).count()
+        };
+        assert_eq!(held(Legroom::Standard), PASS_WAIT as usize);
+        assert_eq!(held(Legroom::Tight), PASS_WAIT as usize + 1);
+        assert_eq!(held(Legroom::Extra), PASS_WAIT as usize - 1);
+        assert_eq!(held(Legroom::Premium), 0);
+
+        assert_eq!(Tile::seat().get_legroom(), Legroom::Standard);
+        assert_eq!(str_to_legroom("extra"), Some(Legroom::Extra));
+        assert_eq!(str_to_legroom(Legroom::Tight.as_str()),
+                   Some(Legroom::Tight));
+        assert_eq!(str_to_legroom("roomy"), None);
+    }
+
+    #[test]
+    fn variants() {
+        assert!(Variant::Galley.is_walkable());
+        assert!(Variant::CrossAisle.is_walkable());
+        assert!(!Variant::Seat.is_walkable());
+        assert!(Variant::Seat.is_passable());
+        for variant in &[Variant::Lavatory, Variant::Bulkhead, Variant::None] {
+            assert!(!variant.is_passable(), "{:?} was passable", variant);
+        }
+        assert!(!Variant::Aisle.allows_passing());
+        assert!(Variant::ExitRow.allows_passing());
+
+        let held = |variant: Variant| {
+            let mut tile = Tile::new(variant);
+            (0..).take_while(|_| !tile.pass_count()).count()
+        };
+        assert_eq!(held(Variant::Galley), OPEN_PASS_WAIT as usize);
+    }
 }
@@ -0,0 +1,430 @@
+//! Runs many aircraft simulations in parallel on a fixed number of threads.
+//!
+//! This is shared by the Python `mass_sim` function and the command-line
+//! `batch` subcommand, along with `Record`, which describes a single run in a
+//! form that can be written out as JSON or csv.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::Serialize;
+
+use super::aircraft::{Aircraft, Conflict, Overtaking};
+use super::aircraft::cabin::ClassMetrics;
+use super::aircraft::heatmap::Heatmap;
+use super::config::PassengerOptions;
+
+/// The inputs and results of a single simulation.
+///
+/// `passengers` holds either the passenger list's path or the name of the
+/// strategy used to generate it. The fields from `late` to `walk_time` hold
+/// how the run was set up, as filled in by `set_options` and `set_aircraft`,
+/// and are left at their defaults for runs that don't change them: `crew`
+/// lists each crew member's duty, separated by semicolons, and `policy` the
+/// registered policy passengers without their own follow, if one was chosen.
+/// `steps` and `error` are filled in by
+/// `set_result` once the simulation has run, and `conflicts` and
+/// `conflict_steps` by `set_conflicts`: how many passengers were found in the
+/// wrong seat, and how long on average each took to reach their own seat
+/// after being found, along with `conflict_seats`, the label of each seat
+/// taken by mistake. `classes` holds how long each cabin class took to be
+/// seated. Both lists are left out of csv output, and out of JSON when empty.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Record {
+    pub layout: String,
+    pub passengers: String,
+    pub seed: Option<u64>,
+    pub load_factor: Option<f32>,
+    pub deplaning: Option<String>,
+    pub late: f32,
+    pub max_shift: usize,
+    pub max_delay: u16,
+    pub arrival_rate: Option<f32>,
+    pub parties: f32,
+    pub max_party: u16,
+    pub aisle_chairs: usize,
+    pub chairs_last: bool,
+    pub wrong_seats: f32,
+    pub overtaking: String,
+    pub yield_steps: Option<u8>,
+    pub nearest_door: bool,
+    pub policy: Option<String>,
+    pub crew: Option<String>,
+    pub service_time: u16,
+    pub bridge_length: usize,
+    pub walk_time: u16,
+    pub passenger_count: usize,
+    pub steps: Option<u16>,
+    pub error: Option<String>,
+    pub conflicts: usize,
+    pub conflict_steps: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflict_seats: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub classes: Vec<ClassMetrics>,
+}
+
+impl Record {
+    /// Constructor
+    pub fn new(layout: &str, passengers: &str) -> Record {
+        let options = PassengerOptions::default();
+        Record {
+            layout: layout.to_string(),
+            passengers: passengers.to_string(),
+            seed: None,
+            load_factor: None,
+            deplaning: None,
+            late: options.late,
+            max_shift: options.max_shift,
+            max_delay: options.max_delay,
+            arrival_rate: options.arrival_rate,
+            parties: options.parties,
+            max_party: options.max_party,
+            aisle_chairs: 0,
+            chairs_last: false,
+            wrong_seats: 0.0,
+            overtaking: Overtaking::Wide.as_str().to_string(),
+            yield_steps: None,
+            nearest_door: false,
+            policy: None,
+            crew: None,
+            service_time: 0,
+            bridge_length: 0,
+            walk_time: 0,
+            passenger_count: 0,
+            steps: None,
+            error: None,
+            conflicts: 0,
+            conflict_steps: None,
+            conflict_seats: Vec::new(),
+            classes: Vec::new(),
+        }
+    }
+
+    /// Records the changes made to the boarding order after it was generated
+    /// or read.
+    pub fn set_options(&mut self, options: &PassengerOptions) {
+        self.late = options.late;
+        self.max_shift = options.max_shift;
+        self.max_delay = options.max_delay;
+        self.arrival_rate = options.arrival_rate;
+        self.parties = options.parties;
+        self.max_party = options.max_party;
+    }
+
+    /// Records who passengers may overtake, how long they stand aside, which
+    /// doors they board through and how quickly the gate lets them on.
+    pub fn set_aircraft(&mut self, aircraft: &Aircraft) {
+        self.overtaking = aircraft.get_overtaking().as_str().to_string();
+        self.yield_steps = aircraft.get_yield_steps();
+        self.nearest_door = aircraft.get_nearest_door();
+        let gate = aircraft.get_gate();
+        self.service_time = gate.get_service_time();
+        self.bridge_length = gate.get_bridge_length();
+        self.walk_time = gate.get_walk_time();
+    }
+
+    /// Records the outcome of running the simulation.
+    pub fn set_result<E: ToString>(&mut self, result: Result<u16, E>) {
+        match result {
+            Ok(steps) => self.steps = Some(steps),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    /// Records how many passengers were found in the wrong seat and where, and
+    /// how long those who made it to their own seat took to get there.
+    ///
+    /// Seats without a label are given by their coordinates.
+    pub fn set_conflicts(&mut self, conflicts: &[Conflict]) {
+        self.conflicts = conflicts.len();
+        self.conflict_seats = conflicts.iter()
+            .map(|c| c.label.clone().unwrap_or_else(|| {
+                format!("{},{}", c.seat.0, c.seat.1)
+            }))
+            .collect();
+        let steps: Vec<u16> = conflicts.iter()
+            .filter_map(|c| c.resolved
+                        .map(|resolved| resolved.saturating_sub(c.found)))
+            .collect();
+        if !steps.is_empty() {
+            self.conflict_steps = Some(steps.iter().map(|s| *s as f32)
+                                       .sum::<f32>() / steps.len() as f32);
+        }
+    }
+
+    /// Returns this record as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialise record")
+    }
+}
+
+/// Writes records as csv, with a header row, to `writer`.
+pub fn write_csv<W: std::io::Write>(records: &[Record], writer: W)
+    -> Result<(), &'static str> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    for record in records {
+        // Each record needs the same columns, so lists are left out
+        let record = Record {
+            conflict_seats: Vec::new(),
+            classes: Vec::new(),
+            ..record.clone()
+        };
+        wtr.serialize(record).map_err(|_| "Couldn't write record")?;
+    }
+    wtr.flush().map_err(|_| "Couldn't write record")
+}
+
+/// Simulates every aircraft to completion using at most `threads` threads.
+///
+/// Results are returned in the same order as the aircraft were given, each
+/// holding either the number of steps taken or the reason the simulation
+/// failed.
+///
+/// # Examples
+///
+/// ```
+/// # use aircraft_sim::aircraft::Aircraft;
+/// # use aircraft_sim::batch::run;
+/// let results = run(vec![Aircraft::new(5,5)], 4);
+///
+/// assert_eq!(results, vec![Ok(0)]);
+/// ```
+pub fn run(aircraft: Vec<Aircraft>, threads: usize)
+    -> Vec<Result<u16, &'static str>> {
+    run_with(aircraft, threads, |_, result| result)
+}
+
+/// Simulates every aircraft like `run`, also returning each aircraft's
+/// heatmap so that they can be averaged with `Heatmap::average`.
+pub fn run_heatmaps(aircraft: Vec<Aircraft>, threads: usize)
+    -> Vec<(Result<u16, &'static str>, Heatmap)> {
+    run_with(aircraft, threads,
+             |aircraft, result| (result, aircraft.get_heatmap().clone()))
+}
+
+/// Simulates every aircraft on a pool of threads, keeping whatever `finish`
+/// returns from each finished aircraft in the order they were given.
+pub fn run_with<T, F>(aircraft: Vec<Aircraft>, threads: usize, finish: F)
+    -> Vec<T>
+    where T: Send + 'static,
+          F: Fn(&Aircraft, Result<u16, &'static str>) -> T
+             + Send + Sync + 'static {
+    let count = aircraft.len();
+    let threads = threads.max(1).min(count.max(1));
+    log::info!("Running {} simulations on {} threads", count, threads);
+
+    // Each worker takes the next waiting aircraft from the queue until it is
+    // empty, recording its result against the aircraft's original position.
+    let queue = Arc::new(Mutex::new(aircraft.into_iter()
+                                    .enumerate()
+                                    .collect::<Vec<(usize, Aircraft)>>()));
+    let results = Arc::new(Mutex::new((0..count).map(|_| None)
+                                      .collect::<Vec<Option<T>>>()));
+    let finish = Arc::new(finish);
+
+    let mut workers: Vec<thread::JoinHandle<()>> = Vec::new();
+    for _ in 0..threads {
+        let queue = Arc::clone(&queue);
+        let results = Arc::clone(&results);
+        let finish = Arc::clone(&finish);
+        workers.push(thread::spawn(move || {
+            loop {
+                let job = queue.lock().unwrap().pop();
+                match job {
+                    Some((i, mut aircraft)) => {
+                        let result = aircraft.run_to_completion();
+                        if let Err(e) = result {
+                            log::error!("Simulation failed: {}", e);
+                        }
+                        let result = finish(&aircraft, result);
+                        results.lock().unwrap()[i] = Some(result);
+                    },
+                    None => break,
+                }
+            }
+        }));
+    }
+
+    for worker in workers {
+        if let Err(e) = worker.join() {
+            log::error!("Simulation thread failed: {:?}", e);
+        }
+    }
+
+    let mut results = results.lock().unwrap();
+    results.drain(..)
+        .map(|result| result.expect("Simulation didn't run"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::aircraft::tile::Variant;
+    use super::super::aircraft::person::Person;
+    use super::super::aircraft::cabin::Class;
+    use super::super::aircraft::gate::Gate;
+
+    #[test]
+    fn records() {
+        let mut record = Record::new("layout.csv", "back_first");
+        record.seed = Some(4);
+        record.set_result::<&str>(Ok(12));
+        assert_eq!(record.to_json(),
+                   "{\"layout\":\"layout.csv\",\"passengers\":\"back_first\",\
+                    \"seed\":4,\"load_factor\":null,\"deplaning\":null,\
+                    \"late\":0.0,\"max_shift\":5,\"max_delay\":30,\
+                    \"arrival_rate\":null,\"parties\":0.0,\"max_party\":4,\
+                    \"aisle_chairs\":0,\"chairs_last\":false,\
+                    \"wrong_seats\":0.0,\"overtaking\":\"wide\",\
+                    \"yield_steps\":null,\"nearest_door\":false,\
+                    \"policy\":null,\"crew\":null,\"service_time\":0,\
+                    \"bridge_length\":0,\"walk_time\":0,\
+                    \"passenger_count\":0,\"steps\":12,\"error\":null,\
+                    \"conflicts\":0,\"conflict_steps\":null}");
+
+        // Runs set up differently can be told apart
+        let mut configured = record.clone();
+        configured.set_options(&PassengerOptions {
+            late: 0.5,
+            arrival_rate: Some(2.0),
+            ..PassengerOptions::default()
+        });
+        let mut aircraft = Aircraft::new(3, 3);
+        aircraft.set_overtaking(Overtaking::Never);
+        aircraft.set_yield_steps(Some(2));
+        aircraft.set_nearest_door(true);
+        aircraft.set_gate(Gate::new(4, 10, 2));
+        configured.set_aircraft(&aircraft);
+        configured.policy = Some("greedy".to_string());
+        configured.crew = Some("assist 1,0; stand 1,2".to_string());
+        let json = configured.to_json();
+        for key in ["\"late\":0.5", "\"arrival_rate\":2.0",
+                    "\"overtaking\":\"never\"", "\"yield_steps\":2",
+                    "\"nearest_door\":true", "\"policy\":\"greedy\"",
+                    "\"crew\":\"assist 1,0; stand 1,2\"",
+                    "\"service_time\":4", "\"bridge_length\":10",
+                    "\"walk_time\":2"] {
+            assert!(json.contains(key), "{} missing from {}", key, json);
+        }
+        let mut configured_csv = Vec::<u8>::new();
+        write_csv(&[configured], &mut configured_csv).unwrap();
+
+        let mut conflicted = record.clone();
+        conflicted.set_conflicts(&[
+            Conflict { seat: (0, 1), own_seat: (1, 1),
+                       label: Some("1A".to_string()), found: 3,
+                       resolved: Some(7) },
+            Conflict { seat: (0, 2), own_seat: (1, 2),
+                       label: Some("2A".to_string()), found: 5,
+                       resolved: Some(7) },
+            Conflict { seat: (0, 3), own_seat: (1, 3), label: None, found: 6,
+                       resolved: None },
+        ]);
+        assert_eq!((conflicted.conflicts, conflicted.conflict_steps),
+                   (3, Some(3.0)));
+        assert_eq!(conflicted.conflict_seats, vec!["1A", "2A", "0,3"]);
+        assert!(conflicted.to_json().ends_with(
+            "\"conflict_seats\":[\"1A\",\"2A\",\"0,3\"]}"));
+        let mut conflicted_csv = Vec::<u8>::new();
+        write_csv(&[conflicted], &mut conflicted_csv).unwrap();
+
+        let mut failed = Record::new("layout.csv", "list.csv");
+        failed.set_result(Err("Passengers could not all be seated."));
+
+        // Classes are only written as JSON
+        let mut classed = record.clone();
+        classed.classes.push(ClassMetrics {
+            class: Some(Class::Business),
+            passengers: 2,
+            seated: 2,
+            mean_step: Some(4.5),
+            last_step: Some(6),
+        });
+        assert!(classed.to_json().ends_with(
+            "\"classes\":[{\"class\":\"business\",\"passengers\":2,\
+             \"seated\":2,\"mean_step\":4.5,\"last_step\":6}]}"));
+        let mut classed_csv = Vec::<u8>::new();
+        write_csv(&[classed], &mut classed_csv).unwrap();
+
+        let mut csv = Vec::<u8>::new();
+        write_csv(&[record, failed], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "layout,passengers,seed,load_factor,deplaning,\
+                              late,max_shift,max_delay,arrival_rate,parties,\
+                              max_party,aisle_chairs,chairs_last,wrong_seats,\
+                              overtaking,yield_steps,nearest_door,policy,crew,\
+                              service_time,bridge_length,walk_time,\
+                              passenger_count,steps,error,conflicts,\
+                              conflict_steps");
+        assert_eq!(lines[1], "layout.csv,back_first,4,,,\
+                              0.0,5,30,,0.0,4,0,false,0.0,wide,,false,,,0,0,0,\
+                              0,12,,0,");
+        assert_eq!(lines[2], "layout.csv,list.csv,,,,\
+                              0.0,5,30,,0.0,4,0,false,0.0,wide,,false,,,0,0,0,\
+                              0,,Passengers could not all be seated.,0,");
+        assert_eq!(String::from_utf8(classed_csv).unwrap().lines().nth(1),
+                   Some(lines[1]));
+        assert_eq!(String::from_utf8(conflicted_csv).unwrap().lines().nth(1),
+                   Some("layout.csv,back_first,4,,,\
+                         0.0,5,30,,0.0,4,0,false,0.0,wide,,false,,,0,0,0,\
+                         0,12,,3,3.0"));
+        assert_eq!(String::from_utf8(configured_csv).unwrap().lines().nth(1),
+                   Some("layout.csv,back_first,4,,,\
+                         0.5,5,30,2.0,0.0,4,0,false,0.0,never,2,true,greedy,\
+                         \"assist 1,0; stand 1,2\",4,10,2,0,12,,0,"));
+    }
+
+    #[test]
+    fn results_in_order() {
+        // Larger aircraft take longer for a single passenger to cross.
+        let mut aircraft = Vec::<Aircraft>::new();
+        for size_y in 2..10 {
+            let mut plane = Aircraft::new(2, size_y);
+            for y in 0..size_y {
+                plane.set_tile(0, y, Variant::Seat);
+            }
+            plane.set_tile(1, size_y - 1, Variant::Entrance);
+            let mut passenger = Person::new("DEFAULT");
+            passenger.target_seat(0, 0);
+            plane.add_passenger(passenger);
+            aircraft.push(plane);
+        }
+
+        let results = run(aircraft, 3);
+        assert_eq!(results.len(), 8);
+        for pair in results.windows(2) {
+            assert!(pair[0].unwrap() < pair[1].unwrap());
+        }
+    }
+
+    #[test]
+    fn heatmaps() {
+        let mut aircraft = Vec::<Aircraft>::new();
+        for _ in 0..4 {
+            let mut plane = Aircraft::new(2, 3);
+            for y in 0..3 {
+                plane.set_tile(0, y, Variant::Seat);
+            }
+            plane.set_tile(1, 2, Variant::Entrance);
+            let mut passenger = Person::new("DEFAULT");
+            passenger.target_seat(0, 0);
+            plane.add_passenger(passenger);
+            aircraft.push(plane);
+        }
+
+        let results = run_heatmaps(aircraft, 2);
+        assert_eq!(results.len(), 4);
+        let heatmaps: Vec<Heatmap> = results.into_iter()
+            .map(|(result, heatmap)| {
+                assert!(result.is_ok());
+                heatmap
+            })
+            .collect();
+        let average = Heatmap::average(&heatmaps).unwrap();
+        assert_eq!(&average, &heatmaps[0],
+                   "Identical simulations averaged to something different");
+    }
+}
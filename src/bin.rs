@@ -2,48 +2,1071 @@
 
 mod config;
 mod aircraft;
+mod batch;
+mod export;
+mod tui;
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::Duration;
 use simple_logger::SimpleLogger;
 use log::LevelFilter;
 
-use config::{read_layout, read_passengers};
+use aircraft::Aircraft;
+use aircraft::cabin::{Class, ClassMetrics};
+use aircraft::crew;
+use aircraft::person::Person;
+use aircraft::policy::named_policy;
+use aircraft::render::RenderOptions;
+use aircraft::heatmap::{str_to_counter, Heatmap};
+use aircraft::tile::Variant;
+use batch::Record;
+use config::*;
+
+/// The formats results can be printed in.
+///
+/// `Json` prints one JSON object per line, and `Csv` prints a header row
+/// followed by one row per simulation.
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
 
 fn main() {
     let matches = App::new("aircraft_sim")
-                    .arg(Arg::with_name("layout")
-                           .index(1)
-                           .required(true)
-                           .help("Layout file as csv with headers"))
-                    .arg(Arg::with_name("passengers")
-                           .index(2)
-                           .required(true)
-                           .help("Passenger list as csv with headers"))
-                    .get_matches();
-
-    let layout_file = matches.value_of("layout").unwrap();
-    let passenger_list = matches.value_of("passengers").unwrap();
-    
-    let aircraft = read_layout(Path::new(layout_file));
-    let passengers = read_passengers(Path::new(passenger_list));
-    
-    if aircraft.is_some() && passengers.is_some() {
-        let mut aircraft = aircraft.unwrap();
-        SimpleLogger::new()
-            .with_level(LevelFilter::Warn)
-            .init()
-            .expect("Failed to initialise logger");
-
-        log::info!("Initialised logger");
-        for i in passengers.unwrap() {
-            aircraft.add_passenger(i);
-        }
-        match aircraft.run_to_completion() {
-            Ok(x) => println!("Completed in {} steps!", x),
-            _ => println!("Incorrect input files"),
+        .about("Simulates passengers boarding and leaving an aircraft")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(Arg::with_name("seed")
+               .long("seed")
+               .takes_value(true)
+               .global(true)
+               .help("Seed for any randomly generated boarding patterns"))
+        .arg(Arg::with_name("verbose")
+               .short("v")
+               .long("verbose")
+               .multiple(true)
+               .global(true)
+               .help("Shows more log output; repeat for more detail"))
+        .arg(Arg::with_name("threads")
+               .short("j")
+               .long("threads")
+               .takes_value(true)
+               .global(true)
+               .help("Number of simulations to run at once"))
+        .arg(Arg::with_name("format")
+               .long("format")
+               .takes_value(true)
+               .possible_values(&["text", "csv", "json"])
+               .default_value("text")
+               .global(true)
+               .help("Format to print results in"))
+        .subcommand(SubCommand::with_name("run")
+            .about("Simulates a single aircraft")
+            .arg(Arg::with_name("layout")
+                   .index(1)
+                   .required(true)
+                   .help("Layout file, bundled aircraft or seat map"))
+            .arg(Arg::with_name("passengers")
+                   .index(2)
+                   .required(true)
+                   .help("Passenger list as csv with headers"))
+            .arg(late_arg())
+            .arg(max_shift_arg())
+            .arg(max_delay_arg())
+            .arg(arrival_rate_arg())
+            .arg(parties_arg())
+            .arg(max_party_arg())
+            .arg(heatmap_arg())
+            .arg(counter_arg())
+            .arg(crew_arg())
+            .arg(overtaking_arg())
+            .arg(yield_arg())
+            .arg(doors_arg())
+            .arg(policy_arg())
+            .arg(deplane_arg()))
+        .subcommand(SubCommand::with_name("batch")
+            .about("Simulates many aircraft in parallel, either from pairs of \
+                    files or from seeds and boarding strategies")
+            .arg(Arg::with_name("layout")
+                   .long("layout")
+                   .takes_value(true)
+                   .multiple(true)
+                   .requires("passengers")
+                   .help("Layout files, paired with passenger lists in order"))
+            .arg(Arg::with_name("passengers")
+                   .long("passengers")
+                   .takes_value(true)
+                   .multiple(true)
+                   .requires("layout")
+                   .help("Passenger lists, paired with layouts in order"))
+            .arg(Arg::with_name("strategy")
+                   .long("strategy")
+                   .takes_value(true)
+                   .multiple(true)
+                   .possible_values(&STRATEGIES)
+                   .conflicts_with("layout")
+                   .help("Boarding strategies to generate passengers with"))
+            .arg(Arg::with_name("seeds")
+                   .long("seeds")
+                   .takes_value(true)
+                   .default_value("1")
+                   .help("Number of seeds to run each strategy with, counting \
+                          up from --seed"))
+            .arg(Arg::with_name("save-passengers")
+                   .long("save-passengers")
+                   .takes_value(true)
+                   .requires("strategy")
+                   .help("Directory to save each generated passenger list to, \
+                          as STRATEGY_SEED.csv"))
+            .arg(Arg::with_name("aircraft")
+                   .long("aircraft")
+                   .takes_value(true)
+                   .requires("strategy")
+                   .help("Layout file, bundled aircraft or seat map to seat \
+                          generated passengers on, premium classes first, \
+                          instead of a standard layout"))
+            .arg(size_arg())
+            .arg(load_factor_arg())
+            .arg(aisle_chairs_arg())
+            .arg(chairs_last_arg())
+            .arg(wrong_seats_arg())
+            .arg(late_arg())
+            .arg(max_shift_arg())
+            .arg(max_delay_arg())
+            .arg(arrival_rate_arg())
+            .arg(parties_arg())
+            .arg(max_party_arg())
+            .arg(heatmap_arg())
+            .arg(counter_arg())
+            .arg(crew_arg())
+            .arg(overtaking_arg())
+            .arg(yield_arg())
+            .arg(doors_arg())
+            .arg(policy_arg())
+            .arg(deplane_arg()))
+        .subcommand(SubCommand::with_name("generate")
+            .about("Writes a layout or a generated passenger list to a file")
+            .arg(Arg::with_name("kind")
+                   .index(1)
+                   .required(true)
+                   .possible_values(&["layout", "passengers"])
+                   .help("What to generate"))
+            .arg(Arg::with_name("output")
+                   .short("o")
+                   .long("output")
+                   .takes_value(true)
+                   .required(true)
+                   .help("File to write to"))
+            .arg(Arg::with_name("strategy")
+                   .long("strategy")
+                   .takes_value(true)
+                   .possible_values(&STRATEGIES)
+                   .default_value("random")
+                   .help("Boarding strategy to generate passengers with"))
+            .arg(Arg::with_name("aircraft")
+                   .long("aircraft")
+                   .takes_value(true)
+                   .help("Bundled aircraft or seat map to write, or to seat \
+                          passengers on, premium classes first, instead of a \
+                          standard layout"))
+            .arg(Arg::with_name("labels")
+                   .long("labels")
+                   .help("Writes passengers' seats by label, such as 23C, \
+                          instead of by coordinates"))
+            .arg(size_arg())
+            .arg(load_factor_arg())
+            .arg(aisle_chairs_arg())
+            .arg(chairs_last_arg())
+            .arg(wrong_seats_arg())
+            .arg(late_arg())
+            .arg(max_shift_arg())
+            .arg(max_delay_arg())
+            .arg(arrival_rate_arg())
+            .arg(parties_arg())
+            .arg(max_party_arg()))
+        .subcommand(SubCommand::with_name("aircraft")
+            .about("Lists the bundled aircraft"))
+        .subcommand(SubCommand::with_name("validate")
+            .about("Checks a layout, and optionally a passenger list, for \
+                    problems")
+            .arg(Arg::with_name("layout")
+                   .index(1)
+                   .required(true)
+                   .help("Layout file, bundled aircraft or seat map"))
+            .arg(Arg::with_name("passengers")
+                   .index(2)
+                   .help("Passenger list as csv with headers"))
+            .arg(Arg::with_name("strict")
+                   .long("strict")
+                   .help("Stops at the first passenger that can't be read \
+                          instead of listing every one")))
+        .subcommand(SubCommand::with_name("render")
+            .about("Plays back a simulation step by step as ASCII")
+            .arg(Arg::with_name("layout")
+                   .index(1)
+                   .required(true)
+                   .help("Layout file, bundled aircraft or seat map"))
+            .arg(Arg::with_name("passengers")
+                   .index(2)
+                   .required(true)
+                   .help("Passenger list as csv with headers"))
+            .arg(Arg::with_name("delay")
+                   .long("delay")
+                   .takes_value(true)
+                   .default_value("200")
+                   .help("Milliseconds to wait between steps"))
+            .arg(Arg::with_name("labels")
+                   .long("labels")
+                   .help("Labels rows by number and columns by seat letter \
+                          instead of by coordinate"))
+            .arg(crew_arg())
+            .arg(overtaking_arg())
+            .arg(yield_arg())
+            .arg(doors_arg())
+            .arg(policy_arg())
+            .arg(deplane_arg()))
+        .subcommand(SubCommand::with_name("play")
+            .about("Plays back a simulation interactively in the terminal")
+            .arg(Arg::with_name("layout")
+                   .index(1)
+                   .required(true)
+                   .help("Layout file, bundled aircraft or seat map"))
+            .arg(Arg::with_name("passengers")
+                   .index(2)
+                   .required(true)
+                   .help("Passenger list as csv with headers"))
+            .arg(Arg::with_name("delay")
+                   .long("delay")
+                   .takes_value(true)
+                   .default_value("200")
+                   .help("Initial milliseconds to wait between steps"))
+            .arg(crew_arg())
+            .arg(overtaking_arg())
+            .arg(yield_arg())
+            .arg(doors_arg())
+            .arg(policy_arg())
+            .arg(deplane_arg()))
+        .subcommand(SubCommand::with_name("export")
+            .about("Draws every step of a simulation as SVG or PNG frames, or \
+                    as an animated GIF")
+            .arg(Arg::with_name("layout")
+                   .index(1)
+                   .required(true)
+                   .help("Layout file, bundled aircraft or seat map"))
+            .arg(Arg::with_name("passengers")
+                   .index(2)
+                   .required(true)
+                   .help("Passenger list as csv with headers"))
+            .arg(Arg::with_name("output")
+                   .short("o")
+                   .long("output")
+                   .takes_value(true)
+                   .required(true)
+                   .help("GIF file, or directory for SVG and PNG frames"))
+            .arg(Arg::with_name("image-format")
+                   .long("image-format")
+                   .takes_value(true)
+                   .possible_values(&["svg", "png", "gif"])
+                   .default_value("gif")
+                   .help("Format to draw steps in"))
+            .arg(Arg::with_name("images")
+                   .long("images")
+                   .takes_value(true)
+                   .help("Directory holding seat.png, pass.png and allo.png \
+                          to draw with instead of the bundled sprites"))
+            .arg(Arg::with_name("delay")
+                   .long("delay")
+                   .takes_value(true)
+                   .default_value("200")
+                   .help("Milliseconds each step is shown for in a GIF"))
+            .arg(Arg::with_name("step")
+                   .long("step")
+                   .help("Draws the step number in the corner"))
+            .arg(Arg::with_name("zones")
+                   .long("zones")
+                   .help("Colours passengers by boarding zone"))
+            .arg(crew_arg())
+            .arg(overtaking_arg())
+            .arg(yield_arg())
+            .arg(doors_arg())
+            .arg(policy_arg())
+            .arg(deplane_arg()))
+        .get_matches();
+
+    let (name, sub_matches) = matches.subcommand();
+    let sub_matches = sub_matches.unwrap();
+
+    SimpleLogger::new()
+        .with_level(match sub_matches.occurrences_of("verbose") {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        })
+        .init()
+        .expect("Failed to initialise logger");
+    log::info!("Initialised logger");
+
+    let result = parse_options(sub_matches).and_then(|options| {
+        if let Some(seed) = options.seed {
+            config::seed(seed);
+        }
+        match name {
+            "run" => run(sub_matches, &options),
+            "batch" => run_batch(sub_matches, &options),
+            "generate" => generate_file(sub_matches),
+            "aircraft" => list_aircraft(),
+            "validate" => validate_files(sub_matches),
+            "render" => render(sub_matches),
+            "play" => play(sub_matches),
+            "export" => export_frames(sub_matches),
+            _ => Err("Unknown subcommand".to_string()),
+        }
+    });
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Options shared by every subcommand.
+struct Options {
+    seed: Option<u64>,
+    threads: usize,
+    format: Format,
+}
+
+fn parse_options(matches: &ArgMatches) -> Result<Options, String> {
+    let seed = match matches.value_of("seed") {
+        Some(seed) => Some(seed.parse().map_err(|_| "Invalid seed")?),
+        None => None,
+    };
+    let threads = match matches.value_of("threads") {
+        Some(threads) => threads.parse().map_err(|_| "Invalid thread count")?,
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let format = match matches.value_of("format") {
+        Some("csv") => Format::Csv,
+        Some("json") => Format::Json,
+        _ => Format::Text,
+    };
+
+    Ok(Options { seed, threads, format })
+}
+
+fn deplane_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("deplane")
+        .long("deplane")
+        .takes_value(true)
+        .possible_values(&["row_by_row", "free_for_all", "aisle_first"])
+        .help("Simulates deplaning with the given strategy instead of boarding")
+}
+
+fn crew_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("crew")
+        .long("crew")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Adds a crew member, as \"patrol X,Y X,Y\", \"assist X,Y\" or \
+               \"stand X,Y\"; repeat for more crew")
+}
+
+fn overtaking_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("overtaking")
+        .long("overtaking")
+        .takes_value(true)
+        .possible_values(&["never", "wide"])
+        .default_value("wide")
+        .help("Who passengers squeeze past in an aisle: nobody, or anybody \
+               where the aisle is wide enough")
+}
+
+fn policy_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("policy")
+        .long("policy")
+        .takes_value(true)
+        .help("The registered policy, such as \"greedy\", by which passengers \
+               without their own decide how to move")
+}
+
+fn yield_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("yield")
+        .long("yield")
+        .takes_value(true)
+        .help("Lets passengers held up in the aisle step into an empty seat to \
+               let somebody reach their row, staying there this many steps")
+}
+
+fn doors_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("doors")
+        .long("doors")
+        .takes_value(true)
+        .possible_values(&["any", "nearest"])
+        .help("Whether passengers board through any free door or only the \
+               one nearest their seat; seat maps and bundled aircraft default \
+               to nearest, layout files to any")
+}
+
+fn heatmap_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("heatmap")
+        .long("heatmap")
+        .takes_value(true)
+        .help("Writes where passengers were held up to a .csv, .png or .svg \
+               file, averaged over every successful simulation")
+}
+
+fn counter_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("counter")
+        .long("counter")
+        .takes_value(true)
+        .possible_values(&["occupied", "waited", "passes"])
+        .default_value("waited")
+        .help("Which count to draw in a .png or .svg heatmap")
+}
+
+fn size_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("size")
+        .long("size")
+        .takes_value(true)
+        .default_value("7x23")
+        .help("Size of a generated standard layout, as WIDTHxLENGTH")
+}
+
+fn load_factor_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("load_factor")
+        .long("load-factor")
+        .takes_value(true)
+        .default_value("1.0")
+        .help("Share of seats that generated passengers fill")
+}
+
+fn aisle_chairs_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("aisle_chairs")
+        .long("aisle-chairs")
+        .takes_value(true)
+        .default_value("0")
+        .help("Number of generated passengers who board in aisle chairs, \
+               before everybody else")
+}
+
+fn chairs_last_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("chairs_last")
+        .long("chairs-last")
+        .help("Boards passengers in aisle chairs after everybody else")
+}
+
+fn wrong_seats_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("wrong_seats")
+        .long("wrong-seats")
+        .takes_value(true)
+        .default_value("0")
+        .help("Share of generated passengers who head for somebody else's \
+               seat beside their own")
+}
+
+fn late_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("late")
+        .long("late")
+        .takes_value(true)
+        .default_value("0")
+        .help("Share of passengers who don't follow the called order, either \
+               boarding with the wrong group or arriving late")
+}
+
+fn max_shift_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("max_shift")
+        .long("max-shift")
+        .takes_value(true)
+        .default_value("5")
+        .help("Most places a passenger boarding with the wrong group moves in \
+               the order")
+}
+
+fn max_delay_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("max_delay")
+        .long("max-delay")
+        .takes_value(true)
+        .default_value("30")
+        .help("Most steps later than everybody else a late passenger arrives")
+}
+
+fn arrival_rate_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("arrival_rate")
+        .long("arrival-rate")
+        .takes_value(true)
+        .help("Has passengers arrive at the gate in boarding order, on average \
+               this many each step, instead of all being there at the start")
+}
+
+fn parties_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("parties")
+        .long("parties")
+        .takes_value(true)
+        .default_value("0")
+        .help("Share of passengers travelling in parties, who sit together \
+               and board one after another")
+}
+
+fn max_party_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("max_party")
+        .long("max-party")
+        .takes_value(true)
+        .default_value("4")
+        .help("Most passengers in a party")
+}
+
+/// Reads the changes to make to a boarding order from the `late`,
+/// `max_shift`, `max_delay`, `arrival_rate`, `parties` and `max_party`
+/// arguments, leaving out any not given.
+fn passenger_options(matches: &ArgMatches)
+    -> Result<PassengerOptions, String> {
+    let mut options = PassengerOptions::default();
+    if let Some(late) = matches.value_of("late") {
+        options.late = late.parse().map_err(|_| "Invalid late share")?;
+    }
+    if let Some(shift) = matches.value_of("max_shift") {
+        options.max_shift = shift.parse().map_err(|_| "Invalid maximum shift")?;
+    }
+    if let Some(delay) = matches.value_of("max_delay") {
+        options.max_delay = delay.parse().map_err(|_| "Invalid maximum delay")?;
+    }
+    if let Some(rate) = matches.value_of("arrival_rate") {
+        options.arrival_rate = Some(rate.parse()
+                                    .map_err(|_| "Invalid arrival rate")?);
+    }
+    if let Some(share) = matches.value_of("parties") {
+        options.parties = share.parse().map_err(|_| "Invalid party share")?;
+    }
+    if let Some(size) = matches.value_of("max_party") {
+        options.max_party = size.parse().map_err(|_| "Invalid party size")?;
+    }
+    Ok(options)
+}
+
+/// Sends as many generated passengers to the wrong seat as the `wrong_seats`
+/// argument asks for.
+fn wrong_seats(matches: &ArgMatches, passengers: Vec<Person>)
+    -> Result<Vec<Person>, String> {
+    let rate: f32 = matches.value_of("wrong_seats").unwrap()
+        .parse().map_err(|_| "Invalid wrong seat rate")?;
+    Ok(assign_wrong_seats(passengers, rate)?)
+}
+
+/// Seats as many generated passengers in aisle chairs as the `aisle_chairs`
+/// argument asks for.
+fn aisle_chairs(matches: &ArgMatches, passengers: Vec<Person>)
+    -> Result<Vec<Person>, String> {
+    let count: usize = matches.value_of("aisle_chairs").unwrap()
+        .parse().map_err(|_| "Invalid number of aisle chairs")?;
+    Ok(assign_aisle_chairs(passengers, count,
+                           matches.is_present("chairs_last"))?)
+}
+
+/// Parses a size given as "WIDTHxLENGTH".
+fn parse_size(size: &str) -> Result<(u16, u16), String> {
+    let mut parts = size.split('x').map(|part| part.trim().parse::<u16>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) if x > 0 && y > 0 => Ok((x, y)),
+        _ => Err(format!("Invalid size '{}'", size)),
+    }
+}
+
+/// Reads a passenger list, reporting missing files as an error.
+fn load_passengers(path: &str) -> Result<Vec<Person>, String> {
+    if !Path::new(path).is_file() {
+        return Err(format!("Passenger file '{}' not found", path));
+    }
+    read_passengers(Path::new(path))
+        .ok_or_else(|| format!("Invalid passenger file '{}'", path))
+}
+
+/// Reads a layout and passenger list and boards the passengers, switching to
+/// deplaning if the `deplane` argument was given.
+///
+/// Returns the aircraft along with a record of how it was set up.
+fn load(matches: &ArgMatches, layout: &str, passengers: &str)
+    -> Result<(Aircraft, Record), String> {
+    let mut record = Record::new(layout, passengers);
+    let mut aircraft = load_layout(layout)?;
+    let passengers = apply_options(load_passengers(passengers)?,
+                                   &passenger_options(matches)?)?;
+    record.passenger_count = passengers.len();
+    for (i, person) in passengers.into_iter().enumerate() {
+        aircraft.try_add_passenger(person)
+            .map_err(|e| format!("{} (passenger {})", e, i))?;
+    }
+    add_crew(matches, &mut aircraft)?;
+    overtaking(matches, &mut aircraft)?;
+    deplane(matches, &mut aircraft, &mut record)?;
+    describe(matches, &aircraft, &mut record)?;
+    Ok((aircraft, record))
+}
+
+/// Fills in how a run was set up, from the arguments that change the
+/// passengers and from the aircraft they are about to board.
+fn describe(matches: &ArgMatches, aircraft: &Aircraft, record: &mut Record)
+    -> Result<(), String> {
+    record.set_options(&passenger_options(matches)?);
+    if let Some(count) = matches.value_of("aisle_chairs") {
+        record.aisle_chairs = count.parse()
+            .map_err(|_| "Invalid number of aisle chairs")?;
+    }
+    record.chairs_last = matches.is_present("chairs_last");
+    if let Some(rate) = matches.value_of("wrong_seats") {
+        record.wrong_seats = rate.parse()
+            .map_err(|_| "Invalid wrong seat rate")?;
+    }
+    record.policy = matches.value_of("policy").map(str::to_string);
+    let crew: Vec<&str> = matches.values_of("crew").into_iter().flatten()
+        .collect();
+    if !crew.is_empty() {
+        record.crew = Some(crew.join("; "));
+    }
+    record.set_aircraft(aircraft);
+    Ok(())
+}
+
+/// Places every crew member given by the `crew` argument.
+fn add_crew(matches: &ArgMatches, aircraft: &mut Aircraft)
+    -> Result<(), String> {
+    for spec in matches.values_of("crew").into_iter().flatten() {
+        let ((x, y), duty) = crew::parse_crew(spec)
+            .map_err(|e| format!("{} in '{}'", e, spec))?;
+        aircraft.add_crew(x, y, duty)
+            .map_err(|e| format!("{} in '{}'", e, spec))?;
+    }
+    Ok(())
+}
+
+/// Sets who passengers may overtake in an aisle from the `overtaking`
+/// argument, whether they step aside to let others by from `yield`, which
+/// doors they board through from `doors`, and the policy they decide how to
+/// move by from `policy`.
+fn overtaking(matches: &ArgMatches, aircraft: &mut Aircraft)
+    -> Result<(), String> {
+    if let Some(overtaking) = matches.value_of("overtaking") {
+        aircraft.set_overtaking(str_to_overtaking(overtaking)
+                                .ok_or("Invalid overtaking rules")?);
+    }
+    if let Some(steps) = matches.value_of("yield") {
+        aircraft.set_yield_steps(Some(steps.parse()
+                                      .map_err(|_| "Invalid yield steps")?));
+    }
+    if let Some(doors) = matches.value_of("doors") {
+        aircraft.set_nearest_door(doors == "nearest");
+    }
+    if let Some(policy) = matches.value_of("policy") {
+        aircraft.set_policy(named_policy(policy).ok_or("Unknown policy")?);
+    }
+    Ok(())
+}
+
+/// Switches an aircraft to deplaning if the `deplane` argument was given.
+fn deplane(matches: &ArgMatches, aircraft: &mut Aircraft, record: &mut Record)
+    -> Result<(), String> {
+    if let Some(strategy) = matches.value_of("deplane") {
+        aircraft.deplane(str_to_deplaning(strategy)
+                         .ok_or("Invalid deplaning strategy")?);
+        record.deplaning = Some(strategy.to_string());
+    }
+    Ok(())
+}
+
+/// Prints the records of finished simulations in the chosen format.
+fn print_records(records: &[Record], format: Format) -> Result<(), String> {
+    match format {
+        Format::Text => {
+            for record in records {
+                let label = match record.seed {
+                    Some(seed) => format!("{} (seed {})",
+                                          record.passengers, seed),
+                    None => format!("{} + {}",
+                                    record.layout, record.passengers),
+                };
+                match (record.steps, &record.error) {
+                    (Some(steps), _) => println!("{}: completed in {} steps",
+                                                 label, steps),
+                    (None, Some(e)) => println!("{}: failed ({})", label, e),
+                    (None, None) => println!("{}: not run", label),
+                }
+                print_conflicts(record);
+            }
+        },
+        Format::Csv => batch::write_csv(records, std::io::stdout())?,
+        Format::Json => {
+            for record in records {
+                println!("{}", record.to_json());
+            }
+        },
+    }
+    Ok(())
+}
+
+fn run(matches: &ArgMatches, options: &Options) -> Result<(), String> {
+    let layout = matches.value_of("layout").unwrap();
+    let passengers = matches.value_of("passengers").unwrap();
+    let (mut aircraft, mut record) = load(matches, layout, passengers)?;
+    record.seed = options.seed;
+
+    let result = aircraft.run_to_completion();
+    if let Some(path) = matches.value_of("heatmap") {
+        save_heatmap(matches, Path::new(path), aircraft.get_heatmap(),
+                     &aircraft)?;
+    }
+    let classes = aircraft.class_metrics();
+    record.set_conflicts(aircraft.get_conflicts());
+    if options.format == Format::Text {
+        if let Ok(x) = result {
+            println!("Completed in {} steps!", x);
+        }
+        print_conflicts(&record);
+        print_classes(&classes);
+    } else {
+        record.set_result(result);
+        record.classes = classes;
+        print_records(&[record], options.format)?;
+    }
+    result.map(|_| ()).map_err(|e| format!("Simulation failed: {}", e))
+}
+
+/// Prints how many passengers were found in the wrong seat, and which seats
+/// they took, if any.
+fn print_conflicts(record: &Record) {
+    let seats = record.conflict_seats.join(", ");
+    match (record.conflicts, record.conflict_steps) {
+        (0, _) => {},
+        (count, Some(steps)) => {
+            println!("  {} passengers found in the wrong seat ({}), taking \
+                      on average {:.1} steps to reach their own", count,
+                     seats, steps);
+        },
+        (count, None) => {
+            println!("  {} passengers found in the wrong seat ({})", count,
+                     seats);
+        },
+    }
+}
+
+/// Prints how long each cabin class took to be seated, if the aircraft has
+/// any classes.
+fn print_classes(classes: &[ClassMetrics]) {
+    if classes.iter().all(|metrics| metrics.class.is_none()) {
+        return;
+    }
+    for metrics in classes {
+        let class = metrics.class.map_or("unclassed", Class::as_str);
+        match (metrics.mean_step, metrics.last_step) {
+            (Some(mean), Some(last)) => {
+                println!("  {}: {} of {} passengers seated, on average by \
+                          step {:.1} and all by step {}",
+                         class, metrics.seated, metrics.passengers, mean,
+                         last);
+            },
+            _ => println!("  {}: none of {} passengers seated", class,
+                          metrics.passengers),
+        }
+    }
+}
+
+fn run_batch(matches: &ArgMatches, options: &Options) -> Result<(), String> {
+    let mut records = Vec::<Record>::new();
+    // Which record each aircraft being simulated belongs to; records for runs
+    // that couldn't be set up hold an error instead.
+    let mut indices = Vec::<usize>::new();
+    let mut jobs = Vec::<Aircraft>::new();
+    // An empty aircraft to draw the heatmap over
+    let mut plan: Option<Aircraft> = None;
+
+    if let Some(strategies) = matches.values_of("strategy") {
+        let size = matches.value_of("size").unwrap();
+        let (size_x, size_y) = parse_size(size)?;
+        let load_factor: f32 = matches.value_of("load_factor").unwrap()
+            .parse().map_err(|_| "Invalid load factor")?;
+        let seeds: u64 = matches.value_of("seeds").unwrap()
+            .parse().map_err(|_| "Invalid number of seeds")?;
+        let first_seed = options.seed.unwrap_or(0);
+        let layout = matches.value_of("aircraft");
+        // Builds a fresh aircraft for each run
+        let build = || match layout {
+            Some(layout) => load_layout(layout)
+                .map_err(|e| format!("{} ('{}')", e, layout)),
+            None => standard_layout(size_x, size_y).map_err(str::to_string),
+        };
+        let name = match layout {
+            Some(layout) => layout.to_string(),
+            None => format!("standard {}", size),
+        };
+        let options = passenger_options(matches)?;
+        plan = Some(build()?);
+        let save_dir = matches.value_of("save-passengers").map(Path::new);
+        if let Some(dir) = save_dir {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                format!("Couldn't create '{}': {}", dir.display(), e)
+            })?;
+        }
+
+        for strategy in strategies {
+            for seed in first_seed..first_seed + seeds {
+                config::seed(seed);
+                let mut record = Record::new(&name, strategy);
+                record.seed = Some(seed);
+                record.load_factor = Some(load_factor);
+
+                let mut aircraft = build()?;
+                let passengers = match layout {
+                    Some(_) => generate_for(&aircraft, strategy, load_factor)?,
+                    None => generate(strategy, size_x, size_y, load_factor)?,
+                };
+                let passengers = aisle_chairs(matches, passengers)?;
+                let passengers = apply_options(passengers, &options)?;
+                let passengers = wrong_seats(matches, passengers)?;
+                if let Some(dir) = save_dir {
+                    let name = format!("{}_{}.csv", strategy, seed);
+                    write_passengers(&passengers, &dir.join(name))?;
+                }
+                record.passenger_count = passengers.len();
+                for person in passengers {
+                    aircraft.add_passenger(person);
+                }
+                // A layout that can't take the crew or other settings only
+                // fails its own record
+                let setup = add_crew(matches, &mut aircraft)
+                    .and_then(|_| overtaking(matches, &mut aircraft))
+                    .and_then(|_| deplane(matches, &mut aircraft,
+                                          &mut record))
+                    .and_then(|_| describe(matches, &aircraft, &mut record));
+                if let Err(e) = setup {
+                    record.set_result(Err(e));
+                    records.push(record);
+                    continue;
+                }
+
+                indices.push(records.len());
+                records.push(record);
+                jobs.push(aircraft);
+            }
+        }
+    } else if let (Some(layouts), Some(passengers))
+        = (matches.values_of("layout"), matches.values_of("passengers")) {
+        let layouts: Vec<&str> = layouts.collect();
+        let passengers: Vec<&str> = passengers.collect();
+        if layouts.len() != passengers.len() {
+            return Err("Each layout needs exactly one passenger list"
+                       .to_string());
+        }
+        if let Some(layout) = layouts.first() {
+            plan = load_layout(layout).ok();
+        }
+        for (layout, passengers) in layouts.iter().zip(passengers.iter()) {
+            match load(matches, layout, passengers) {
+                Ok((aircraft, record)) => {
+                    indices.push(records.len());
+                    records.push(record);
+                    jobs.push(aircraft);
+                },
+                Err(e) => {
+                    let mut record = Record::new(layout, passengers);
+                    record.set_result(Err(e));
+                    records.push(record);
+                },
+            }
+        }
+    } else {
+        return Err("Give either --strategy or --layout and --passengers"
+                   .to_string());
+    }
+
+    let mut heatmaps = Vec::<Heatmap>::new();
+    let results = batch::run_with(jobs, options.threads, |aircraft, result| {
+        (result, aircraft.get_heatmap().clone(), aircraft.class_metrics(),
+         aircraft.get_conflicts().to_vec())
+    });
+    for (i, (result, heatmap, classes, conflicts))
+        in indices.into_iter().zip(results) {
+        if result.is_ok() {
+            heatmaps.push(heatmap);
         }
+        records[i].set_result(result);
+        records[i].set_conflicts(&conflicts);
+        records[i].classes = classes;
+    }
+
+    if let (Some(path), Some(plan)) = (matches.value_of("heatmap"), plan) {
+        let heatmap = Heatmap::average(&heatmaps)
+            .ok_or("No heatmap, as simulations failed or differed in size")?;
+        save_heatmap(matches, Path::new(path), &heatmap, &plan)?;
+    }
+    print_records(&records, options.format)
+}
+
+/// Writes a heatmap to a .csv file, or draws it over `aircraft`'s layout in
+/// any other format supported by `export`.
+fn save_heatmap(matches: &ArgMatches, path: &Path, heatmap: &Heatmap,
+                aircraft: &Aircraft) -> Result<(), String> {
+    if path.extension().and_then(|e| e.to_str()) == Some("csv") {
+        write_heatmap(heatmap, path)?;
+    } else {
+        let counter = str_to_counter(matches.value_of("counter").unwrap())
+            .ok_or("Invalid heatmap counter")?;
+        export::write_heatmap(aircraft, heatmap, counter, path,
+                              &export::Sprites::bundled())?;
+    }
+    log::info!("Wrote heatmap to {}", path.display());
+    Ok(())
+}
+
+fn generate_file(matches: &ArgMatches) -> Result<(), String> {
+    let (size_x, size_y) = parse_size(matches.value_of("size").unwrap())?;
+    let output = Path::new(matches.value_of("output").unwrap());
+
+    match matches.value_of("kind") {
+        Some("layout") => {
+            let aircraft = match matches.value_of("aircraft") {
+                Some(aircraft) => load_layout(aircraft)?,
+                None => standard_layout(size_x, size_y)?,
+            };
+            write_layout(&aircraft, output)?;
+        },
+        _ => {
+            let strategy = matches.value_of("strategy").unwrap();
+            let load_factor: f32 = matches.value_of("load_factor").unwrap()
+                .parse().map_err(|_| "Invalid load factor")?;
+            let aircraft = match matches.value_of("aircraft") {
+                Some(aircraft) => Some(load_layout(aircraft)?),
+                None => None,
+            };
+            let passengers = match &aircraft {
+                Some(aircraft) => generate_for(aircraft, strategy,
+                                               load_factor)?,
+                None => generate(strategy, size_x, size_y, load_factor)?,
+            };
+            let passengers = aisle_chairs(matches, passengers)?;
+            let passengers = apply_options(passengers,
+                                           &passenger_options(matches)?)?;
+            let passengers = wrong_seats(matches, passengers)?;
+            if matches.is_present("labels") {
+                let aircraft = match aircraft {
+                    Some(aircraft) => aircraft,
+                    None => standard_layout(size_x, size_y)?,
+                };
+                write_labelled_passengers(&aircraft, &passengers, output)?;
+            } else {
+                write_passengers(&passengers, output)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+fn list_aircraft() -> Result<(), String> {
+    for (name, spec) in seat_map::BUNDLED.iter() {
+        let aircraft = seat_map::bundled(name)
+            .ok_or_else(|| format!("Invalid bundled aircraft '{}'", name))?;
+        let (size_x, size_y) = aircraft.get_size();
+        let seats = (0..size_x)
+            .flat_map(|x| (0..size_y).map(move |y| (x, y)))
+            .filter(|(x, y)| aircraft.get_tile_variant(*x, *y) == Variant::Seat)
+            .count();
+        println!("{:<10} {:>3} seats  {}", name, seats, spec);
+    }
+    Ok(())
+}
+
+fn validate_files(matches: &ArgMatches) -> Result<(), String> {
+    let aircraft = load_layout(matches.value_of("layout").unwrap())?;
+    let (passengers, diagnostics) = match matches.value_of("passengers") {
+        Some(path) => {
+            let reader = passengers::PassengerReader::open(Path::new(path))
+                .map_err(|e| e.to_string())?;
+            if matches.is_present("strict") {
+                (reader.strict().map_err(|e| e.to_string())?, Vec::new())
+            } else {
+                reader.lenient()
+            }
+        },
+        None => (Vec::new(), Vec::new()),
+    };
+
+    let mut problems: Vec<String> = diagnostics.iter()
+        .map(|d| d.to_string())
+        .collect();
+    problems.extend(validate(&aircraft, &passengers));
+    if problems.is_empty() {
+        println!("No problems found");
+        Ok(())
     } else {
-        println!("Invalid files given as input; exiting");
+        for problem in &problems {
+            println!("{}", problem);
+        }
+        Err(format!("{} problems found", problems.len()))
     }
 }
+
+fn render(matches: &ArgMatches) -> Result<(), String> {
+    let (mut aircraft, _) = load(matches,
+                                 matches.value_of("layout").unwrap(),
+                                 matches.value_of("passengers").unwrap())?;
+    let delay: u64 = matches.value_of("delay").unwrap()
+        .parse().map_err(|_| "Invalid delay")?;
+    let options = RenderOptions {
+        labels: matches.is_present("labels"),
+        ..RenderOptions::default()
+    };
+
+    loop {
+        println!("Step {}", aircraft.get_step());
+        aircraft.render(&mut std::io::stdout(), &options)
+            .map_err(|e| e.to_string())?;
+        if aircraft.is_complete() {
+            break;
+        }
+        if aircraft.get_step() >= aircraft::MAX_ITERATIONS {
+            return Err("Passengers could not all be seated.".to_string());
+        }
+        aircraft.update();
+        thread::sleep(Duration::from_millis(delay));
+    }
+    Ok(())
+}
+
+fn play(matches: &ArgMatches) -> Result<(), String> {
+    let (aircraft, _) = load(matches,
+                             matches.value_of("layout").unwrap(),
+                             matches.value_of("passengers").unwrap())?;
+    let delay: u64 = matches.value_of("delay").unwrap()
+        .parse().map_err(|_| "Invalid delay")?;
+
+    tui::play(aircraft, delay)
+}
+
+fn export_frames(matches: &ArgMatches) -> Result<(), String> {
+    let (mut aircraft, _) = load(matches,
+                                 matches.value_of("layout").unwrap(),
+                                 matches.value_of("passengers").unwrap())?;
+    let delay: u32 = matches.value_of("delay").unwrap()
+        .parse().map_err(|_| "Invalid delay")?;
+    let format = export::str_to_format(matches.value_of("image-format")
+                                       .unwrap())
+        .ok_or("Invalid image format")?;
+    let sprites = match matches.value_of("images") {
+        Some(dir) => export::Sprites::load(Path::new(dir))?,
+        None => export::Sprites::bundled(),
+    };
+    let overlays = export::Overlays {
+        step: matches.is_present("step"),
+        zones: matches.is_present("zones"),
+        ..export::Overlays::default()
+    };
+
+    let output = matches.value_of("output").unwrap();
+    let steps = export::export(&mut aircraft, Path::new(output), format,
+                               &sprites, &overlays, delay)?;
+    println!("Wrote {} steps to {}", steps, output);
+    Ok(())
+}
@@ -0,0 +1,328 @@
+//! Reads aircraft definitions, a richer alternative to csv layouts that also
+//! describes an aircraft's name, seat labels, cabin classes, legroom, exits,
+//! overhead bins and aisles.
+//!
+//! Definitions can be written as JSON or TOML. Each row of the cabin lists its
+//! tiles as a string, using the same characters as `Aircraft::render`:
+//!
+//! ```toml
+//! name = "Example"
+//! columns = "AB CD"
+//!
+//! [[rows]]
+//! number = 1
+//! class = "business"
+//! bin_capacity = 4
+//! exit = true
+//! legroom = "premium"
+//! aisle_width = 2
+//! tiles = "##.##"
+//!
+//! [[rows]]
+//! tiles = "L-E-G"
+//!
+//! [[exits]]
+//! name = "L1"
+//! x = 2
+//! y = 1
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::super::aircraft::Aircraft;
+use super::super::aircraft::cabin::{Aisle, Cabin, Class, Exit, Row};
+use super::super::aircraft::render::Glyphs;
+use super::super::aircraft::tile::{Legroom, Variant};
+
+/// A single row of an aircraft definition, from the front of the aircraft.
+///
+/// `legroom` is given to every seat on the row, which otherwise has standard
+/// legroom, and `aisle_width` to every aisle on the row.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct RowDefinition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<Class>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bin_capacity: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legroom: Option<Legroom>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aisle_width: Option<u16>,
+    pub tiles: String,
+}
+
+/// An aircraft as written in a definition file.
+///
+/// Every row must have the same number of tiles. Any `exits` are placed as
+/// entrances, in addition to those already drawn in the rows.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct Definition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub columns: String,
+    pub rows: Vec<RowDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exits: Vec<Exit>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aisles: Vec<Aisle>,
+}
+
+impl Definition {
+    pub fn from_json(text: &str) -> Result<Definition, &'static str> {
+        serde_json::from_str(text).map_err(|e| {
+            log::error!("Invalid aircraft definition: {}", e);
+            "Invalid aircraft definition"
+        })
+    }
+
+    pub fn from_toml(text: &str) -> Result<Definition, &'static str> {
+        toml::from_str(text).map_err(|e| {
+            log::error!("Invalid aircraft definition: {}", e);
+            "Invalid aircraft definition"
+        })
+    }
+
+    /// Describes an aircraft's layout and cabin as a definition.
+    pub fn from_aircraft(aircraft: &Aircraft) -> Definition {
+        let glyphs = Glyphs::default();
+        let cabin = aircraft.get_cabin();
+        let (size_x, size_y) = aircraft.get_size();
+        let rows = (0..size_y)
+            .map(|y| {
+                let row = cabin.rows.get(y as usize).cloned()
+                    .unwrap_or_default();
+                // Rows take the legroom of their first seat
+                let legroom = (0..size_x)
+                    .find(|x| aircraft.get_tile_variant(*x, y) == Variant::Seat)
+                    .map(|x| aircraft.get_legroom(x, y))
+                    .filter(|legroom| *legroom != Legroom::Standard);
+                RowDefinition {
+                    number: row.number,
+                    class: row.class,
+                    bin_capacity: row.bin_capacity,
+                    legroom,
+                    aisle_width: row.aisle_width,
+                    tiles: (0..size_x)
+                        .map(|x| glyphs.empty(aircraft.get_tile_variant(x, y)))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Definition {
+            name: cabin.name.clone(),
+            columns: cabin.columns.clone(),
+            rows,
+            exits: cabin.exits.clone(),
+            aisles: cabin.aisles.clone(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, &'static str> {
+        serde_json::to_string_pretty(self)
+            .map_err(|_| "Couldn't write aircraft definition")
+    }
+
+    pub fn to_toml(&self) -> Result<String, &'static str> {
+        toml::to_string(self).map_err(|_| "Couldn't write aircraft definition")
+    }
+
+    /// Builds the aircraft this definition describes.
+    pub fn to_aircraft(&self) -> Result<Aircraft, &'static str> {
+        let size_x = self.rows.first()
+            .ok_or("Aircraft definition has no rows")?
+            .tiles.chars().count();
+        if self.rows.iter().any(|row| row.tiles.chars().count() != size_x) {
+            return Err("Every row must have the same number of tiles");
+        }
+        let size_y = self.rows.len();
+        if size_x > u16::MAX as usize || size_y > u16::MAX as usize {
+            return Err("Aircraft definition is too large");
+        }
+
+        let glyphs = Glyphs::default();
+        let mut aircraft = Aircraft::new(size_x as u16, size_y as u16);
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, c) in row.tiles.chars().enumerate() {
+                let variant = glyphs.variant(c).ok_or("Unknown tile in row")?;
+                aircraft.set_tile(x as u16, y as u16, variant);
+                if let Some(legroom) = row.legroom {
+                    aircraft.set_legroom(x as u16, y as u16, legroom);
+                }
+            }
+        }
+        for exit in &self.exits {
+            if exit.x as usize >= size_x || exit.y as usize >= size_y {
+                return Err("Exit is outside the aircraft");
+            }
+            aircraft.set_tile(exit.x, exit.y, Variant::Entrance);
+        }
+
+        aircraft.set_cabin(Cabin {
+            name: self.name.clone(),
+            columns: self.columns.clone(),
+            rows: self.rows.iter()
+                .map(|row| Row {
+                    number: row.number,
+                    class: row.class,
+                    bin_capacity: row.bin_capacity,
+                    aisle_width: row.aisle_width,
+                })
+                .collect(),
+            exits: self.exits.clone(),
+            aisles: self.aisles.clone(),
+        });
+        Ok(aircraft)
+    }
+}
+
+/// Reads an aircraft definition from a .json or .toml file.
+pub fn read_definition(path: &Path) -> Result<Aircraft, &'static str> {
+    let text = fs::read_to_string(path)
+        .map_err(|_| "Couldn't read aircraft definition")?;
+    let definition = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => Definition::from_json(&text)?,
+        Some("toml") => Definition::from_toml(&text)?,
+        _ => return Err("Aircraft definitions must be .json or .toml"),
+    };
+    definition.to_aircraft()
+}
+
+/// Writes an aircraft's layout and cabin to a .json or .toml file in the format
+/// read by `read_definition`.
+pub fn write_definition(aircraft: &Aircraft, path: &Path)
+    -> Result<(), &'static str> {
+    let definition = Definition::from_aircraft(aircraft);
+    let text = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => definition.to_json()?,
+        Some("toml") => definition.to_toml()?,
+        _ => return Err("Aircraft definitions must be .json or .toml"),
+    };
+    fs::write(path, text).map_err(|_| "Couldn't write aircraft definition")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+name = \"Example\"
+columns = \"AB CD\"
+
+[[rows]]
+number = 1
+class = \"business\"
+bin_capacity = 4
+tiles = \"##.##\"
+
+[[rows]]
+number = 2
+legroom = \"extra\"
+aisle_width = 3
+tiles = \"##.##\"
+
+[[rows]]
+tiles = \"  .  \"
+
+[[exits]]
+name = \"L1\"
+x = 2
+y = 2
+
+[[aisles]]
+x = 2
+width = 2
+";
+
+    #[test]
+    fn toml_definition() {
+        let aircraft = Definition::from_toml(EXAMPLE).unwrap()
+            .to_aircraft().unwrap();
+        assert_eq!(aircraft.get_size(), (5, 3));
+        assert_eq!(aircraft.get_tile_variant(0, 0), Variant::Seat);
+        assert_eq!(aircraft.get_tile_variant(2, 1), Variant::Aisle);
+        assert_eq!(aircraft.get_tile_variant(0, 2), Variant::None);
+        assert_eq!(aircraft.get_tile_variant(2, 2), Variant::Entrance);
+        assert_eq!(aircraft.get_legroom(0, 0), Legroom::Standard);
+        assert_eq!(aircraft.get_legroom(4, 1), Legroom::Extra);
+
+        let cabin = aircraft.get_cabin();
+        assert_eq!(cabin.name, Some("Example".to_string()));
+        assert_eq!(aircraft.seat_label(4, 1), Some("2D".to_string()));
+        assert_eq!(cabin.get_class(0), Some(Class::Business));
+        assert_eq!(cabin.rows[0].bin_capacity, Some(4));
+        assert_eq!(cabin.exits[0].name, Some("L1".to_string()));
+        assert_eq!(cabin.aisle_width(2, 0), 2);
+        assert_eq!(cabin.aisle_width(2, 1), 3);
+    }
+
+    #[test]
+    fn json_definition() {
+        let toml = Definition::from_toml(EXAMPLE).unwrap();
+        let json = serde_json::to_string(&toml).unwrap();
+        assert_eq!(Definition::from_json(&json).unwrap(), toml);
+
+        let minimal = "{\"rows\": [{\"tiles\": \"#.E\"}]}";
+        let minimal = Definition::from_json(minimal).unwrap()
+            .to_aircraft().unwrap();
+        assert_eq!(minimal.get_size(), (3, 1));
+        assert_eq!(minimal.get_cabin().name, None);
+    }
+
+    #[test]
+    fn invalid_definitions() {
+        let uneven = "{\"rows\": [{\"tiles\": \"#.#\"}, {\"tiles\": \"#.\"}]}";
+        assert!(Definition::from_json(uneven).unwrap().to_aircraft().is_err());
+
+        let unknown = "{\"rows\": [{\"tiles\": \"#?#\"}]}";
+        assert!(Definition::from_json(unknown).unwrap().to_aircraft().is_err());
+
+        let exit = "{\"rows\": [{\"tiles\": \"#.#\"}], \
+                     \"exits\": [{\"x\": 5, \"y\": 0}]}";
+        assert!(Definition::from_json(exit).unwrap().to_aircraft().is_err());
+
+        assert!(Definition::from_json("{\"rows\": []}").unwrap()
+                .to_aircraft().is_err());
+        assert!(Definition::from_toml("rows = 3").is_err());
+    }
+
+    #[test]
+    fn matches_csv() {
+        let csv = super::super::read_layout(
+            Path::new("./config/standard_layout.csv")).unwrap();
+        let definition = read_definition(
+            Path::new("./config/standard_layout.toml")).unwrap();
+
+        assert_eq!(definition.get_size(), csv.get_size());
+        let (size_x, size_y) = csv.get_size();
+        for x in 0..size_x {
+            for y in 0..size_y {
+                assert_eq!(definition.get_tile_variant(x, y),
+                           csv.get_tile_variant(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn write_read() {
+        let aircraft = Definition::from_toml(EXAMPLE).unwrap()
+            .to_aircraft().unwrap();
+        for extension in ["json", "toml"] {
+            let path = std::env::temp_dir()
+                .join(format!("aircraft_sim_definition.{}", extension));
+            write_definition(&aircraft, &path).unwrap();
+            let read = read_definition(&path).unwrap();
+            assert_eq!(read.get_cabin(), aircraft.get_cabin());
+            assert_eq!(Definition::from_aircraft(&read),
+                       Definition::from_aircraft(&aircraft));
+        }
+        assert!(write_definition(&aircraft, Path::new("aircraft.csv"))
+                .is_err());
+    }
+}
@@ -1,30 +1,79 @@
 //! Handles configuration such as reading from files and generating boarding
 //! patterns.
 
+pub mod definition;
+pub mod passengers;
+pub mod seat_map;
+
+use std::cell::RefCell;
 use std::fs::File;
 use std::path::Path;
 
-use super::aircraft::Aircraft;
-use super::aircraft::tile::Variant;
+use super::aircraft::{Aircraft, Deplaning, Overtaking};
+use super::aircraft::tile::{str_to_legroom, Legroom, Variant};
+use super::aircraft::cabin::{Class, SeatMap};
+use super::aircraft::heatmap::{Counter, Heatmap};
 use super::aircraft::person::Person;
+use super::aircraft::policy::policy_name;
 
-use rand::thread_rng;
+use rand::{Rng, RngCore, SeedableRng};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Seeds the random number generator used on this thread, making any boarding
+/// patterns generated afterwards reproducible.
+pub fn seed(seed: u64) {
+    log::info!("Seeding random number generator with {}", seed);
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// A handle to this thread's random number generator.
+struct ThreadRng;
+
+impl RngCore for ThreadRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+    }
+}
+
+/// Returns a handle to this thread's random number generator, which can be
+/// made deterministic with `seed`.
+fn thread_rng() -> ThreadRng {
+    ThreadRng
+}
+
 /// Temporary holder for data about seats when reading from files.
 struct seat_data {
     seat_x: u16,
     seat_y: u16,
     variant: Variant,
+    legroom: Legroom,
 }
 
 impl seat_data {
     /// Constructor
-    fn new(x: u16, y: u16, variant: &str) -> seat_data {
+    fn new(x: u16, y: u16, variant: Variant, legroom: Legroom) -> seat_data {
         seat_data {
             seat_x: x,
             seat_y: y,
-            variant: str_to_var(variant),
+            variant,
+            legroom,
         }
     }
 
@@ -39,18 +88,64 @@ impl seat_data {
     fn get_variant(&self) -> Variant {
         self.variant
     }
+
+    fn get_legroom(&self) -> Legroom {
+        self.legroom
+    }
 }
 
 /// Converts a string into an associated Variant.
 ///
 /// This is for use when calling Rust code from Python, as Python does not share
 /// the same enums.
-fn str_to_var(var: &str) -> Variant {
+fn str_to_var(var: &str) -> Option<Variant> {
     match var {
-        "aisle" => Variant::Aisle,
-        "seat" => Variant::Seat,
-        "entrance" => Variant::Entrance,
-        _ => Variant::None, // Shouldn't happen
+        "aisle" => Some(Variant::Aisle),
+        "seat" => Some(Variant::Seat),
+        "entrance" => Some(Variant::Entrance),
+        "none" => Some(Variant::None),
+        "galley" => Some(Variant::Galley),
+        "lavatory" => Some(Variant::Lavatory),
+        "bulkhead" => Some(Variant::Bulkhead),
+        "exit_row" => Some(Variant::ExitRow),
+        "cross_aisle" => Some(Variant::CrossAisle),
+        "seat_span" => Some(Variant::SeatSpan),
+        _ => None,
+    }
+}
+
+/// Converts a string into an associated deplaning strategy.
+pub fn str_to_deplaning(strategy: &str) -> Option<Deplaning> {
+    match strategy {
+        "row_by_row" => Some(Deplaning::RowByRow),
+        "free_for_all" => Some(Deplaning::FreeForAll),
+        "aisle_first" => Some(Deplaning::AisleFirst),
+        _ => None,
+    }
+}
+
+/// Converts a string into the associated rules for overtaking in an aisle.
+pub fn str_to_overtaking(overtaking: &str) -> Option<Overtaking> {
+    match overtaking {
+        "never" => Some(Overtaking::Never),
+        "wide" => Some(Overtaking::Wide),
+        _ => None,
+    }
+}
+
+/// Converts a Variant into the string used for it in layout files.
+pub fn var_to_str(var: Variant) -> &'static str {
+    match var {
+        Variant::Aisle => "aisle",
+        Variant::Seat => "seat",
+        Variant::Entrance => "entrance",
+        Variant::None => "none",
+        Variant::Galley => "galley",
+        Variant::Lavatory => "lavatory",
+        Variant::Bulkhead => "bulkhead",
+        Variant::ExitRow => "exit_row",
+        Variant::CrossAisle => "cross_aisle",
+        Variant::SeatSpan => "seat_span",
     }
 }
 
@@ -83,7 +178,7 @@ pub fn standard_layout(mut size_x: u16, size_y: u16)
 
 /// Generates a list of passengers that will board in standard back-first order
 /// with randomised positions on each row.
-pub fn random_back_first(mut size_x: u16, size_y: u16)
+pub fn random_back_first(mut size_x: u16, size_y: u16, load_factor: f32)
     -> Result<Vec<Person>, &'static str> {
 
     log::info!("Generating random back-first boarding pattern");
@@ -102,17 +197,18 @@ pub fn random_back_first(mut size_x: u16, size_y: u16)
                 let mut person = Person::new("DEFAULT");
                 person.target_seat(x, size_y - (y + 1));
                 person.set_baggage(true);
+                person.set_zone(y);
                 persons.push(person);
             }
         }
     }
 
-    Ok(persons)
+    apply_load_factor(persons, load_factor)
 }
 
 /// Generates a list of passengers that will board in standard front-first order
 /// with randomised positions on each row.
-pub fn random_front_first(mut size_x: u16, size_y: u16)
+pub fn random_front_first(mut size_x: u16, size_y: u16, load_factor: f32)
     -> Result<Vec<Person>, &'static str> {
 
     log::info!("Generating random front-first boarding pattern");
@@ -131,17 +227,18 @@ pub fn random_front_first(mut size_x: u16, size_y: u16)
                 let mut person = Person::new("DEFAULT");
                 person.target_seat(x, y);
                 person.set_baggage(true);
+                person.set_zone(y);
                 persons.push(person);
             }
         }
     }
 
-    Ok(persons)
+    apply_load_factor(persons, load_factor)
 }
 
 /// Generates a list of passengers that will board in standard window-first
 /// order with randomised positions on each row.
-pub fn random_window_first(mut size_x: u16, size_y: u16)
+pub fn random_window_first(mut size_x: u16, size_y: u16, load_factor: f32)
     -> Result<Vec<Person>, &'static str> {
 
     log::info!("Generating random aisle-first boarding pattern");
@@ -163,18 +260,20 @@ pub fn random_window_first(mut size_x: u16, size_y: u16)
             person0.set_baggage(true);
             person1.target_seat(aisle + (x + 1), y);
             person1.set_baggage(true);
+            person0.set_zone(x);
+            person1.set_zone(x);
             
             persons.push(person0);
             persons.push(person1);
         }
     }
 
-    Ok(persons)
+    apply_load_factor(persons, load_factor)
 }
 
 /// Generates a list of passengers that will board in standard aisle-first
 /// order with randomised positions on each row.
-pub fn random_aisle_first(mut size_x: u16, size_y: u16)
+pub fn random_aisle_first(mut size_x: u16, size_y: u16, load_factor: f32)
     -> Result<Vec<Person>, &'static str> {
 
     log::info!("Generating random aisle-first boarding pattern");
@@ -196,17 +295,20 @@ pub fn random_aisle_first(mut size_x: u16, size_y: u16)
             person0.set_baggage(true);
             person1.target_seat(size_x - (x + 1), y);
             person1.set_baggage(true);
+            person0.set_zone(x);
+            person1.set_zone(x);
             
             persons.push(person0);
             persons.push(person1);
         }
     }
 
-    Ok(persons)
+    apply_load_factor(persons, load_factor)
 }
 
 /// Generates a list of passengers that will board in completely random order.
-pub fn random(mut size_x: u16, size_y: u16) -> Result<Vec<Person>, &'static str> {
+pub fn random(mut size_x: u16, size_y: u16, load_factor: f32)
+    -> Result<Vec<Person>, &'static str> {
     log::info!("Generating random aisle-first boarding pattern");
     if size_x % 2 == 0 {
         log::warn!("Invalid size_x value; assuming closest odd value.");
@@ -235,48 +337,505 @@ pub fn random(mut size_x: u16, size_y: u16) -> Result<Vec<Person>, &'static str>
         persons.push(person);
     }
 
-    Ok(persons)
+    apply_load_factor(persons, load_factor)
 }
 
-/// Reads a list of passengers from a correctly formatted csv file and returns
-/// them as a vector of `Person` objects.
-pub fn read_passengers(path: &Path) -> Option<Vec<Person>> {
+/// Names of the boarding patterns that `generate` can produce.
+pub const STRATEGIES: [&str; 5] = ["back_first",
+                                   "front_first",
+                                   "window_first",
+                                   "aisle_first",
+                                   "random"];
+
+/// Generates a list of passengers using the boarding pattern named `strategy`,
+/// one of `STRATEGIES`.
+///
+/// Every generator except `random` calls passengers in groups, either a row or
+/// a pair of seat columns at a time, and gives each group its own boarding
+/// zone starting from 0.
+pub fn generate(strategy: &str, size_x: u16, size_y: u16, load_factor: f32)
+    -> Result<Vec<Person>, &'static str> {
+    match strategy {
+        "back_first" => random_back_first(size_x, size_y, load_factor),
+        "front_first" => random_front_first(size_x, size_y, load_factor),
+        "window_first" => random_window_first(size_x, size_y, load_factor),
+        "aisle_first" => random_aisle_first(size_x, size_y, load_factor),
+        "random" => random(size_x, size_y, load_factor),
+        _ => Err("Unknown boarding strategy"),
+    }
+}
+
+/// Generates a list of passengers for every seat of `aircraft`, whatever its
+/// layout.
+///
+/// Premium cabin classes board first, a class at a time starting with first
+/// class, in random order within each class. Everybody else follows in the
+/// order set by `strategy`, one of `STRATEGIES`, with rows measured from the
+/// nearest entrance and seats from the nearest aisle. Each group is given its
+/// own boarding zone, numbered from 0 in the order the groups board.
+pub fn generate_for(aircraft: &Aircraft, strategy: &str, load_factor: f32)
+    -> Result<Vec<Person>, &'static str> {
+    if !STRATEGIES.contains(&strategy) {
+        return Err("Unknown boarding strategy");
+    }
+    log::info!("Generating {} boarding pattern with premium classes first",
+               strategy);
+
+    let (size_x, size_y) = aircraft.get_size();
+    let mut seats = Vec::<((Class, u16), (u16, u16))>::new();
+    for x in 0..size_x {
+        for y in 0..size_y {
+            if aircraft.get_tile_variant(x, y) != Variant::Seat {
+                continue;
+            }
+            let seat = (x, y);
+            let key = match aircraft.get_class(x, y) {
+                Some(class) if class.is_premium() => (class, 0),
+                _ => {
+                    let rows = (aircraft.nearest_exit(seat).1 as i32
+                                - y as i32).unsigned_abs() as u16;
+                    let from_aisle = aircraft.seats_from_aisle(seat);
+                    (Class::Economy, match strategy {
+                        "back_first" => u16::MAX - rows,
+                        "front_first" => rows,
+                        "window_first" => u16::MAX - from_aisle,
+                        "aisle_first" => from_aisle,
+                        _ => 0,
+                    })
+                },
+            };
+            seats.push((key, seat));
+        }
+    }
+
+    // Shuffling before a stable sort leaves each group in random order
+    seats.shuffle(&mut thread_rng());
+    seats.sort_by_key(|(key, _)| *key);
+
     let mut persons = Vec::<Person>::new();
-    let file = File::open(path).expect("Invalid file path");
-    let mut rdr = csv::Reader::from_reader(file);
-    for result in rdr.records() {
-        let record = result.expect("Result is invalid record");
-        let mut data = Person::new(
-            &record[0],
-        );
-        data.target_seat(record[1].parse().expect("Invalid x coord"),
-                         record[2].parse().expect("Invalid y coord"));
-        data.set_baggage(match &record[3] {
-            "0" => false,
-            "1" => true,
-            _ => {
-                log::error!("Invalid baggage value, defaulting to 0");
-                false
+    let mut zone: u16 = 0;
+    for (i, (key, seat)) in seats.iter().enumerate() {
+        if i > 0 && seats[i - 1].0 != *key {
+            zone += 1;
+        }
+        let mut person = Person::new("DEFAULT");
+        person.target_seat(seat.0, seat.1);
+        person.set_baggage(true);
+        person.set_zone(zone);
+        persons.push(person);
+    }
+    // The last passenger in the list is the first to board
+    persons.reverse();
+
+    apply_load_factor(persons, load_factor)
+}
+
+/// Randomly removes passengers from a boarding order so that only
+/// `load_factor` of its seats are sold, leaving the rest empty.
+///
+/// The relative order of the remaining passengers is preserved so that the
+/// boarding pattern is still followed.
+pub fn apply_load_factor(persons: Vec<Person>, load_factor: f32)
+    -> Result<Vec<Person>, &'static str> {
+
+    if !(0.0..=1.0).contains(&load_factor) {
+        return Err("Load factor must be between 0 and 1");
+    }
+
+    let sold = (persons.len() as f32 * load_factor).round() as usize;
+    log::info!("Filling {} of {} seats", sold, persons.len());
+
+    Ok(remove_random(persons, sold).0)
+}
+
+/// Removes a random share of passengers from an already generated boarding
+/// order to simulate no-shows.
+///
+/// Returns the passengers who did show up, in their original order, along
+/// with the passengers who didn't. The seats of no-shows are left empty and
+/// are not required to be filled for boarding to be complete.
+pub fn remove_no_shows(persons: Vec<Person>, rate: f32)
+    -> Result<(Vec<Person>, Vec<Person>), &'static str> {
+
+    if !(0.0..=1.0).contains(&rate) {
+        return Err("No-show rate must be between 0 and 1");
+    }
+
+    let shown = persons.len()
+        - (persons.len() as f32 * rate).round() as usize;
+    let (persons, no_shows) = remove_random(persons, shown);
+    log::info!("{} passengers did not show up", no_shows.len());
+
+    Ok((persons, no_shows))
+}
+
+/// Applies a compliance model on top of a boarding order.
+///
+/// `rate` of the passengers don't follow the called order: each of them is
+/// either displaced in the queue by up to `max_shift` places in either
+/// direction, as if boarding with the wrong group, or arrives at the door up
+/// to `max_delay` steps later than they otherwise would. Passing 0 for either
+/// disables that behaviour.
+pub fn apply_compliance(mut persons: Vec<Person>,
+                        rate: f32,
+                        max_shift: usize,
+                        max_delay: u16) -> Result<Vec<Person>, &'static str> {
+
+    if !(0.0..=1.0).contains(&rate) {
+        return Err("Compliance rate must be between 0 and 1");
+    }
+    if persons.is_empty() || (max_shift == 0 && max_delay == 0) {
+        return Ok(persons);
+    }
+
+    let mut rng = thread_rng();
+    let count = (persons.len() as f32 * rate).round() as usize;
+    let mut indices: Vec<usize> = (0..persons.len()).collect();
+    indices.shuffle(&mut rng);
+    indices.truncate(count);
+
+    let (mut shifted, mut delayed) = (0, 0);
+    for i in indices {
+        if max_delay == 0 || (max_shift > 0 && rng.gen_bool(0.5)) {
+            let offset = rng.gen_range(0..=max_shift * 2) as isize
+                - max_shift as isize;
+            let new_index = (i as isize + offset)
+                .max(0)
+                .min(persons.len() as isize - 1) as usize;
+            let person = persons.remove(i);
+            persons.insert(new_index, person);
+            shifted += 1;
+        } else {
+            let arrival = persons[i].get_arrival()
+                .saturating_add(rng.gen_range(1..=max_delay));
+            persons[i].set_arrival(arrival);
+            delayed += 1;
+        }
+    }
+    log::info!("{} passengers displaced, {} arriving late", shifted, delayed);
+
+    Ok(persons)
+}
+
+/// Schedules passengers to arrive at the gate as a Poisson process with an
+/// average of `rate` passengers per step.
+///
+/// Arrivals follow the boarding order, so the first passenger to board is
+/// also the first to arrive.
+pub fn poisson_arrivals(mut persons: Vec<Person>, rate: f32)
+    -> Result<Vec<Person>, &'static str> {
+
+    if rate <= 0.0 {
+        return Err("Arrival rate must be positive");
+    }
+
+    let mut rng = thread_rng();
+    let mut time: f32 = 0.0;
+    // Passengers board from the back of the list.
+    for person in persons.iter_mut().rev() {
+        person.set_arrival(time.min(u16::MAX as f32) as u16);
+        time += -(1.0 - rng.gen::<f32>()).ln() / rate;
+    }
+
+    Ok(persons)
+}
+
+/// Groups passengers in a boarding order into travelling parties who sit
+/// together.
+///
+/// Roughly `share` of the passengers are placed into parties of between 2 and
+/// `max_size` members, made up of passengers seated next to each other on the
+/// same row. Each party is moved to board together at the point in the order
+/// where its first member would have boarded, so the chosen boarding pattern
+/// is otherwise still followed.
+pub fn form_parties(persons: Vec<Person>, share: f32, max_size: u16)
+    -> Result<Vec<Person>, &'static str> {
+
+    if !(0.0..=1.0).contains(&share) {
+        return Err("Party share must be between 0 and 1");
+    }
+    if max_size < 2 {
+        return Err("Parties must be able to hold at least two passengers");
+    }
+
+    let mut rng = thread_rng();
+    // Passengers board from the back of the list, so work in boarding order.
+    let mut persons: Vec<Person> = persons.into_iter().rev().collect();
+    let mut parties: Vec<Vec<usize>> = Vec::new();
+    let mut assigned = vec![false; persons.len()];
+    let mut grouped: usize = 0;
+
+    for i in 0..persons.len() {
+        if assigned[i] || persons[i].get_seat().is_none()
+            || (grouped as f32) >= share * persons.len() as f32 {
+            continue;
+        }
+        let size = rng.gen_range(2..=max_size) as usize;
+        let (x, y) = persons[i].get_seat().unwrap();
+        let mut members = vec![i];
+
+        // Grow the party outwards along the row from its first member.
+        for direction in &[1i32, -1] {
+            let mut next_x = x as i32 + direction;
+            while members.len() < size && next_x >= 0 {
+                let neighbour = persons.iter().enumerate().position(|(j, p)| {
+                    !assigned[j] && p.get_seat() == Some((next_x as u16, y))
+                });
+                match neighbour {
+                    Some(j) if !members.contains(&j) => members.push(j),
+                    _ => break,
+                }
+                next_x += direction;
+            }
+        }
+
+        if members.len() > 1 {
+            for j in &members {
+                assigned[*j] = true;
+                persons[*j].set_party(Some(parties.len() as u16));
+            }
+            grouped += members.len();
+            members.sort_unstable();
+            parties.push(members);
+        }
+    }
+    log::info!("Formed {} parties from {} passengers",
+               parties.len(), grouped);
+
+    // Rebuild the order, letting each party board when its first member would
+    // have.
+    let mut order: Vec<usize> = Vec::new();
+    for (i, person) in persons.iter().enumerate() {
+        match person.get_party() {
+            Some(party) if parties[party as usize][0] == i => {
+                order.extend(&parties[party as usize]);
             },
-        });
-        persons.push(data);
+            Some(_) => {},
+            None => order.push(i),
+        }
     }
-    return Some(persons);
+
+    let mut slots: Vec<Option<Person>> = persons.into_iter().map(Some).collect();
+    Ok(order.into_iter()
+       .rev()
+       .map(|i| slots[i].take().unwrap())
+       .collect())
+}
+
+/// Changes applied to a boarding order after it is generated, each of which
+/// leaves the order alone at its default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PassengerOptions {
+    /// Share of passengers who don't follow the called order.
+    pub late: f32,
+    /// Most places a passenger boarding with the wrong group is displaced by.
+    pub max_shift: usize,
+    /// Most steps later than they otherwise would a late passenger arrives.
+    pub max_delay: u16,
+    /// Average number of passengers arriving at the gate each step, if they
+    /// don't all arrive before boarding begins.
+    pub arrival_rate: Option<f32>,
+    /// Share of passengers travelling in parties who sit together.
+    pub parties: f32,
+    /// Most passengers in a party.
+    pub max_party: u16,
+}
+
+impl Default for PassengerOptions {
+    fn default() -> Self {
+        PassengerOptions {
+            late: 0.0,
+            max_shift: 5,
+            max_delay: 30,
+            arrival_rate: None,
+            parties: 0.0,
+            max_party: 4,
+        }
+    }
+}
+
+/// Applies every change given by `options` to a generated boarding order.
+pub fn apply_options(persons: Vec<Person>, options: &PassengerOptions)
+    -> Result<Vec<Person>, &'static str> {
+    let persons = match options.parties {
+        share if share > 0.0 => form_parties(persons, share,
+                                             options.max_party)?,
+        _ => persons,
+    };
+    let persons = match options.arrival_rate {
+        Some(rate) => poisson_arrivals(persons, rate)?,
+        None => persons,
+    };
+    apply_compliance(persons, options.late, options.max_shift,
+                     options.max_delay)
+}
+
+/// Seats `count` randomly chosen passengers in aisle chairs and moves them to
+/// board before everybody else, or after everybody else if `last` is set.
+///
+/// Passengers in aisle chairs take up two tiles of the aisle, can't be
+/// squeezed past, and take a while to move into their seats, so airlines
+/// usually board them separately from everybody else.
+pub fn assign_aisle_chairs(persons: Vec<Person>, count: usize, last: bool)
+    -> Result<Vec<Person>, &'static str> {
+
+    if count > persons.len() {
+        return Err("More aisle chairs than passengers");
+    }
+    if count == 0 {
+        return Ok(persons);
+    }
+
+    let keep = persons.len() - count;
+    let (mut persons, mut chairs) = remove_random(persons, keep);
+    for person in chairs.iter_mut() {
+        person.set_aisle_chair(true);
+    }
+    log::info!("{} passengers boarding in aisle chairs", chairs.len());
+
+    // Passengers board from the back of the list.
+    if last {
+        chairs.append(&mut persons);
+        Ok(chairs)
+    } else {
+        persons.append(&mut chairs);
+        Ok(persons)
+    }
+}
+
+/// Sends roughly `rate` of the passengers to the wrong seat, as if they had
+/// misread their boarding pass.
+///
+/// Each mistaken passenger heads for a seat next to their own on the same row
+/// that belongs to another passenger, who will find them there. Passengers
+/// with nobody sitting beside them always find their own seat, and no two
+/// passengers take each other's seats, as neither would ever find out.
+pub fn assign_wrong_seats(mut persons: Vec<Person>, rate: f32)
+    -> Result<Vec<Person>, &'static str> {
+
+    if !(0.0..=1.0).contains(&rate) {
+        return Err("Wrong seat rate must be between 0 and 1");
+    }
+
+    let mut rng = thread_rng();
+    let count = (persons.len() as f32 * rate).round() as usize;
+    let mut indices: Vec<usize> = (0..persons.len()).collect();
+    indices.shuffle(&mut rng);
+
+    let mut mistaken: usize = 0;
+    for i in indices {
+        if mistaken == count {
+            break;
+        }
+        let (x, y) = match persons[i].get_seat() {
+            Some(seat) => seat,
+            None => continue,
+        };
+        let neighbours: Vec<(u16, u16)> = [x.checked_sub(1), x.checked_add(1)]
+            .iter()
+            .flatten()
+            .map(|x| (*x, y))
+            .filter(|seat| persons.iter().any(|p| {
+                p.get_seat() == Some(*seat)
+                    && p.get_wrong_seat() != Some((x, y))
+            }))
+            .collect();
+        if let Some((x, y)) = neighbours.choose(&mut rng) {
+            persons[i].mistake_seat(*x, *y);
+            mistaken += 1;
+        }
+    }
+    log::info!("{} passengers heading for the wrong seat", mistaken);
+
+    Ok(persons)
+}
+
+/// Splits a list of passengers into `keep` randomly chosen passengers and the
+/// remainder, preserving the order of both.
+fn remove_random(persons: Vec<Person>, keep: usize)
+    -> (Vec<Person>, Vec<Person>) {
+
+    let mut indices: Vec<usize> = (0..persons.len()).collect();
+    indices.shuffle(&mut thread_rng());
+    let mut kept = vec![false; persons.len()];
+    for i in indices.into_iter().take(keep) {
+        kept[i] = true;
+    }
+
+    let mut remaining = Vec::<Person>::new();
+    let mut removed = Vec::<Person>::new();
+    for (i, person) in persons.into_iter().enumerate() {
+        if kept[i] {
+            remaining.push(person);
+        } else {
+            removed.push(person);
+        }
+    }
+
+    (remaining, removed)
+}
+
+/// Reads a list of passengers from a csv file and returns them as a vector of
+/// `Person` objects.
+///
+/// Columns are matched by their headers, see the `passengers` module. Rows
+/// that can't be read are skipped and logged, and `None` is returned only if
+/// the file itself can't be read. Use `passengers::PassengerReader` to find out
+/// which rows were skipped, or to fail on the first invalid row.
+pub fn read_passengers(path: &Path) -> Option<Vec<Person>> {
+    let reader = passengers::PassengerReader::open(path)
+        .map_err(|e| log::error!("{}", e))
+        .ok()?;
+    let (persons, diagnostics) = reader.lenient();
+    for diagnostic in diagnostics {
+        log::warn!("Skipping passenger: {}", diagnostic);
+    }
+    Some(persons)
 }
 
 /// Reads a list of tiles from a correctly formatted csv and returns them as an
 /// `Aircraft` object with that layout.
-pub fn read_layout(path: &Path) -> Option<Aircraft> {
+///
+/// An optional fourth column gives each seat's legroom, one of "tight",
+/// "standard", "extra" or "premium", and may be left blank for standard
+/// legroom.
+///
+/// Files ending in .json or .toml are instead read as aircraft definitions,
+/// see `definition::read_definition`.
+pub fn read_layout(path: &Path) -> Result<Aircraft, &'static str> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") | Some("toml") => {
+            return definition::read_definition(path);
+        },
+        _ => (),
+    }
+
     let mut seats = Vec::<seat_data>::new();
-    let file = File::open(path).expect("Invalid file path");
+    let file = File::open(path).map_err(|_| "Couldn't open layout file")?;
     let mut rdr = csv::Reader::from_reader(file);
     
     for result in rdr.records() {
-        let record = result.expect("Result is invalid record");
+        let record = result.map_err(|_| "Invalid layout record")?;
+        if record.len() < 3 {
+            return Err("Layout record is missing a column");
+        }
+        let legroom = match record.get(3).map(str::trim) {
+            None | Some("") => Legroom::Standard,
+            Some(legroom) => match str_to_legroom(legroom) {
+                Some(legroom) => legroom,
+                None => {
+                    log::error!("Invalid legroom '{}'", legroom);
+                    return Err("Invalid legroom");
+                },
+            },
+        };
         let data = seat_data::new(
-            record[0].parse().expect("Couldn't parse"),
-            record[1].parse().expect("Couldn't parse"),
-            &record[2],
+            record[0].trim().parse()
+                .map_err(|_| "Invalid layout coordinates")?,
+            record[1].trim().parse()
+                .map_err(|_| "Invalid layout coordinates")?,
+            str_to_var(record[2].trim()).ok_or("Invalid tile variant")?,
+            legroom,
         );
         seats.push(data);
     }
@@ -292,14 +851,289 @@ pub fn read_layout(path: &Path) -> Option<Aircraft> {
     let mut aircraft = Aircraft::new(size_x, size_y);
     for i in seats {
         aircraft.set_tile(i.get_x(), i.get_y(), i.get_variant());
+        aircraft.set_legroom(i.get_x(), i.get_y(), i.get_legroom());
     }
 
-    return Some(aircraft);
+    return Ok(aircraft);
+}
+
+/// Reads a layout file, or builds the bundled aircraft or seat map given
+/// instead, depending on what `layout` names.
+pub fn load_layout(layout: &str) -> Result<Aircraft, &'static str> {
+    if Path::new(layout).is_file() {
+        read_layout(Path::new(layout))
+    } else if let Some(aircraft) = seat_map::bundled(layout) {
+        Ok(aircraft)
+    } else if layout.contains(':') {
+        seat_map::read_seat_map(layout)
+    } else {
+        Err("Layout file not found")
+    }
+}
+
+/// Checks that a layout and a list of passengers make sense together.
+///
+/// Returns a description of every problem found, so an empty vector means the
+/// aircraft can be boarded.
+pub fn validate(aircraft: &Aircraft, persons: &[Person]) -> Vec<String> {
+    let mut problems = Vec::<String>::new();
+    let (size_x, size_y) = aircraft.get_size();
+
+    let entrances = (0..size_x)
+        .flat_map(|x| (0..size_y).map(move |y| (x, y)))
+        .filter(|(x, y)| aircraft.get_tile_variant(*x, *y) == Variant::Entrance)
+        .count();
+    if entrances == 0 {
+        problems.push("Layout has no entrance".to_string());
+    }
+
+    let seat_map = SeatMap::new(aircraft);
+    let mut taken = Vec::<(u16, u16)>::new();
+    for (i, person) in persons.iter().enumerate() {
+        let seat = match (person.get_seat(), person.get_seat_label()) {
+            (Some(seat), _) => seat,
+            (None, Some(label)) => match seat_map.find(label) {
+                Some(seat) => seat,
+                None => {
+                    problems.push(format!("Passenger {}'s seat {} doesn't \
+                                           exist", i, label));
+                    continue;
+                },
+            },
+            (None, None) => {
+                problems.push(format!("Passenger {} has no seat", i));
+                continue;
+            },
+        };
+        if seat.0 >= size_x || seat.1 >= size_y {
+            problems.push(format!("Passenger {}'s seat {},{} is outside the \
+                                   aircraft", i, seat.0, seat.1));
+            continue;
+        }
+        let name = seat_map.label(seat.0, seat.1)
+            .unwrap_or_else(|| format!("{},{}", seat.0, seat.1));
+        if aircraft.get_tile_variant(seat.0, seat.1) != Variant::Seat {
+            problems.push(format!("Passenger {}'s seat {} is not a seat",
+                                  i, name));
+        }
+        if taken.contains(&seat) {
+            problems.push(format!("Passenger {}'s seat {} is already taken",
+                                  i, name));
+        }
+        taken.push(seat);
+    }
+
+    problems
+}
+
+/// Writes an aircraft's layout to a csv file in the format read by
+/// `read_layout`.
+///
+/// Files ending in .json or .toml are instead written as aircraft definitions,
+/// keeping any row numbers, seat letters and other cabin details.
+pub fn write_layout(aircraft: &Aircraft, path: &Path)
+    -> Result<(), &'static str> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") | Some("toml") => {
+            return definition::write_definition(aircraft, path);
+        },
+        _ => (),
+    }
+
+    // Legroom is only written for layouts that have seats with more or less
+    // than standard legroom.
+    let (size_x, size_y) = aircraft.get_size();
+    let legroom = (0..size_x)
+        .flat_map(|x| (0..size_y).map(move |y| (x, y)))
+        .any(|(x, y)| aircraft.get_legroom(x, y) != Legroom::Standard);
+
+    let mut wtr = csv::Writer::from_path(path)
+        .map_err(|_| "Couldn't create layout file")?;
+    let mut header = vec!["X", "Y", "VARIANT"];
+    if legroom {
+        header.push("LEGROOM");
+    }
+    wtr.write_record(&header)
+        .map_err(|_| "Couldn't write layout file")?;
+
+    for x in 0..size_x {
+        for y in 0..size_y {
+            let mut record = vec![x.to_string(),
+                                  y.to_string(),
+                                  var_to_str(aircraft.get_tile_variant(x, y))
+                                      .to_string()];
+            if legroom {
+                record.push(match aircraft.get_tile_variant(x, y) {
+                    Variant::Seat => aircraft.get_legroom(x, y).as_str(),
+                    _ => "",
+                }.to_string());
+            }
+            wtr.write_record(&record)
+                .map_err(|_| "Couldn't write layout file")?;
+        }
+    }
+
+    wtr.flush().map_err(|_| "Couldn't write layout file")
+}
+
+/// Writes a list of passengers to a csv file in the format read by
+/// `read_passengers`, in the order they were given.
+///
+/// A `CHAIR` column is only written if any of the passengers board in an
+/// aisle chair, `WRONG_X` and `WRONG_Y` columns only if any of them head
+/// for the wrong seat, and a `POLICY` column only if any of them have a
+/// policy of their own, which must be registered by name.
+pub fn write_passengers(persons: &[Person], path: &Path)
+    -> Result<(), &'static str> {
+    let chairs = persons.iter().any(Person::uses_aisle_chair);
+    let mistakes = persons.iter().any(|p| p.get_wrong_seat().is_some());
+    let policies = persons.iter().any(|p| p.get_policy().is_some());
+    let mut wtr = csv::Writer::from_path(path)
+        .map_err(|_| "Couldn't create passenger file")?;
+    let mut header = vec!["NAME", "X", "Y", "BAGGAGE", "ARRIVAL", "PARTY",
+                          "ZONE"];
+    if chairs {
+        header.push("CHAIR");
+    }
+    if mistakes {
+        header.extend(&["WRONG_X", "WRONG_Y"]);
+    }
+    if policies {
+        header.push("POLICY");
+    }
+    wtr.write_record(&header)
+        .map_err(|_| "Couldn't write passenger file")?;
+
+    for person in persons {
+        let seat = person.get_seat().ok_or("Passenger has no seat")?;
+        let mut record = vec![person.get_name(),
+                              seat.0.to_string(),
+                              seat.1.to_string(),
+                              (person.has_baggage() as u8).to_string(),
+                              person.get_arrival().to_string(),
+                              person.get_party()
+                                  .map_or(String::new(), |p| p.to_string()),
+                              person.get_zone().to_string()];
+        if chairs {
+            record.push((person.uses_aisle_chair() as u8).to_string());
+        }
+        if mistakes {
+            let wrong = person.get_wrong_seat();
+            record.push(wrong.map_or(String::new(), |s| s.0.to_string()));
+            record.push(wrong.map_or(String::new(), |s| s.1.to_string()));
+        }
+        if policies {
+            record.push(policy_column(person)?);
+        }
+        wtr.write_record(&record)
+            .map_err(|_| "Couldn't write passenger file")?;
+    }
+
+    wtr.flush().map_err(|_| "Couldn't write passenger file")
+}
+
+/// Writes a list of passengers to a csv file with their seats given by label,
+/// such as `23C`, in the format read by `read_passengers`.
+///
+/// Labels are looked up in `aircraft`, so that the list can be read back for
+/// any aircraft with the same seat labels. Wrong seats are written by label
+/// in a `WRONG_SEAT` column, if any of the passengers have one, and policies
+/// as by `write_passengers`.
+pub fn write_labelled_passengers(aircraft: &Aircraft, persons: &[Person],
+                                 path: &Path) -> Result<(), &'static str> {
+    let chairs = persons.iter().any(Person::uses_aisle_chair);
+    let mistakes = persons.iter().any(|p| p.get_wrong_seat().is_some()
+                                      || p.get_wrong_label().is_some());
+    let policies = persons.iter().any(|p| p.get_policy().is_some());
+    let mut wtr = csv::Writer::from_path(path)
+        .map_err(|_| "Couldn't create passenger file")?;
+    let mut header = vec!["NAME", "SEAT", "BAGGAGE", "ARRIVAL", "PARTY",
+                          "ZONE"];
+    if chairs {
+        header.push("CHAIR");
+    }
+    if mistakes {
+        header.push("WRONG_SEAT");
+    }
+    if policies {
+        header.push("POLICY");
+    }
+    wtr.write_record(&header)
+        .map_err(|_| "Couldn't write passenger file")?;
+
+    let seat_map = SeatMap::new(aircraft);
+    for person in persons {
+        let label = match (person.get_seat(), person.get_seat_label()) {
+            (Some((x, y)), _) => seat_map.label(x, y),
+            (None, label) => label.map(str::to_string),
+        }.ok_or("Passenger's seat has no label")?;
+        let mut record = vec![person.get_name(),
+                              label,
+                              (person.has_baggage() as u8).to_string(),
+                              person.get_arrival().to_string(),
+                              person.get_party()
+                                  .map_or(String::new(), |p| p.to_string()),
+                              person.get_zone().to_string()];
+        if chairs {
+            record.push((person.uses_aisle_chair() as u8).to_string());
+        }
+        if mistakes {
+            let wrong = match (person.get_wrong_seat(),
+                               person.get_wrong_label()) {
+                (Some((x, y)), _) => Some(seat_map.label(x, y)
+                    .ok_or("Passenger's wrong seat has no label")?),
+                (None, label) => label.map(str::to_string),
+            };
+            record.push(wrong.unwrap_or_default());
+        }
+        if policies {
+            record.push(policy_column(person)?);
+        }
+        wtr.write_record(&record)
+            .map_err(|_| "Couldn't write passenger file")?;
+    }
+
+    wtr.flush().map_err(|_| "Couldn't write passenger file")
+}
+
+/// The name of a passenger's own policy as written to a passenger list, or
+/// blank if they follow the aircraft's.
+fn policy_column(person: &Person) -> Result<String, &'static str> {
+    match person.get_policy() {
+        Some(policy) => policy_name(policy)
+            .ok_or("Passenger's policy isn't registered"),
+        None => Ok(String::new()),
+    }
+}
+
+/// Writes every counter of a heatmap to a csv file, one row per tile.
+pub fn write_heatmap(heatmap: &Heatmap, path: &Path)
+    -> Result<(), &'static str> {
+    let mut wtr = csv::Writer::from_path(path)
+        .map_err(|_| "Couldn't create heatmap file")?;
+    wtr.write_record(["X", "Y", "OCCUPIED", "WAITED", "PASSES"])
+        .map_err(|_| "Couldn't write heatmap file")?;
+
+    let (size_x, size_y) = heatmap.get_size();
+    for x in 0..size_x as usize {
+        for y in 0..size_y as usize {
+            wtr.write_record(&[x.to_string(),
+                               y.to_string(),
+                               heatmap.get(Counter::Occupied)[x][y].to_string(),
+                               heatmap.get(Counter::Waited)[x][y].to_string(),
+                               heatmap.get(Counter::Passes)[x][y].to_string()])
+                .map_err(|_| "Couldn't write heatmap file")?;
+        }
+    }
+
+    wtr.flush().map_err(|_| "Couldn't write heatmap file")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::aircraft::policy::named_policy;
+    use super::super::aircraft::tile::VARIANTS;
 
     // #[test]
     // fn test_read_passengers() {
@@ -318,21 +1152,405 @@ mod tests {
         let aircraft = read_layout(Path::new("./config/test_layout.csv")).unwrap();
 
         assert_eq!((5,5), aircraft.get_size());
+
+        assert!(read_layout(Path::new("./config/missing.csv")).is_err());
+        let path = std::env::temp_dir().join("aircraft_sim_bad_layout.csv");
+        std::fs::write(&path, "X,Y,VARIANT\n0,zero,seat\n").unwrap();
+        assert!(read_layout(&path).is_err());
+        std::fs::write(&path, "X,Y,VARIANT\n0,0\n").unwrap();
+        assert!(read_layout(&path).is_err());
+        std::fs::write(&path, "X,Y,VARIANT\n0,0,sofa\n").unwrap();
+        assert_eq!(read_layout(&path).err(), Some("Invalid tile variant"));
+    }
+
+    #[test]
+    fn write_read() {
+        let dir = std::env::temp_dir();
+        let layout_path = dir.join("aircraft_sim_write_layout.csv");
+        let passenger_path = dir.join("aircraft_sim_write_passengers.csv");
+
+        let aircraft = standard_layout(7, 10).unwrap();
+        write_layout(&aircraft, &layout_path).unwrap();
+        let read = read_layout(&layout_path).unwrap();
+        assert_eq!(read.get_size(), (7, 10));
+        assert_eq!(read.get_tile_variant(3, 9), Variant::Entrance);
+        let header = std::fs::read_to_string(&layout_path).unwrap();
+        assert_eq!(header.lines().next(), Some("X,Y,VARIANT"));
+
+        let mut roomy = standard_layout(7, 10).unwrap();
+        roomy.set_legroom(0, 9, Legroom::Extra);
+        roomy.set_tile(3, 0, Variant::CrossAisle);
+        write_layout(&roomy, &layout_path).unwrap();
+        let read = read_layout(&layout_path).unwrap();
+        assert_eq!(read.get_legroom(0, 9), Legroom::Extra);
+        assert_eq!(read.get_tile_variant(3, 0), Variant::CrossAisle);
+        assert_eq!(read.get_legroom(1, 9), Legroom::Standard);
+
+        let mut list = random(7, 10, 1.0).unwrap();
+        list[0].set_party(Some(3));
+        list[0].set_arrival(7);
+        list[0].set_zone(2);
+        list[1].set_aisle_chair(true);
+        list[2].mistake_seat(0, 0);
+        list[3].set_policy(named_policy("greedy").unwrap());
+        write_passengers(&list, &passenger_path).unwrap();
+        let read = read_passengers(&passenger_path).unwrap();
+        assert_eq!(read.len(), list.len());
+        assert_eq!(read[0].get_seat(), list[0].get_seat());
+        assert_eq!(read[0].get_party(), Some(3));
+        assert_eq!(read[1].get_party(), None);
+        assert_eq!(read[0].get_arrival(), 7);
+        assert_eq!(read[0].get_zone(), 2);
+        assert_eq!(read[1].get_zone(), 0);
+        assert!(read[1].uses_aisle_chair() && read[1].is_transferring());
+        assert!(!read[0].uses_aisle_chair());
+        assert_eq!(read[2].get_wrong_seat(), Some((0, 0)));
+        assert_eq!(read[0].get_wrong_seat(), None);
+        assert!(read[3].get_policy().is_some());
+        assert!(read[0].get_policy().is_none());
+
+        let labelled_path = dir.join("aircraft_sim_write_labelled.csv");
+        write_labelled_passengers(&aircraft, &list, &labelled_path).unwrap();
+        let read = read_passengers(&labelled_path).unwrap();
+        assert_eq!(read.len(), list.len());
+        let (x, y) = list[0].get_seat().unwrap();
+        assert_eq!(read[0].get_seat_label(),
+                   aircraft.seat_label(x, y).as_deref());
+        assert_eq!(read[0].get_party(), Some(3));
+        assert_eq!(read[0].get_arrival(), 7);
+        assert_eq!(read[0].get_zone(), 2);
+        assert_eq!(read[2].get_wrong_label(),
+                   aircraft.seat_label(0, 0).as_deref());
+        assert!(read[3].get_policy().is_some());
+
+        let mut aisle = Person::new("DEFAULT");
+        aisle.target_seat(3, 0);
+        assert!(write_labelled_passengers(&aircraft, &[aisle], &labelled_path)
+                .is_err(), "Aisle was written as a seat");
+    }
+
+    #[test]
+    fn test_write_heatmap() {
+        let path = std::env::temp_dir().join("aircraft_sim_write_heatmap.csv");
+        let mut heatmap = Heatmap::new(2, 2);
+        heatmap.wait(1, 0);
+        heatmap.pass(1, 0);
+        write_heatmap(&heatmap, &path).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "X,Y,OCCUPIED,WAITED,PASSES");
+        assert_eq!(lines[3], "1,0,0,1,1");
+    }
+
+    #[test]
+    fn test_validate() {
+        let aircraft = standard_layout(5, 5).unwrap();
+        let list = random(5, 5, 1.0).unwrap();
+        assert!(validate(&aircraft, &list).is_empty());
+
+        let mut bad = Person::new("DEFAULT");
+        bad.target_seat(2, 0);
+        let mut outside = Person::new("DEFAULT");
+        outside.target_seat(9, 9);
+        let list = vec![bad, outside, Person::new("DEFAULT")];
+        assert_eq!(validate(&aircraft, &list).len(), 3);
+
+        let mut labelled = Person::new("DEFAULT");
+        labelled.target_label("1A");
+        let mut unknown = Person::new("DEFAULT");
+        unknown.target_label("9Z");
+        let problems = validate(&aircraft, &[labelled, unknown]);
+        assert_eq!(problems, vec!["Passenger 1's seat 9Z doesn't exist"]);
+    }
+
+    #[test]
+    fn read_labelled_passengers() {
+        let path = std::env::temp_dir()
+            .join("aircraft_sim_labelled_passengers.csv");
+        std::fs::write(&path, "NAME,SEAT,BAGGAGE,ARRIVAL,PARTY\n\
+                               a,2B,1,4,\n\
+                               b,1a,0,,2\n").unwrap();
+        let list = read_passengers(&path).unwrap();
+        assert_eq!(list[0].get_seat_label(), Some("2B"));
+        assert!(list[0].has_baggage());
+        assert_eq!(list[0].get_arrival(), 4);
+        assert_eq!(list[1].get_party(), Some(2));
+
+        let mut aircraft = standard_layout(5, 5).unwrap();
+        for person in list {
+            aircraft.add_passenger(person);
+        }
+        assert!(aircraft.run_to_completion().is_ok());
+        assert!(aircraft.get_occupier(1, 1).is_some());
+        assert!(aircraft.get_occupier(0, 0).is_some());
+
+        let mut unknown = Person::new("DEFAULT");
+        unknown.target_label("9Z");
+        let mut aircraft = standard_layout(5, 5).unwrap();
+        assert_eq!(aircraft.try_add_passenger(unknown),
+                   Err("No seat has the passenger's seat label"));
+        let mut known = Person::new("DEFAULT");
+        known.target_label("1A");
+        assert!(aircraft.try_add_passenger(known).is_ok());
     }
 
     #[test]
     fn test_str_to_var() {
-        assert_eq!(str_to_var("aisle"), Variant::Aisle);
-        assert_eq!(str_to_var("seat"), Variant::Seat);
-        assert_eq!(str_to_var("entrance"), Variant::Entrance);
-        assert_eq!(str_to_var("none"), Variant::None);
-        assert_eq!(str_to_var("invalid"), Variant::None);
+        assert_eq!(str_to_var("aisle"), Some(Variant::Aisle));
+        assert_eq!(str_to_var("seat"), Some(Variant::Seat));
+        assert_eq!(str_to_var("entrance"), Some(Variant::Entrance));
+        assert_eq!(str_to_var("none"), Some(Variant::None));
+        assert_eq!(str_to_var("exit_row"), Some(Variant::ExitRow));
+        for variant in VARIANTS.iter() {
+            assert_eq!(str_to_var(var_to_str(*variant)), Some(*variant));
+        }
+        assert_eq!(str_to_var("invalid"), None);
+    }
+
+    #[test]
+    fn test_str_to_deplaning() {
+        assert_eq!(str_to_deplaning("row_by_row"), Some(Deplaning::RowByRow));
+        assert_eq!(str_to_deplaning("free_for_all"),
+                   Some(Deplaning::FreeForAll));
+        assert_eq!(str_to_deplaning("aisle_first"),
+                   Some(Deplaning::AisleFirst));
+        assert_eq!(str_to_deplaning("invalid"), None);
+    }
+
+    #[test]
+    fn test_str_to_overtaking() {
+        assert_eq!(str_to_overtaking("never"), Some(Overtaking::Never));
+        assert_eq!(str_to_overtaking("wide"), Some(Overtaking::Wide));
+        assert_eq!(str_to_overtaking("sometimes"), None);
     }
 
     #[test]
     fn rand_back_first() {
-        let list = random_back_first(5, 10).unwrap();
+        let list = random_back_first(5, 10, 1.0).unwrap();
+
+        assert_eq!(list.len(), 40);
+    }
+
+    #[test]
+    fn seeded() {
+        let seats = |list: Vec<Person>| -> Vec<(u16, u16)> {
+            list.iter().map(|p| p.get_seat().unwrap()).collect()
+        };
+
+        seed(42);
+        let first = seats(generate("random", 7, 10, 1.0).unwrap());
+        seed(42);
+        let second = seats(generate("random", 7, 10, 1.0).unwrap());
+        assert_eq!(first, second);
+
+        assert!(generate("invalid", 7, 10, 1.0).is_err());
+    }
+
+    #[test]
+    fn classed() {
+        let mut aircraft = standard_layout(5, 10).unwrap();
+        aircraft.set_class(9, Some(Class::First));
+        for y in 7..9 {
+            aircraft.set_class(y, Some(Class::Business));
+        }
+        let list = generate_for(&aircraft, "back_first", 1.0).unwrap();
+        assert_eq!(list.len(), 40);
+
+        let order: Vec<&Person> = list.iter().rev().collect();
+        let classes: Vec<Option<Class>> = order.iter()
+            .map(|p| {
+                let (x, y) = p.get_seat().unwrap();
+                aircraft.get_class(x, y)
+            })
+            .collect();
+        assert!(classes[..4].iter().all(|c| *c == Some(Class::First)));
+        assert!(classes[4..12].iter().all(|c| *c == Some(Class::Business)));
+        assert!(classes[12..].iter().all(Option::is_none));
+        // Economy boards from the row furthest from the entrance
+        assert_eq!(order[12].get_seat().unwrap().1, 0);
+        assert_eq!(order[39].get_seat().unwrap().1, 6);
+        assert!(order.windows(2)
+                .all(|pair| pair[0].get_zone() <= pair[1].get_zone()));
+        assert_eq!(order[39].get_zone(), 8);
+
+        assert_eq!(generate_for(&aircraft, "random", 0.5).unwrap().len(), 20);
+        assert!(generate_for(&aircraft, "invalid", 1.0).is_err());
+    }
+
+    #[test]
+    fn load_factor() {
+        let list = random(7, 10, 0.75).unwrap();
+        assert_eq!(list.len(), 45);
+
+        assert!(random_aisle_first(7, 10, 1.5).is_err());
+    }
+
+    #[test]
+    fn compliance() {
+        let list = random_back_first(5, 10, 1.0).unwrap();
+        let list = apply_compliance(list, 0.5, 0, 10).unwrap();
 
         assert_eq!(list.len(), 40);
+        assert_eq!(list.iter().filter(|p| p.get_arrival() > 0).count(), 20);
+
+        let list = apply_compliance(list, 1.0, 3, 0).unwrap();
+        assert_eq!(list.len(), 40);
+
+        let list = random_back_first(5, 10, 1.0).unwrap();
+        let options = PassengerOptions { late: 0.5, max_shift: 0,
+                                         ..PassengerOptions::default() };
+        let list = apply_options(list, &options).unwrap();
+        assert_eq!(list.iter().filter(|p| p.get_arrival() > 0).count(), 20);
+        assert!(apply_options(list, &PassengerOptions {
+            late: 1.5, ..PassengerOptions::default()
+        }).is_err());
+    }
+
+    #[test]
+    fn arrivals() {
+        let list = random(5, 10, 1.0).unwrap();
+        let list = poisson_arrivals(list, 0.5).unwrap();
+
+        assert_eq!(list.last().unwrap().get_arrival(), 0);
+        let arrivals: Vec<u16> = list.iter()
+            .rev()
+            .map(|p| p.get_arrival())
+            .collect();
+        assert!(arrivals.windows(2).all(|w| w[0] <= w[1]));
+        assert!(poisson_arrivals(Vec::new(), 0.0).is_err());
+
+        let list = random(5, 10, 1.0).unwrap();
+        let options = PassengerOptions { arrival_rate: Some(0.5),
+                                         ..PassengerOptions::default() };
+        let list = apply_options(list, &options).unwrap();
+        assert!(list.iter().any(|p| p.get_arrival() > 0));
+    }
+
+    #[test]
+    fn parties() {
+        let list = random_back_first(7, 10, 1.0).unwrap();
+        let list = form_parties(list, 1.0, 3).unwrap();
+        assert_eq!(list.len(), 60);
+
+        // Party members board one after another.
+        let mut seen = Vec::<u16>::new();
+        for (i, person) in list.iter().enumerate() {
+            if let Some(party) = person.get_party() {
+                if i == 0 || list[i - 1].get_party() != Some(party) {
+                    assert!(!seen.contains(&party), "Party was split up");
+                    seen.push(party);
+                }
+            }
+        }
+        assert!(!seen.is_empty());
+
+        // Party members sit next to each other on the same row.
+        for party in seen {
+            let mut seats: Vec<(u16, u16)> = list.iter()
+                .filter(|p| p.get_party() == Some(party))
+                .map(|p| p.get_seat().unwrap())
+                .collect();
+            seats.sort_unstable();
+            for pair in seats.windows(2) {
+                assert_eq!(pair[0].1, pair[1].1);
+                assert_eq!(pair[0].0 + 1, pair[1].0);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_parties() {
+        let aircraft = seat_map::bundled("a320").unwrap();
+        let options = PassengerOptions { parties: 1.0, max_party: 3,
+                                         ..PassengerOptions::default() };
+        for strategy in STRATEGIES.iter() {
+            let list = generate_for(&aircraft, strategy, 1.0).unwrap();
+            let list = apply_options(list, &options).unwrap();
+
+            let parties: Vec<u16> = list.iter()
+                .filter_map(|p| p.get_party())
+                .collect();
+            assert!(!parties.is_empty(), "No parties for {}", strategy);
+            for party in parties {
+                let mut seats: Vec<(u16, u16)> = list.iter()
+                    .filter(|p| p.get_party() == Some(party))
+                    .map(|p| p.get_seat().unwrap())
+                    .collect();
+                seats.sort_unstable();
+                assert!(seats.len() > 1);
+                for pair in seats.windows(2) {
+                    assert_eq!(pair[0].1, pair[1].1);
+                    assert_eq!(pair[0].0 + 1, pair[1].0,
+                               "Party split across an aisle for {}", strategy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn no_shows() {
+        let list = random_front_first(5, 10, 1.0).unwrap();
+        let (list, no_shows) = remove_no_shows(list, 0.1).unwrap();
+
+        assert_eq!(list.len(), 36);
+        assert_eq!(no_shows.len(), 4);
+
+        // The remaining passengers should still board front-first.
+        let rows: Vec<u16> = list.iter()
+            .map(|p| p.get_seat().unwrap().1)
+            .collect();
+        assert!(rows.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn aisle_chairs() {
+        let list = random_front_first(5, 10, 1.0).unwrap();
+        let first = assign_aisle_chairs(list.clone(), 2, false).unwrap();
+        assert_eq!(first.len(), 40);
+        // Passengers board from the back of the list
+        let chairs: Vec<bool> = first.iter()
+            .map(Person::uses_aisle_chair)
+            .collect();
+        assert_eq!(chairs.iter().filter(|c| **c).count(), 2);
+        assert!(chairs[38] && chairs[39]);
+        // Everybody else still boards front-first
+        let rows: Vec<u16> = first[..38].iter()
+            .map(|p| p.get_seat().unwrap().1)
+            .collect();
+        assert!(rows.windows(2).all(|w| w[0] <= w[1]));
+
+        let last = assign_aisle_chairs(list.clone(), 2, true).unwrap();
+        assert!(last[0].uses_aisle_chair() && last[1].uses_aisle_chair());
+        assert!(!last[2].uses_aisle_chair());
+
+        assert!(assign_aisle_chairs(list, 41, false).is_err());
+    }
+
+    #[test]
+    fn wrong_seats() {
+        let list = random(7, 10, 1.0).unwrap();
+        let mistaken = assign_wrong_seats(list.clone(), 0.2).unwrap();
+        assert_eq!(mistaken.len(), list.len());
+        let wrong: Vec<&Person> = mistaken.iter()
+            .filter(|p| p.get_wrong_seat().is_some())
+            .collect();
+        assert_eq!(wrong.len(), 12);
+        for person in wrong {
+            // Each wrong seat is beside their own and belongs to somebody
+            let (x, y) = person.get_seat().unwrap();
+            let (wrong_x, wrong_y) = person.get_wrong_seat().unwrap();
+            assert_eq!(wrong_y, y);
+            assert_eq!(wrong_x.abs_diff(x), 1);
+            let neighbour = mistaken.iter()
+                .find(|p| p.get_seat() == Some((wrong_x, y)))
+                .unwrap();
+            assert_ne!(neighbour.get_wrong_seat(), Some((x, y)));
+        }
+
+        assert!(assign_wrong_seats(list.clone(), 0.0).unwrap().iter()
+                .all(|p| p.get_wrong_seat().is_none()));
+        assert!(assign_wrong_seats(list, 1.5).is_err());
     }
 }
@@ -0,0 +1,374 @@
+//! Reads passenger lists one row at a time, describing any problems with a row
+//! instead of panicking on it.
+//!
+//! Columns are found by their header, in any order and ignoring case, and any
+//! columns not listed here are ignored:
+//!
+//! - `NAME`: the passenger's name.
+//! - `X` and `Y`: the coordinates of their seat.
+//! - `SEAT`: their seat's label, such as `23C`, in place of `X` and `Y`.
+//! - `BAGGAGE`: `1` if they carry baggage to stow, otherwise `0`.
+//! - `ARRIVAL`: the step at which they arrive at the gate, or blank.
+//! - `PARTY`: the number of the party they travel with, or blank.
+//! - `ZONE`: the boarding zone they are called in, or blank for zone 0.
+//! - `CHAIR`: `1` if they board in an aisle chair, otherwise `0` or blank.
+//! - `WRONG_X` and `WRONG_Y`, or `WRONG_SEAT`: the seat they head for by
+//!   mistake, given like their own seat, or blank.
+//! - `POLICY`: the name of the registered `AgentPolicy` they decide how to
+//!   move by, or blank to follow the aircraft's.
+//!
+//! Only the seat columns are required.
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::super::aircraft::person::Person;
+use super::super::aircraft::policy::named_policy;
+
+/// A problem found while reading a passenger list.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Diagnostic {
+    /// The line of the file the problem is on, counting the header as line 1,
+    /// or 0 if it concerns the whole file.
+    pub line: u64,
+    /// The header of the column the problem is in, if any.
+    pub column: Option<String>,
+    pub reason: String,
+}
+
+impl Diagnostic {
+    fn new(line: u64, column: Option<&str>, reason: String) -> Diagnostic {
+        Diagnostic {
+            line,
+            column: column.map(str::to_string),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "Line {}", self.line)?;
+            if let Some(column) = &self.column {
+                write!(f, ", column {}", column)?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.reason)
+    }
+}
+
+/// The position of each known column in a passenger list.
+#[derive(Default)]
+struct Columns {
+    name: Option<usize>,
+    x: Option<usize>,
+    y: Option<usize>,
+    seat: Option<usize>,
+    baggage: Option<usize>,
+    arrival: Option<usize>,
+    party: Option<usize>,
+    zone: Option<usize>,
+    chair: Option<usize>,
+    wrong_x: Option<usize>,
+    wrong_y: Option<usize>,
+    wrong_seat: Option<usize>,
+    policy: Option<usize>,
+    headers: Vec<String>,
+}
+
+impl Columns {
+    fn new(headers: &csv::StringRecord) -> Result<Columns, Diagnostic> {
+        let mut columns = Columns {
+            headers: headers.iter().map(|h| h.trim().to_string()).collect(),
+            ..Columns::default()
+        };
+        for (i, header) in columns.headers.iter().enumerate() {
+            let column = match header.to_uppercase().as_str() {
+                "NAME" => &mut columns.name,
+                "X" => &mut columns.x,
+                "Y" => &mut columns.y,
+                "SEAT" => &mut columns.seat,
+                "BAGGAGE" => &mut columns.baggage,
+                "ARRIVAL" => &mut columns.arrival,
+                "PARTY" => &mut columns.party,
+                "ZONE" => &mut columns.zone,
+                "CHAIR" => &mut columns.chair,
+                "WRONG_X" => &mut columns.wrong_x,
+                "WRONG_Y" => &mut columns.wrong_y,
+                "WRONG_SEAT" => &mut columns.wrong_seat,
+                "POLICY" => &mut columns.policy,
+                _ => continue,
+            };
+            column.get_or_insert(i);
+        }
+
+        if columns.seat.is_none()
+            && (columns.x.is_none() || columns.y.is_none()) {
+            return Err(Diagnostic::new(
+                1, None,
+                "Passenger lists need X and Y columns or a SEAT column"
+                    .to_string()));
+        }
+        Ok(columns)
+    }
+
+    /// Reads a passenger from a single row.
+    fn person(&self, record: &csv::StringRecord, line: u64)
+        -> Result<Person, Diagnostic> {
+        // Finds the trimmed value of a column, failing on short rows
+        let value = |column: usize| {
+            record.get(column).map(str::trim).ok_or_else(|| {
+                Diagnostic::new(line, Some(&self.headers[column]),
+                                "Missing value".to_string())
+            })
+        };
+        // Finds the value of an optional column, if present and not blank
+        let optional = |column: Option<usize>| {
+            column.and_then(|c| record.get(c))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let invalid = |column: Option<usize>, reason: &str, value: &str| {
+            let header = column.map(|c| self.headers[c].as_str());
+            Diagnostic::new(line, header,
+                            format!("Invalid {} '{}'", reason, value))
+        };
+
+        let mut person = Person::new(match self.name {
+            Some(column) => value(column)?,
+            None => "",
+        });
+
+        if let Some(column) = self.seat {
+            let label = value(column)?;
+            if label.is_empty() {
+                return Err(Diagnostic::new(line, Some(&self.headers[column]),
+                                           "Missing seat label".to_string()));
+            }
+            person.target_label(label);
+        } else if let (Some(x_column), Some(y_column)) = (self.x, self.y) {
+            let (x, y) = (value(x_column)?, value(y_column)?);
+            person.target_seat(
+                x.parse().map_err(|_| invalid(self.x, "x coordinate", x))?,
+                y.parse().map_err(|_| invalid(self.y, "y coordinate", y))?);
+        }
+
+        if let Some(column) = self.baggage {
+            person.set_baggage(match value(column)? {
+                "0" => false,
+                "1" => true,
+                other => return Err(Diagnostic::new(
+                    line, Some(&self.headers[column]),
+                    format!("Baggage must be 0 or 1, not '{}'", other))),
+            });
+        }
+        if let Some(arrival) = optional(self.arrival) {
+            person.set_arrival(arrival.parse().map_err(|_| {
+                invalid(self.arrival, "arrival step", arrival)
+            })?);
+        }
+        if let Some(party) = optional(self.party) {
+            person.set_party(Some(party.parse().map_err(|_| {
+                invalid(self.party, "party number", party)
+            })?));
+        }
+        if let Some(zone) = optional(self.zone) {
+            person.set_zone(zone.parse().map_err(|_| {
+                invalid(self.zone, "zone", zone)
+            })?);
+        }
+        if let Some(chair) = optional(self.chair) {
+            person.set_aisle_chair(match chair {
+                "0" => false,
+                "1" => true,
+                other => return Err(Diagnostic::new(
+                    line, self.chair.map(|c| self.headers[c].as_str()),
+                    format!("Chair must be 0 or 1, not '{}'", other))),
+            });
+        }
+        if let Some(label) = optional(self.wrong_seat) {
+            person.mistake_label(label);
+        } else if let (Some(x), Some(y)) = (optional(self.wrong_x),
+                                            optional(self.wrong_y)) {
+            person.mistake_seat(
+                x.parse().map_err(|_| invalid(self.wrong_x, "x coordinate",
+                                              x))?,
+                y.parse().map_err(|_| invalid(self.wrong_y, "y coordinate",
+                                              y))?);
+        }
+        if let Some(name) = optional(self.policy) {
+            person.set_policy(named_policy(name)
+                              .ok_or_else(|| invalid(self.policy, "policy",
+                                                     name))?);
+        }
+        Ok(person)
+    }
+}
+
+/// Reads passengers from a csv passenger list one row at a time, so that
+/// large lists needn't be held in memory as text.
+///
+/// Each item is either the passenger on the next row, or a description of why
+/// that row couldn't be read. Rows after an invalid one are still read.
+pub struct PassengerReader<R: Read> {
+    records: csv::StringRecordsIntoIter<R>,
+    columns: Columns,
+}
+
+impl PassengerReader<File> {
+    /// Opens a passenger list file and reads its header.
+    pub fn open(path: &Path) -> Result<PassengerReader<File>, Diagnostic> {
+        let file = File::open(path).map_err(|e| {
+            Diagnostic::new(0, None, format!("Couldn't open '{}': {}",
+                                             path.display(), e))
+        })?;
+        PassengerReader::new(file)
+    }
+}
+
+impl<R: Read> PassengerReader<R> {
+    /// Reads the header of a passenger list.
+    pub fn new(reader: R) -> Result<PassengerReader<R>, Diagnostic> {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(reader);
+        let headers = reader.headers().map_err(|e| {
+            Diagnostic::new(1, None, format!("Invalid header: {}", e))
+        })?;
+        Ok(PassengerReader {
+            columns: Columns::new(headers)?,
+            records: reader.into_records(),
+        })
+    }
+
+    /// Reads every passenger, failing with the first invalid row.
+    pub fn strict(self) -> Result<Vec<Person>, Diagnostic> {
+        self.collect()
+    }
+
+    /// Reads every valid passenger, skipping invalid rows and describing why
+    /// each was skipped.
+    pub fn lenient(self) -> (Vec<Person>, Vec<Diagnostic>) {
+        let mut persons = Vec::<Person>::new();
+        let mut diagnostics = Vec::<Diagnostic>::new();
+        for result in self {
+            match result {
+                Ok(person) => persons.push(person),
+                Err(diagnostic) => diagnostics.push(diagnostic),
+            }
+        }
+        (persons, diagnostics)
+    }
+}
+
+impl<R: Read> Iterator for PassengerReader<R> {
+    type Item = Result<Person, Diagnostic>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                return Some(Err(Diagnostic::new(line, None, e.to_string())));
+            },
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        Some(self.columns.person(&record, line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> PassengerReader<&[u8]> {
+        PassengerReader::new(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn columns_by_name() {
+        let persons = read("party,Baggage,EXTRA,y,x,name,chair,zone\n\
+                            3,1,?,4,2,alice,,2\n\
+                            ,0,?,5,0,bob,1,\n")
+            .strict().unwrap();
+        assert_eq!(persons.len(), 2);
+        assert_eq!(persons[0].get_name(), "alice");
+        assert_eq!(persons[0].get_seat(), Some((2, 4)));
+        assert!(persons[0].has_baggage());
+        assert_eq!(persons[0].get_party(), Some(3));
+        assert_eq!(persons[1].get_seat(), Some((0, 5)));
+        assert_eq!(persons[1].get_party(), None);
+        assert!(!persons[0].uses_aisle_chair());
+        assert!(persons[1].uses_aisle_chair());
+        assert_eq!(persons[0].get_zone(), 2);
+        assert_eq!(persons[1].get_zone(), 0);
+
+        let labelled = read("SEAT,NAME,WRONG_SEAT\n23C,carol,23D\n24A,dan,\n")
+            .strict().unwrap();
+        assert_eq!(labelled[0].get_seat_label(), Some("23C"));
+        assert_eq!(labelled[0].get_wrong_label(), Some("23D"));
+        assert_eq!(labelled[1].get_wrong_label(), None);
+        assert!(!labelled[0].has_baggage());
+
+        assert_eq!(PassengerReader::new("NAME,X,BAGGAGE\n".as_bytes())
+                   .err().unwrap().line, 1);
+    }
+
+    #[test]
+    fn lenient() {
+        let (persons, diagnostics) = read("NAME,X,Y,BAGGAGE,ARRIVAL\n\
+                                           alice,0,1,1\n\
+                                           bob,0\n\
+                                           carol,a,1,0\n\
+                                           dave,0,2,2\n\
+                                           erin,0,3,0,soon\n\
+                                           frank,0,4,0,5\n")
+            .lenient();
+        let names: Vec<String> = persons.iter().map(Person::get_name).collect();
+        assert_eq!(names, vec!["alice", "frank"]);
+        assert_eq!(persons[1].get_arrival(), 5);
+
+        let lines: Vec<u64> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6]);
+        assert_eq!(diagnostics[0].column, Some("Y".to_string()));
+        assert_eq!(diagnostics[1].to_string(),
+                   "Line 4, column X: Invalid x coordinate 'a'");
+        assert_eq!(diagnostics[2].to_string(),
+                   "Line 5, column BAGGAGE: Baggage must be 0 or 1, not '2'");
+        assert_eq!(diagnostics[3].column, Some("ARRIVAL".to_string()));
+    }
+
+    #[test]
+    fn strict() {
+        let error = read("NAME,X,Y,BAGGAGE\n\
+                          alice,0,1,1\n\
+                          bob,0,-1,0\n\
+                          carol,a,1,0\n")
+            .strict().unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, Some("Y".to_string()));
+
+        let error = PassengerReader::open(Path::new("./missing.csv"))
+            .err().unwrap();
+        assert_eq!(error.line, 0);
+    }
+
+    #[test]
+    fn policies() {
+        let (persons, diagnostics) = read("NAME,X,Y,POLICY
+                                           alice,0,1,greedy
+                                           bob,0,2,
+                                           carol,0,3,wander
+")
+            .lenient();
+        assert_eq!(persons.len(), 2);
+        assert!(persons[0].get_policy().is_some());
+        assert!(persons[1].get_policy().is_none());
+        assert_eq!(diagnostics[0].to_string(),
+                   "Line 4, column POLICY: Invalid policy 'wander'");
+    }
+}
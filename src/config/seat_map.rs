@@ -0,0 +1,430 @@
+//! Builds aircraft from compact seat-map descriptions, and holds a library of
+//! common aircraft described this way.
+//!
+//! A seat map is a list of clauses separated by commas, such as:
+//!
+//! ```text
+//! rows 1-4: AC-DF business, rows 5-30: ABC-DEF economy,
+//! exits at 12 and 13, doors L1 and L2
+//! ```
+//!
+//! Each `rows` clause gives a range of row numbers, the seat letters in each
+//! of those rows with a `-` for every aisle, and optionally a cabin class.
+//! Rows narrower than the widest have their seats lined up with the seats of
+//! the same letter, leaving open floor in the gaps. `exits` marks the rows
+//! beside emergency exits, whose aisles and gaps become exit-row floor, and
+//! `doors` adds boarding doors such as L1 or R2, each in a cross-aisle spread
+//! evenly from the front of the cabin (door 1) to the back. Doors on the left
+//! open onto the leftmost aisle, and doors on the right onto the rightmost.
+//! Without any doors, the aircraft is given L1. Passengers board through the
+//! door nearest their seat, as otherwise those heading for opposite ends of
+//! the cabin meet head on in the aisle.
+//!
+//! First and business class seats are given premium legroom, and premium
+//! economy seats and exit rows extra legroom.
+
+use super::super::aircraft::Aircraft;
+use super::super::aircraft::cabin::{str_to_class, Class, Exit};
+use super::super::aircraft::tile::Legroom;
+use super::definition::{Definition, RowDefinition};
+
+/// The aircraft bundled with the simulator, as pairs of names and seat maps.
+pub const BUNDLED: [(&str, &str); 8] = [
+    ("a220-300", "rows 1-3: AC-DF business, rows 4-28: AC-DEF economy, \
+                  exits at 11 and 12, doors L1 and L2"),
+    ("a320", "rows 1-3: AC-DF business, rows 4-30: ABC-DEF economy, \
+              exits at 10 and 11, doors L1 and L2"),
+    ("a321", "rows 1-4: AC-DF business, rows 5-37: ABC-DEF economy, \
+              exits at 11, 12 and 26, doors L1, L2, L3 and L4"),
+    ("737-800", "rows 1-4: AC-DF first, rows 5-32: ABC-DEF economy, \
+                 exits at 15 and 16, doors L1 and L2"),
+    ("e190", "rows 1-26: AC-DF economy, exits at 11 and 12, doors L1 and L2"),
+    ("787-9", "rows 1-8: A-DF-K business, rows 20-44: ABC-DEF-GHK economy, \
+               doors L1, L2, L3 and L4"),
+    ("777-300er", "rows 1-8: A-EF-K business, rows 20-54: ABC-DEFG-HJK \
+                   economy, doors L1, L2, L3, L4 and L5"),
+    ("a350-900", "rows 1-11: A-DF-K business, rows 20-46: ABC-DEF-GHK \
+                  economy, doors L1, L2, L3 and L4"),
+];
+
+/// A run of rows that share the same seats.
+struct Section {
+    numbers: Vec<u16>,
+    groups: Vec<Vec<char>>,
+    class: Option<Class>,
+}
+
+impl Section {
+    fn has_seat(&self, letter: char) -> bool {
+        self.groups.iter().any(|group| group.contains(&letter))
+    }
+
+    fn seat_count(&self) -> usize {
+        self.groups.iter().map(Vec::len).sum()
+    }
+}
+
+/// The lists that may carry on past a comma, as in "doors L1, L2 and L3".
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum List {
+    Exits,
+    Doors,
+}
+
+/// Reads the range of rows and seat letters following "rows".
+fn parse_section(text: &str) -> Result<Section, &'static str> {
+    let (range, seats) = text.split_once(':')
+        .ok_or("Rows need a seat layout, such as \"rows 1-4: AC-DF\"")?;
+    let (first, last) = range.split_once('-').unwrap_or((range, range));
+    let first: u16 = first.trim().parse().map_err(|_| "Invalid row range")?;
+    let last: u16 = last.trim().parse().map_err(|_| "Invalid row range")?;
+    if first > last {
+        return Err("Invalid row range");
+    }
+
+    let mut words = seats.split_whitespace();
+    let groups: Vec<Vec<char>> = words.next()
+        .ok_or("Rows need a seat layout, such as \"rows 1-4: AC-DF\"")?
+        .split('-')
+        .map(|group| group.chars().map(|c| c.to_ascii_uppercase()).collect())
+        .collect();
+    if groups.iter().any(|group: &Vec<char>| group.is_empty()
+                         || !group.iter().all(char::is_ascii_alphabetic)) {
+        return Err("Invalid seat letters");
+    }
+    let mut letters: Vec<char> = groups.iter().flatten().copied().collect();
+    letters.sort_unstable();
+    if letters.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err("Seat letter used twice in one row");
+    }
+
+    let class = words.collect::<Vec<&str>>().join(" ");
+    let class = if class.is_empty() {
+        None
+    } else {
+        Some(str_to_class(&class).ok_or("Unknown cabin class")?)
+    };
+    Ok(Section {
+        numbers: (first..=last).collect(),
+        groups,
+        class,
+    })
+}
+
+/// Reads a list of row numbers, such as "at 12 and 13".
+fn parse_exits(text: &str) -> Result<Vec<u16>, &'static str> {
+    text.split_whitespace()
+        .filter(|word| !["at", "and", "row", "rows"].contains(word))
+        .map(|word| word.parse().map_err(|_| "Invalid exit row"))
+        .collect()
+}
+
+/// Reads a list of doors, such as "L1 and R1".
+fn parse_doors(text: &str) -> Result<Vec<(char, u16)>, &'static str> {
+    text.split_whitespace()
+        .filter(|word| *word != "and")
+        .map(|word| {
+            let side = word.chars().next()
+                .map(|c| c.to_ascii_uppercase())
+                .filter(|c| *c == 'L' || *c == 'R')
+                .ok_or("Doors must be named L or R and a number, such as L1")?;
+            let number = word[1..].parse::<u16>().ok()
+                .filter(|n| *n > 0)
+                .ok_or("Doors must be named L or R and a number, such as L1")?;
+            Ok((side, number))
+        })
+        .collect()
+}
+
+/// Reads a seat map into an aircraft definition.
+///
+/// # Examples
+///
+/// ```
+/// # use aircraft_sim::config::seat_map::parse_seat_map;
+/// let definition = parse_seat_map("rows 1-20: ABC-DEF, doors L1").unwrap();
+///
+/// assert_eq!(definition.columns, "ABC DEF");
+/// ```
+pub fn parse_seat_map(spec: &str) -> Result<Definition, &'static str> {
+    let mut sections = Vec::<Section>::new();
+    let mut exits = Vec::<u16>::new();
+    let mut doors = Vec::<(char, u16)>::new();
+    let mut list = None;
+
+    for clause in spec.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let (keyword, rest) = clause.split_once(char::is_whitespace)
+            .unwrap_or((clause, ""));
+        match keyword.to_lowercase().as_str() {
+            "row" | "rows" => {
+                sections.push(parse_section(rest)?);
+                list = None;
+            },
+            "exit" | "exits" => {
+                exits.extend(parse_exits(rest)?);
+                list = Some(List::Exits);
+            },
+            "door" | "doors" => {
+                doors.extend(parse_doors(rest)?);
+                list = Some(List::Doors);
+            },
+            _ => match list {
+                Some(List::Exits) => exits.extend(parse_exits(clause)?),
+                Some(List::Doors) => doors.extend(parse_doors(clause)?),
+                None => return Err("Unknown clause in seat map"),
+            },
+        }
+    }
+
+    let numbers: Vec<u16> = sections.iter()
+        .flat_map(|section| section.numbers.iter().copied())
+        .collect();
+    if numbers.is_empty() {
+        return Err("Seat map has no rows");
+    }
+    if numbers.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err("Rows must be numbered from front to back");
+    }
+    if exits.iter().any(|exit| !numbers.contains(exit)) {
+        return Err("Exit row isn't in the seat map");
+    }
+
+    // Columns follow the widest section, taking the first of any ties, with
+    // the letters only used by other sections slotted in alphabetically.
+    let widest = sections.iter().rev()
+        .max_by_key(|section| section.seat_count())
+        .unwrap();
+    let aisles_before: Vec<char> = widest.groups[1..].iter()
+        .map(|group| group[0])
+        .collect();
+    let mut letters: Vec<char> = sections.iter()
+        .flat_map(|section| section.groups.iter().flatten().copied())
+        .collect();
+    letters.sort_unstable();
+    letters.dedup();
+
+    let mut columns = Vec::<Option<char>>::new();
+    for letter in letters {
+        if aisles_before.contains(&letter) {
+            columns.push(None);
+        }
+        columns.push(Some(letter));
+    }
+    let aisles: Vec<u16> = columns.iter().enumerate()
+        .filter(|(_, column)| column.is_none())
+        .map(|(x, _)| x as u16)
+        .collect();
+    if aisles.is_empty() {
+        return Err("Seat map has no aisle");
+    }
+
+    if doors.is_empty() {
+        doors.push(('L', 1));
+    }
+    let mut door_numbers: Vec<u16> = doors.iter().map(|door| door.1).collect();
+    door_numbers.sort_unstable();
+    door_numbers.dedup();
+    // The seat row each door's cross-aisle is placed in front of
+    let seat_rows = numbers.len();
+    let spread = door_numbers.len().saturating_sub(1).max(1);
+    let door_positions: Vec<usize> = (0..door_numbers.len())
+        .map(|i| (i * seat_rows + spread / 2) / spread)
+        .collect();
+
+    let cross_aisle = RowDefinition {
+        tiles: "-".repeat(columns.len()),
+        ..RowDefinition::default()
+    };
+    let mut rows = Vec::<RowDefinition>::new();
+    let mut door_rows = vec![0; door_numbers.len()];
+    let seats = sections.iter()
+        .flat_map(|section| section.numbers.iter().map(move |n| (section, *n)));
+    for (i, (section, number)) in seats.enumerate() {
+        for (d, position) in door_positions.iter().enumerate() {
+            if *position == i {
+                door_rows[d] = rows.len() as u16;
+                rows.push(cross_aisle.clone());
+            }
+        }
+        let exit = exits.contains(&number);
+        rows.push(RowDefinition {
+            number: Some(number),
+            class: section.class,
+            bin_capacity: None,
+            legroom: legroom(section.class, exit),
+            aisle_width: None,
+            tiles: columns.iter()
+                .map(|column| match column {
+                    Some(letter) if section.has_seat(*letter) => '#',
+                    _ if exit => 'X',
+                    _ => '.',
+                })
+                .collect(),
+        });
+    }
+    for (d, position) in door_positions.iter().enumerate() {
+        if *position == seat_rows {
+            door_rows[d] = rows.len() as u16;
+            rows.push(cross_aisle.clone());
+        }
+    }
+
+    let exits = doors.iter()
+        .map(|(side, number)| {
+            let d = door_numbers.iter().position(|n| n == number).unwrap();
+            let x = match side {
+                'L' => aisles[0],
+                _ => aisles[aisles.len() - 1],
+            };
+            Exit {
+                x,
+                y: door_rows[d],
+                name: Some(format!("{}{}", side, number)),
+            }
+        })
+        .collect();
+
+    Ok(Definition {
+        name: None,
+        columns: columns.iter().map(|column| column.unwrap_or(' ')).collect(),
+        rows,
+        exits,
+        aisles: Vec::new(),
+    })
+}
+
+/// Builds the aircraft described by a seat map.
+pub fn read_seat_map(spec: &str) -> Result<Aircraft, &'static str> {
+    let mut aircraft = parse_seat_map(spec)?.to_aircraft()?;
+    aircraft.set_nearest_door(true);
+    Ok(aircraft)
+}
+
+/// The legroom given to the seats on a row of `class`, or beside an exit if
+/// `exit` is set, if they have more than standard legroom.
+fn legroom(class: Option<Class>, exit: bool) -> Option<Legroom> {
+    match class {
+        Some(Class::First) | Some(Class::Business) => Some(Legroom::Premium),
+        Some(Class::PremiumEconomy) => Some(Legroom::Extra),
+        _ if exit => Some(Legroom::Extra),
+        _ => None,
+    }
+}
+
+/// Builds the bundled aircraft called `name`, one of `BUNDLED`.
+pub fn bundled(name: &str) -> Option<Aircraft> {
+    let (name, spec) = BUNDLED.iter()
+        .find(|(bundled, _)| bundled.eq_ignore_ascii_case(name.trim()))?;
+    let mut definition = parse_seat_map(spec).ok()?;
+    definition.name = Some(name.to_string());
+    let mut aircraft = definition.to_aircraft().ok()?;
+    aircraft.set_nearest_door(true);
+    Some(aircraft)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::aircraft::person::Person;
+    use super::super::super::aircraft::tile::Variant;
+
+    #[test]
+    fn narrowbody() {
+        let aircraft = read_seat_map("rows 1-4: AC-DF business, \
+                                      rows 5-30: ABC-DEF economy, \
+                                      exits at 12 and 13, doors L1 and L2")
+            .unwrap();
+        // 30 rows of seats behind the front door and ahead of the back door
+        assert_eq!(aircraft.get_size(), (7, 32));
+        assert_eq!(aircraft.get_tile_variant(3, 0), Variant::Entrance);
+        assert_eq!(aircraft.get_tile_variant(3, 31), Variant::Entrance);
+        assert_eq!(aircraft.get_tile_variant(0, 0), Variant::CrossAisle);
+        assert_eq!(aircraft.get_tile_variant(1, 1), Variant::Aisle,
+                   "Business row had a middle seat");
+        assert_eq!(aircraft.get_tile_variant(1, 5), Variant::Seat);
+
+        assert_eq!(aircraft.seat_label(0, 1), Some("1A".to_string()));
+        assert_eq!(aircraft.seat_label(2, 1), Some("1C".to_string()));
+        assert_eq!(aircraft.find_seat("13A"), Some((0, 13)));
+        assert_eq!(aircraft.find_seat("30F"), Some((6, 30)));
+        assert_eq!(aircraft.find_seat("1B"), None);
+
+        assert_eq!(aircraft.get_legroom(0, 1), Legroom::Premium);
+        assert_eq!(aircraft.get_legroom(0, 12), Legroom::Extra,
+                   "Exit row didn't have extra legroom");
+        assert_eq!(aircraft.get_legroom(0, 14), Legroom::Standard);
+
+        let cabin = aircraft.get_cabin();
+        assert_eq!(cabin.get_class(1), Some(Class::Business));
+        assert_eq!(cabin.get_class(5), Some(Class::Economy));
+        assert_eq!(aircraft.get_tile_variant(3, 12), Variant::ExitRow);
+        assert_eq!(aircraft.get_tile_variant(3, 13), Variant::ExitRow);
+        assert_eq!(aircraft.get_tile_variant(3, 14), Variant::Aisle);
+        assert_eq!(cabin.exits[1].name, Some("L2".to_string()));
+    }
+
+    #[test]
+    fn doors() {
+        let definition = parse_seat_map("rows 1-10: AB-CD, \
+                                         doors L1, R1, L2 and L3")
+            .unwrap();
+        let doors: Vec<(u16, u16)> = definition.exits.iter()
+            .map(|exit| (exit.x, exit.y))
+            .collect();
+        assert_eq!(doors, vec![(2, 0), (2, 0), (2, 6), (2, 12)]);
+        assert_eq!(definition.rows.len(), 13);
+
+        let widebody = parse_seat_map("rows 1-4: A-DF-K business, \
+                                       rows 5-10: ABC-DEF-GHK economy, \
+                                       door L1, door R1")
+            .unwrap();
+        assert_eq!(widebody.columns, "ABC DEF GHK");
+        assert_eq!(widebody.rows[1].tiles, "#...#.#...#");
+        assert_eq!(widebody.exits[0].x, 3);
+        assert_eq!(widebody.exits[1].x, 7);
+
+        let default = parse_seat_map("rows 1-2: A-B").unwrap();
+        assert_eq!(default.exits[0].name, Some("L1".to_string()));
+        assert_eq!(default.exits[0].y, 0);
+    }
+
+    #[test]
+    fn invalid_seat_maps() {
+        for spec in ["",
+                     "rows 1-4 AC-DF",
+                     "rows 5-1: AB-CD",
+                     "rows 1-2: ABC",
+                     "rows 1-2: A1-CD",
+                     "rows 1-2: AB-BC",
+                     "rows 1-2: AB-CD, exits at 5",
+                     "rows 3-4: AB-CD, rows 1-2: AB-CD",
+                     "seats 1-2: AB-CD",
+                     "rows 1-2: AB-CD, doors X1",
+                     "rows 1-2: AB-CD steerage"] {
+            assert!(parse_seat_map(spec).is_err(), "Accepted '{}'", spec);
+        }
+    }
+
+    #[test]
+    fn bundled_aircraft() {
+        assert!(bundled("concorde").is_none());
+        assert_eq!(bundled("A320").unwrap().get_cabin().name,
+                   Some("a320".to_string()));
+
+        for (name, _) in BUNDLED {
+            let mut aircraft = bundled(name).unwrap();
+            let (size_x, size_y) = aircraft.get_size();
+            for y in (0..size_y).rev() {
+                for x in 0..size_x {
+                    if aircraft.get_tile_variant(x, y) == Variant::Seat {
+                        let mut person = Person::new("DEFAULT");
+                        person.target_seat(x, y);
+                        aircraft.add_passenger(person);
+                    }
+                }
+            }
+            assert!(aircraft.run_to_completion().is_ok(),
+                    "{} couldn't be boarded", name);
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! Draws simulations to image files without needing a display.
+//!
+//! Each step can be written as an SVG or PNG image, or every step collected
+//! into a single animated GIF. Tiles are drawn using the same sprites as the
+//! Python frontend, found in `images/`, and can be overlaid with the step
+//! number, a colour for each passenger's boarding zone and a heatmap.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::{Delay, Frame, ImageFormat, Pixel, Rgba, RgbaImage};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops;
+
+use super::aircraft::{Aircraft, MAX_ITERATIONS};
+use super::aircraft::cabin::SeatMap;
+use super::aircraft::tile::Variant;
+use super::aircraft::heatmap::{Counter, Heatmap};
+
+/// The width and height of a single tile, in pixels.
+pub const TILE_SIZE: u32 = 25;
+
+const BACKGROUND: [u8; 4] = [0, 0, 255, 255];
+const ENTRANCE: [u8; 4] = [0, 160, 0, 255];
+const NONE: [u8; 4] = [40, 40, 40, 255];
+const GALLEY: [u8; 4] = [150, 150, 150, 255];
+const LAVATORY: [u8; 4] = [90, 90, 170, 255];
+const BULKHEAD: [u8; 4] = [0, 0, 0, 255];
+const EXIT_ROW: [u8; 4] = [0, 100, 0, 255];
+const CROSS_AISLE: [u8; 4] = [0, 0, 200, 255];
+const SEAT_SPAN: [u8; 4] = [200, 200, 200, 255];
+const HEAT: [u8; 3] = [255, 0, 0];
+
+/// Colours given to passengers, cycling through by boarding zone.
+const ZONE_COLOURS: [[u8; 3]; 6] = [[0, 255, 255],
+                                    [255, 255, 0],
+                                    [255, 0, 255],
+                                    [0, 255, 0],
+                                    [255, 128, 0],
+                                    [255, 0, 0]];
+
+/// Digits drawn as 3x5 bitmaps, one row per byte with the leftmost pixel in
+/// the highest of the lowest three bits.
+const DIGITS: [[u8; 5]; 10] = [[7, 5, 5, 5, 7],
+                               [2, 6, 2, 2, 7],
+                               [7, 1, 7, 4, 7],
+                               [7, 1, 7, 1, 7],
+                               [5, 5, 7, 1, 1],
+                               [7, 4, 7, 1, 7],
+                               [7, 4, 7, 5, 7],
+                               [7, 1, 1, 1, 1],
+                               [7, 5, 7, 5, 7],
+                               [7, 5, 7, 1, 7]];
+const DIGIT_SCALE: u32 = 2;
+
+/// The image formats frames can be written in.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Format {
+    Svg,
+    Png,
+    Gif,
+}
+
+/// Converts a string into an associated image format.
+pub fn str_to_format(format: &str) -> Option<Format> {
+    match format {
+        "svg" => Some(Format::Svg),
+        "png" => Some(Format::Png),
+        "gif" => Some(Format::Gif),
+        _ => None,
+    }
+}
+
+/// The images drawn for seats, passengers, and passengers making their way
+/// past another.
+///
+/// The encoded PNG of each is kept so that it can be embedded in SVGs as is.
+pub struct Sprites {
+    seat: (Vec<u8>, RgbaImage),
+    pass: (Vec<u8>, RgbaImage),
+    allo: (Vec<u8>, RgbaImage),
+}
+
+impl Sprites {
+    /// Constructor using the sprites shipped with the crate.
+    pub fn bundled() -> Sprites {
+        Sprites::from_png(include_bytes!("../../images/seat.png").to_vec(),
+                          include_bytes!("../../images/pass.png").to_vec(),
+                          include_bytes!("../../images/allo.png").to_vec())
+            .expect("Bundled sprites are invalid")
+    }
+
+    /// Constructor loading `seat.png`, `pass.png` and `allo.png` from `dir`.
+    pub fn load(dir: &Path) -> Result<Sprites, &'static str> {
+        let read = |name: &str| fs::read(dir.join(name))
+            .map_err(|_| "Couldn't read sprite");
+        Sprites::from_png(read("seat.png")?,
+                          read("pass.png")?,
+                          read("allo.png")?)
+    }
+
+    fn from_png(seat: Vec<u8>, pass: Vec<u8>, allo: Vec<u8>)
+        -> Result<Sprites, &'static str> {
+        let decode = |png: Vec<u8>| {
+            image::load_from_memory_with_format(&png, ImageFormat::Png)
+                .map(|image| (png, image.to_rgba8()))
+                .map_err(|_| "Couldn't decode sprite")
+        };
+        Ok(Sprites {
+            seat: decode(seat)?,
+            pass: decode(pass)?,
+            allo: decode(allo)?,
+        })
+    }
+}
+
+/// Extra information drawn over each frame.
+///
+/// `heatmap` holds a value between 0 and 1 for each tile, indexed by column
+/// then row like the aircraft's layout, and is drawn as red of that opacity.
+/// `hide_passengers` leaves passengers out so the heatmap can be seen clearly.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct Overlays {
+    pub step: bool,
+    pub zones: bool,
+    pub heatmap: Option<Vec<Vec<f32>>>,
+    pub hide_passengers: bool,
+}
+
+fn zone_colour(zone: u16) -> [u8; 3] {
+    ZONE_COLOURS[zone as usize % ZONE_COLOURS.len()]
+}
+
+fn heat(overlays: &Overlays, x: u16, y: u16) -> Option<f32> {
+    overlays.heatmap.as_ref()
+        .and_then(|map| map.get(x as usize)?.get(y as usize).copied())
+        .map(|value| value.clamp(0.0, 1.0))
+        .filter(|value| *value > 0.0)
+}
+
+/// Draws the aircraft's current step as an SVG document.
+///
+/// Each seat is titled with its label, which viewers show when it is hovered.
+pub fn svg(aircraft: &Aircraft, sprites: &Sprites, overlays: &Overlays)
+    -> String {
+    let (size_x, size_y) = aircraft.get_size();
+    let width = size_x as u32 * TILE_SIZE;
+    let height = size_y as u32 * TILE_SIZE;
+    let rgb = |c: &[u8]| format!("rgb({},{},{})", c[0], c[1], c[2]);
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" \
+                           xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+                           width=\"{0}\" height=\"{1}\" \
+                           viewBox=\"0 0 {0} {1}\">\n<defs>\n",
+                          width, height);
+    for (id, (png, image)) in [("seat", &sprites.seat),
+                               ("pass", &sprites.pass),
+                               ("allo", &sprites.allo)] {
+        svg += &format!("<image id=\"{}\" width=\"{}\" height=\"{}\" \
+                         xlink:href=\"data:image/png;base64,{}\"/>\n",
+                        id, image.width(), image.height(),
+                        STANDARD.encode(png));
+    }
+    svg += &format!("</defs>\n<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    width, height, rgb(&BACKGROUND));
+
+    let seat_map = SeatMap::new(aircraft);
+    for x in 0..size_x {
+        for y in 0..size_y {
+            let (px, py) = (x as u32 * TILE_SIZE, y as u32 * TILE_SIZE);
+            let tile = |fill: &[u8]| format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" \
+                 fill=\"{}\"/>\n", px, py, TILE_SIZE, TILE_SIZE, rgb(fill));
+            svg += &match aircraft.get_tile_variant(x, y) {
+                Variant::Seat => match seat_map.label(x, y) {
+                    Some(label) => format!("<use xlink:href=\"#seat\" \
+                                            x=\"{}\" y=\"{}\"><title>{}\
+                                            </title></use>\n",
+                                           px, py, label),
+                    None => format!("<use xlink:href=\"#seat\" x=\"{}\" \
+                                     y=\"{}\"/>\n", px, py),
+                },
+                Variant::Entrance => tile(&ENTRANCE),
+                Variant::None => tile(&NONE),
+                Variant::Galley => tile(&GALLEY),
+                Variant::Lavatory => tile(&LAVATORY),
+                Variant::Bulkhead => tile(&BULKHEAD),
+                Variant::ExitRow => tile(&EXIT_ROW),
+                Variant::CrossAisle => tile(&CROSS_AISLE),
+                Variant::SeatSpan => tile(&SEAT_SPAN),
+                Variant::Aisle => String::new(),
+            };
+            if let Some(value) = heat(overlays, x, y) {
+                svg += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" \
+                                 height=\"{}\" fill=\"{}\" \
+                                 fill-opacity=\"{}\"/>\n",
+                                px, py, TILE_SIZE, TILE_SIZE, rgb(&HEAT),
+                                value * 0.75);
+            }
+        }
+    }
+
+    if !overlays.hide_passengers {
+        for x in 0..size_x {
+            for y in 0..size_y {
+                let (px, py) = (x as u32 * TILE_SIZE, y as u32 * TILE_SIZE);
+                if let Some(person) = aircraft.get_occupier(x, y) {
+                    svg += &format!("<use xlink:href=\"#pass\" x=\"{}\" \
+                                     y=\"{}\"/>\n", px, py);
+                    if overlays.zones {
+                        svg += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" \
+                                         height=\"{}\" fill=\"{}\" \
+                                         fill-opacity=\"0.5\"/>\n",
+                                        px, py, TILE_SIZE, TILE_SIZE,
+                                        rgb(&zone_colour(person.get_zone())));
+                    }
+                }
+                if aircraft.get_passer(x, y).is_some() {
+                    svg += &format!("<use xlink:href=\"#allo\" x=\"{}\" \
+                                     y=\"{}\"/>\n", px, py);
+                }
+            }
+        }
+    }
+
+    if overlays.step {
+        svg += &format!("<text x=\"4\" y=\"16\" font-family=\"monospace\" \
+                         font-size=\"14\" fill=\"white\" stroke=\"black\" \
+                         stroke-width=\"0.5\">{}</text>\n",
+                        aircraft.get_step());
+    }
+    svg + "</svg>\n"
+}
+
+/// Blends a rectangle of `colour` into `image`, clipped to its bounds.
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32,
+        colour: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.get_pixel_mut(px, py).blend(&colour);
+        }
+    }
+}
+
+/// Draws `number` in white on a black box in the top-left corner.
+fn draw_number(image: &mut RgbaImage, number: u16) {
+    let text = number.to_string();
+    let width = (text.len() as u32 * 4 + 1) * DIGIT_SCALE;
+    fill(image, 0, 0, width, 7 * DIGIT_SCALE, Rgba([0, 0, 0, 255]));
+    for (i, digit) in text.bytes().enumerate() {
+        let bitmap = DIGITS[(digit - b'0') as usize];
+        for (row, bits) in bitmap.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (4 >> column) != 0 {
+                    fill(image,
+                         (i as u32 * 4 + 1 + column) * DIGIT_SCALE,
+                         (row as u32 + 1) * DIGIT_SCALE,
+                         DIGIT_SCALE,
+                         DIGIT_SCALE,
+                         Rgba([255, 255, 255, 255]));
+                }
+            }
+        }
+    }
+}
+
+/// Draws the aircraft's current step as an image.
+pub fn raster(aircraft: &Aircraft, sprites: &Sprites, overlays: &Overlays)
+    -> RgbaImage {
+    let (size_x, size_y) = aircraft.get_size();
+    let mut image = RgbaImage::from_pixel(size_x as u32 * TILE_SIZE,
+                                          size_y as u32 * TILE_SIZE,
+                                          Rgba(BACKGROUND));
+
+    for x in 0..size_x {
+        for y in 0..size_y {
+            let (px, py) = (x as u32 * TILE_SIZE, y as u32 * TILE_SIZE);
+            match aircraft.get_tile_variant(x, y) {
+                Variant::Seat => imageops::overlay(&mut image,
+                                                   &sprites.seat.1,
+                                                   px as i64,
+                                                   py as i64),
+                Variant::Entrance => fill(&mut image, px, py, TILE_SIZE,
+                                          TILE_SIZE, Rgba(ENTRANCE)),
+                Variant::Aisle => {},
+                variant => fill(&mut image, px, py, TILE_SIZE, TILE_SIZE,
+                                Rgba(match variant {
+                                    Variant::Galley => GALLEY,
+                                    Variant::Lavatory => LAVATORY,
+                                    Variant::Bulkhead => BULKHEAD,
+                                    Variant::ExitRow => EXIT_ROW,
+                                    Variant::CrossAisle => CROSS_AISLE,
+                                    Variant::SeatSpan => SEAT_SPAN,
+                                    _ => NONE,
+                                })),
+            }
+            if let Some(value) = heat(overlays, x, y) {
+                let alpha = (value * 0.75 * 255.0) as u8;
+                fill(&mut image, px, py, TILE_SIZE, TILE_SIZE,
+                     Rgba([HEAT[0], HEAT[1], HEAT[2], alpha]));
+            }
+        }
+    }
+
+    if !overlays.hide_passengers {
+        for x in 0..size_x {
+            for y in 0..size_y {
+                let (px, py) = (x as u32 * TILE_SIZE, y as u32 * TILE_SIZE);
+                if let Some(person) = aircraft.get_occupier(x, y) {
+                    imageops::overlay(&mut image, &sprites.pass.1,
+                                      px as i64, py as i64);
+                    if overlays.zones {
+                        let [r, g, b] = zone_colour(person.get_zone());
+                        fill(&mut image, px, py, TILE_SIZE, TILE_SIZE,
+                             Rgba([r, g, b, 128]));
+                    }
+                }
+                if aircraft.get_passer(x, y).is_some() {
+                    imageops::overlay(&mut image, &sprites.allo.1,
+                                      px as i64, py as i64);
+                }
+            }
+        }
+    }
+
+    if overlays.step {
+        draw_number(&mut image, aircraft.get_step());
+    }
+    image
+}
+
+/// Writes the aircraft's current step to `path` as an SVG or PNG, chosen by
+/// the file's extension.
+pub fn write_frame(aircraft: &Aircraft, path: &Path, sprites: &Sprites,
+                   overlays: &Overlays) -> Result<(), &'static str> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => fs::write(path, svg(aircraft, sprites, overlays))
+            .map_err(|_| "Couldn't write frame"),
+        Some("png") => raster(aircraft, sprites, overlays)
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(|_| "Couldn't write frame"),
+        _ => Err("Frames must be written as .svg or .png"),
+    }
+}
+
+/// Draws one of a heatmap's counters over the aircraft's layout, leaving out
+/// passengers, and writes it to `path` as an SVG or PNG.
+///
+/// The busiest tile is drawn darkest.
+pub fn write_heatmap(aircraft: &Aircraft, heatmap: &Heatmap, counter: Counter,
+                     path: &Path, sprites: &Sprites)
+    -> Result<(), &'static str> {
+    if heatmap.get_size() != aircraft.get_size() {
+        return Err("Heatmap doesn't match the aircraft's size");
+    }
+    let overlays = Overlays {
+        heatmap: Some(heatmap.normalised(counter)),
+        hide_passengers: true,
+        ..Overlays::default()
+    };
+    write_frame(aircraft, path, sprites, &overlays)
+}
+
+/// Simulates the aircraft to completion, drawing every step along the way.
+///
+/// SVG and PNG frames are written into the directory at `path`, which is
+/// created if needed, as `step_0000.svg` and so on. GIFs are written to `path`
+/// as a single animation, showing each step for `delay` milliseconds. Returns
+/// the number of steps taken.
+pub fn export(aircraft: &mut Aircraft, path: &Path, format: Format,
+              sprites: &Sprites, overlays: &Overlays, delay: u32)
+    -> Result<u16, &'static str> {
+    let mut frames = Vec::<Frame>::new();
+    let dir = match format {
+        Format::Gif => path.parent(),
+        _ => Some(path),
+    };
+    if let Some(dir) = dir {
+        fs::create_dir_all(dir).map_err(|_| "Couldn't create directory")?;
+    }
+
+    let mut iterations = 0;
+    loop {
+        match format {
+            Format::Svg => write_frame(aircraft,
+                                       &path.join(format!("step_{:04}.svg",
+                                                          iterations)),
+                                       sprites, overlays)?,
+            Format::Png => write_frame(aircraft,
+                                       &path.join(format!("step_{:04}.png",
+                                                          iterations)),
+                                       sprites, overlays)?,
+            Format::Gif => frames.push(Frame::from_parts(
+                raster(aircraft, sprites, overlays), 0, 0,
+                Delay::from_numer_denom_ms(delay, 1))),
+        }
+        if aircraft.is_complete() || iterations >= MAX_ITERATIONS {
+            break;
+        }
+        aircraft.update();
+        iterations += 1;
+    }
+
+    if format == Format::Gif {
+        let file = File::create(path).map_err(|_| "Couldn't create file")?;
+        let mut encoder = GifEncoder::new(BufWriter::new(file));
+        encoder.set_repeat(Repeat::Infinite)
+            .and_then(|_| encoder.encode_frames(frames))
+            .map_err(|_| "Couldn't write animation")?;
+    }
+    log::info!("Exported {} frames", iterations + 1);
+
+    if aircraft.is_complete() {
+        Ok(iterations)
+    } else {
+        Err("Passengers could not all be seated.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::aircraft::person::Person;
+    use super::super::config;
+
+    fn aircraft() -> Aircraft {
+        let mut aircraft = Aircraft::new(2, 3);
+        for y in 0..3 {
+            aircraft.set_tile(0, y, Variant::Seat);
+        }
+        aircraft.set_tile(1, 2, Variant::Entrance);
+        let mut person = Person::new("DEFAULT");
+        person.target_seat(0, 0);
+        person.set_zone(1);
+        aircraft.add_passenger(person);
+        aircraft
+    }
+
+    #[test]
+    fn test_str_to_format() {
+        assert_eq!(str_to_format("svg"), Some(Format::Svg));
+        assert_eq!(str_to_format("gif"), Some(Format::Gif));
+        assert_eq!(str_to_format("jpeg"), None);
+    }
+
+    #[test]
+    fn frames() {
+        let sprites = Sprites::bundled();
+        let mut aircraft = aircraft();
+        aircraft.update();
+
+        let image = raster(&aircraft, &sprites, &Overlays::default());
+        assert_eq!(image.dimensions(), (2 * TILE_SIZE, 3 * TILE_SIZE));
+        assert_eq!(image.get_pixel(TILE_SIZE + 12, TILE_SIZE + 12),
+                   &Rgba(BACKGROUND));
+
+        let overlays = Overlays {
+            step: true,
+            zones: true,
+            heatmap: Some(vec![vec![0.0; 3], vec![0.0, 1.0, 0.0]]),
+            hide_passengers: false,
+        };
+        let image = raster(&aircraft, &sprites, &overlays);
+        assert_ne!(image.get_pixel(TILE_SIZE + 12, TILE_SIZE + 12),
+                   &Rgba(BACKGROUND), "Heatmap wasn't drawn");
+        assert_eq!(image.get_pixel(DIGIT_SCALE * 2, DIGIT_SCALE),
+                   &Rgba([255, 255, 255, 255]), "Step wasn't drawn");
+
+        let svg = svg(&aircraft, &sprites, &overlays);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("xlink:href=\"#seat\"").count(), 3);
+        assert!(svg.contains("<title>3A</title>"));
+        assert_eq!(svg.matches("xlink:href=\"#pass\"").count(), 1);
+        assert!(svg.contains(">1</text>"));
+
+        let hidden = Overlays {
+            hide_passengers: true,
+            ..overlays
+        };
+        let svg = super::svg(&aircraft, &sprites, &hidden);
+        assert_eq!(svg.matches("xlink:href=\"#pass\"").count(), 0);
+    }
+
+    #[test]
+    fn export_frames() {
+        let dir = std::env::temp_dir().join("aircraft_sim_export_test");
+        let _ = fs::remove_dir_all(&dir);
+        let sprites = Sprites::bundled();
+
+        let steps = export(&mut aircraft(), &dir, Format::Png, &sprites,
+                           &Overlays::default(), 100).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), steps as usize + 1);
+        assert!(dir.join("step_0000.png").exists());
+
+        let gif = dir.join("boarding.gif");
+        export(&mut aircraft(), &gif, Format::Gif, &sprites,
+               &Overlays::default(), 100).unwrap();
+        assert!(fs::metadata(&gif).unwrap().len() > 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn zones_from_file() {
+        let path = std::env::temp_dir().join("aircraft_sim_export_zones.csv");
+        let list = config::random_back_first(7, 10, 1.0).unwrap();
+        config::write_passengers(&list, &path).unwrap();
+        let mut aircraft = config::standard_layout(7, 10).unwrap();
+        for person in config::read_passengers(&path).unwrap() {
+            aircraft.add_passenger(person);
+        }
+        aircraft.run_to_completion().unwrap();
+
+        let overlays = Overlays {
+            zones: true,
+            ..Overlays::default()
+        };
+        let svg = svg(&aircraft, &Sprites::bundled(), &overlays);
+        let colours = ZONE_COLOURS.iter()
+            .map(|[r, g, b]| format!("fill=\"rgb({},{},{})\" \
+                                      fill-opacity=\"0.5\"", r, g, b))
+            .filter(|fill| svg.contains(fill.as_str()))
+            .count();
+        assert_eq!(colours, ZONE_COLOURS.len(),
+                   "Passengers read from a file weren't coloured by zone");
+    }
+}
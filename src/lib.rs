@@ -2,27 +2,49 @@
 //! Python library that can access compiled Rust. These are essentially wrappers
 //! around the different modules written for this project, providing
 //! functionality to the basic Python GUI.
+//!
+//! The `aircraft`, `config`, `batch` and `export` modules are also public, so
+//! that Rust crates can run and draw simulations directly and plug in their
+//! own `AgentPolicy`.
 
-mod aircraft;
-mod config;
+pub mod aircraft;
+pub mod config;
+pub mod batch;
+pub mod export;
 
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::wrap_pyfunction;
 use pyo3::create_exception;
-use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::exceptions::{PyException, PyTypeError, PyValueError};
 
 use std::path::Path;
-use std::thread;
 
 use simple_logger::SimpleLogger;
 use log::LevelFilter;
 
 use aircraft::Aircraft;
-use aircraft::tile::Variant;
+use aircraft::cabin::Class;
+use aircraft::crew::parse_crew;
+use aircraft::gate::Gate;
+use aircraft::heatmap::{str_to_counter, Counter, Heatmap};
+use aircraft::policy::named_policy;
+use aircraft::render::{Glyphs, RenderOptions};
+use aircraft::tile::{str_to_legroom, Variant};
+use batch::Record;
 use config::*;
 
 create_exception!(PyAircraft, CustomError, PyException);
 
+/// A cabin class's `ClassMetrics` as a Python tuple of its class's name,
+/// passengers, seated passengers, mean step and last step.
+type PyClassMetrics = (Option<&'static str>, usize, usize, Option<f32>,
+                       Option<u16>);
+
+/// A `Conflict` as a Python tuple of the seat taken by mistake, the
+/// passenger's own seat, the step it was found and the step it was resolved.
+type PyConflict = ((u16, u16), (u16, u16), u16, Option<u16>);
+
 /// Python-accesible structure used for interactive mode
 ///
 /// This structure is accesible via Python and provides methods that call
@@ -60,7 +82,7 @@ impl PyAircraft {
     /// not be output. This can be modified to change the displayed log level.
     #[staticmethod]
     fn initialise_logger() -> PyResult<()> {
-        SimpleLogger::new().with_level().init().expect("Failed to initialise logger");
+        SimpleLogger::new().with_level(LevelFilter::Info).init().expect("Failed to initialise logger");
         log::info!("Initialised logger");
 
         Ok(())
@@ -68,9 +90,17 @@ impl PyAircraft {
     
     /// Initialises an interactive Aircraft object from filenames.
     ///
-    /// Initialises an interactive Aircraft object using the csv file at
+    /// Initialises an interactive Aircraft object using the layout file at
     /// `layout_path` and fills it with the passengers contained in the list at
-    /// `passengers_path`.
+    /// `passengers_path`. `no_show_rate` of the listed passengers are randomly
+    /// removed to simulate no-shows, and the rest are sent out of order, given
+    /// arrival times and grouped into parties as for `init_random`.
+    /// `layout_path` may instead name one of the `bundled_aircraft()` or give
+    /// a seat map such as "rows 1-30: ABC-DEF".
+    ///
+    /// Passengers that can't be read are skipped and logged, unless `strict`
+    /// is set, in which case the first one raises an error giving its line,
+    /// column and what was wrong with it.
     ///
     /// # Examples
     ///
@@ -84,16 +114,33 @@ impl PyAircraft {
     /// except:
     ///     print("File doesn't exist")
     /// ```
-    fn init_from_file(&mut self, layout_path: &str, passengers_path: &str)
-        -> PyResult<()> {
+    #[args(no_show_rate = "0.0", strict = "false", kwargs = "**")]
+    fn init_from_file(&mut self, layout_path: &str, passengers_path: &str,
+                      no_show_rate: f32, strict: bool,
+                      kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options = passenger_options(kwargs)?;
         if self.aircraft.is_none() {
-            let new_aircraft = read_layout(Path::new(layout_path));
-            let passengers = read_passengers(Path::new(passengers_path));
+            let mut new_aircraft = load_layout(layout_path)
+                .map_err(PyTypeError::new_err)?;
+            let passengers = if strict {
+                let path = Path::new(passengers_path);
+                Some(passengers::PassengerReader::open(path)
+                     .and_then(|reader| reader.strict())
+                     .map_err(|e| PyTypeError::new_err(e.to_string()))?)
+            } else {
+                read_passengers(Path::new(passengers_path))
+            };
+            let passengers = match passengers {
+                Some(p) => Some(remove_no_shows(p, no_show_rate)
+                                .and_then(|(p, _)| apply_options(p, &options))
+                                .map_err(PyValueError::new_err)?),
+                None => None,
+            };
             
-            if new_aircraft.is_some() && passengers.is_some() {
-                let mut new_aircraft = new_aircraft.unwrap();
-                for i in passengers.unwrap() {
-                    new_aircraft.add_passenger(i);
+            if let Some(passengers) = passengers {
+                for i in passengers {
+                    new_aircraft.try_add_passenger(i)
+                        .map_err(PyValueError::new_err)?;
                 }
                 self.aircraft = Some(new_aircraft);
                 self.size = self.aircraft.as_ref().unwrap().get_size();
@@ -112,6 +159,14 @@ impl PyAircraft {
     /// Initialises an interactive Aircraft object of the given size and fills
     /// it with passengers in a back-first pattern.
     ///
+    /// Only `load_factor` of the seats are sold, and `no_show_rate` of the
+    /// generated passengers are removed again after the order is generated.
+    /// `late` of those left don't follow the called order, either displaced
+    /// by up to `max_shift` places or arriving up to `max_delay` steps late,
+    /// and given an `arrival_rate` they reach the gate over time, that many a
+    /// step on average. `parties` of them travel in parties of up to
+    /// `max_party` passengers who sit together.
+    ///
     /// # Examples
     ///
     /// ```python
@@ -124,10 +179,17 @@ impl PyAircraft {
     /// except:
     ///     print("Invalid size")
     /// ```
-    fn init_random_back_front(&mut self,size_x:u16,size_y:u16) -> PyResult<()> {
+    #[args(load_factor = "1.0", no_show_rate = "0.0", kwargs = "**")]
+    fn init_random_back_front(&mut self, size_x: u16, size_y: u16,
+                              load_factor: f32, no_show_rate: f32,
+                              kwargs: Option<&PyDict>)
+        -> PyResult<()> {
+        let options = passenger_options(kwargs)?;
         if self.aircraft.is_none() {
             let new_aircraft = standard_layout(size_x, size_y);
-            let passengers = random_back_first(size_x, size_y);
+            let passengers = random_back_first(size_x, size_y, load_factor)
+                .and_then(|p| remove_no_shows(p, no_show_rate))
+                .and_then(|(p, _)| apply_options(p, &options));
 
             if passengers.is_ok() && new_aircraft.is_ok() {
                 let mut new_aircraft = new_aircraft.unwrap();
@@ -151,6 +213,14 @@ impl PyAircraft {
     /// Initialises an interactive Aircraft object of the given size and fills
     /// it with passengers in a front-first pattern.
     ///
+    /// Only `load_factor` of the seats are sold, and `no_show_rate` of the
+    /// generated passengers are removed again after the order is generated.
+    /// `late` of those left don't follow the called order, either displaced
+    /// by up to `max_shift` places or arriving up to `max_delay` steps late,
+    /// and given an `arrival_rate` they reach the gate over time, that many a
+    /// step on average. `parties` of them travel in parties of up to
+    /// `max_party` passengers who sit together.
+    ///
     /// # Examples
     ///
     /// ```python
@@ -163,10 +233,17 @@ impl PyAircraft {
     /// except:
     ///     print("Invalid size")
     /// ```
-    fn init_random_front_back(&mut self,size_x:u16,size_y:u16) -> PyResult<()> {
+    #[args(load_factor = "1.0", no_show_rate = "0.0", kwargs = "**")]
+    fn init_random_front_back(&mut self, size_x: u16, size_y: u16,
+                              load_factor: f32, no_show_rate: f32,
+                              kwargs: Option<&PyDict>)
+        -> PyResult<()> {
+        let options = passenger_options(kwargs)?;
         if self.aircraft.is_none() {
             let new_aircraft = standard_layout(size_x, size_y);
-            let passengers = random_front_first(size_x, size_y);
+            let passengers = random_front_first(size_x, size_y, load_factor)
+                .and_then(|p| remove_no_shows(p, no_show_rate))
+                .and_then(|(p, _)| apply_options(p, &options));
 
             if passengers.is_ok() && new_aircraft.is_ok() {
                 let mut new_aircraft = new_aircraft.unwrap();
@@ -190,6 +267,14 @@ impl PyAircraft {
     /// Initialises an interactive Aircraft object of the given size and fills
     /// it with passengers in a aisle-first pattern.
     ///
+    /// Only `load_factor` of the seats are sold, and `no_show_rate` of the
+    /// generated passengers are removed again after the order is generated.
+    /// `late` of those left don't follow the called order, either displaced
+    /// by up to `max_shift` places or arriving up to `max_delay` steps late,
+    /// and given an `arrival_rate` they reach the gate over time, that many a
+    /// step on average. `parties` of them travel in parties of up to
+    /// `max_party` passengers who sit together.
+    ///
     /// # Examples
     ///
     /// ```python
@@ -202,10 +287,17 @@ impl PyAircraft {
     /// except:
     ///     print("Invalid size")
     /// ```
-    fn init_random_aisle_first(&mut self,size_x:u16,size_y:u16)-> PyResult<()> {
+    #[args(load_factor = "1.0", no_show_rate = "0.0", kwargs = "**")]
+    fn init_random_aisle_first(&mut self, size_x: u16, size_y: u16,
+                               load_factor: f32, no_show_rate: f32,
+                               kwargs: Option<&PyDict>)
+        -> PyResult<()> {
+        let options = passenger_options(kwargs)?;
         if self.aircraft.is_none() {
             let new_aircraft = standard_layout(size_x, size_y);
-            let passengers = random_aisle_first(size_x, size_y);
+            let passengers = random_aisle_first(size_x, size_y, load_factor)
+                .and_then(|p| remove_no_shows(p, no_show_rate))
+                .and_then(|(p, _)| apply_options(p, &options));
 
             if passengers.is_ok() && new_aircraft.is_ok() {
                 let mut new_aircraft = new_aircraft.unwrap();
@@ -229,6 +321,14 @@ impl PyAircraft {
     /// Initialises an interactive Aircraft object of the given size and fills
     /// it with passengers in a window-first pattern.
     ///
+    /// Only `load_factor` of the seats are sold, and `no_show_rate` of the
+    /// generated passengers are removed again after the order is generated.
+    /// `late` of those left don't follow the called order, either displaced
+    /// by up to `max_shift` places or arriving up to `max_delay` steps late,
+    /// and given an `arrival_rate` they reach the gate over time, that many a
+    /// step on average. `parties` of them travel in parties of up to
+    /// `max_party` passengers who sit together.
+    ///
     /// # Examples
     ///
     /// ```python
@@ -241,11 +341,17 @@ impl PyAircraft {
     /// except:
     ///     print("Invalid size")
     /// ```
-    fn init_random_window_first(&mut self, size_x: u16, size_y: u16)
+    #[args(load_factor = "1.0", no_show_rate = "0.0", kwargs = "**")]
+    fn init_random_window_first(&mut self, size_x: u16, size_y: u16,
+                                load_factor: f32, no_show_rate: f32,
+                                kwargs: Option<&PyDict>)
         -> PyResult<()> {
+        let options = passenger_options(kwargs)?;
         if self.aircraft.is_none() {
             let new_aircraft = standard_layout(size_x, size_y);
-            let passengers = random_window_first(size_x, size_y);
+            let passengers = random_window_first(size_x, size_y, load_factor)
+                .and_then(|p| remove_no_shows(p, no_show_rate))
+                .and_then(|(p, _)| apply_options(p, &options));
 
             if passengers.is_ok() && new_aircraft.is_ok() {
                 let mut new_aircraft = new_aircraft.unwrap();
@@ -269,6 +375,14 @@ impl PyAircraft {
     /// Initialises an interactive Aircraft object of the given size and fills
     /// it with passengers in a random pattern.
     ///
+    /// Only `load_factor` of the seats are sold, and `no_show_rate` of the
+    /// generated passengers are removed again after the order is generated.
+    /// `late` of those left don't follow the called order, either displaced
+    /// by up to `max_shift` places or arriving up to `max_delay` steps late,
+    /// and given an `arrival_rate` they reach the gate over time, that many a
+    /// step on average. `parties` of them travel in parties of up to
+    /// `max_party` passengers who sit together.
+    ///
     /// # Examples
     ///
     /// ```python
@@ -281,10 +395,16 @@ impl PyAircraft {
     /// except:
     ///     print("Invalid size")
     /// ```
-    fn init_random(&mut self, size_x: u16, size_y: u16) -> PyResult<()> {
+    #[args(load_factor = "1.0", no_show_rate = "0.0", kwargs = "**")]
+    fn init_random(&mut self, size_x: u16, size_y: u16,
+                   load_factor: f32, no_show_rate: f32,
+                   kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options = passenger_options(kwargs)?;
         if self.aircraft.is_none() {
             let new_aircraft = standard_layout(size_x, size_y);
-            let passengers = random(size_x, size_y);
+            let passengers = random(size_x, size_y, load_factor)
+                .and_then(|p| remove_no_shows(p, no_show_rate))
+                .and_then(|(p, _)| apply_options(p, &options));
 
             if passengers.is_ok() && new_aircraft.is_ok() {
                 let mut new_aircraft = new_aircraft.unwrap();
@@ -302,10 +422,60 @@ impl PyAircraft {
         }
     }
     
+    /// Initialises an interactive Aircraft object from a layout, filling every
+    /// seat with passengers who board premium cabin classes first.
+    ///
+    /// `layout` may be a layout file, one of the `bundled_aircraft()` or a seat
+    /// map. Passengers in first, business and premium economy board a class
+    /// at a time, followed by everybody else in the order given by `strategy`:
+    /// "back_first", "front_first", "window_first", "aisle_first" or
+    /// "random". Besides the keyword arguments of `init_random`, which send
+    /// passengers out of order, give them arrival times and group them into
+    /// parties, the keyword argument `aisle_chairs` seats that many passengers
+    /// in aisle chairs, boarding before everybody else or after them if
+    /// `chairs_last` is set, and `wrong_seats` sends that share of those who
+    /// show up to the seat beside their own.
+    ///
+    /// # Examples
+    ///
+    /// ```python
+    /// # Python Code
+    /// import aircraft_sim
+    ///
+    /// plane = aircraft_sim.PyAircraft()
+    /// plane.init_classed("a320", "back_first", aisle_chairs=2)
+    /// ```
+    #[args(load_factor = "1.0", no_show_rate = "0.0", kwargs = "**")]
+    fn init_classed(&mut self, layout: &str, strategy: &str,
+                    load_factor: f32, no_show_rate: f32,
+                    kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options = classed_options(kwargs)?;
+        if self.aircraft.is_some() {
+            return Err(PyTypeError::new_err("Aircraft already initialised"));
+        }
+        let mut aircraft = load_layout(layout)
+            .map_err(PyTypeError::new_err)?;
+        let passengers = generate_for(&aircraft, strategy, load_factor)
+            .and_then(|p| assign_aisle_chairs(p, options.aisle_chairs,
+                                              options.chairs_last))
+            .and_then(|p| remove_no_shows(p, no_show_rate))
+            .and_then(|(p, _)| apply_options(p, &options.passengers))
+            .and_then(|p| assign_wrong_seats(p, options.wrong_seats))
+            .map_err(PyTypeError::new_err)?;
+        for person in passengers {
+            aircraft.add_passenger(person);
+        }
+        self.size = aircraft.get_size();
+        self.aircraft = Some(aircraft);
+        Ok(())
+    }
+
     /// Returns the aircraft's layout
     ///
     /// Returns the layout of the aircraft as a nested vector using integers
-    /// instead of enums to provide Python compatibility.
+    /// instead of enums to provide Python compatibility: 0 for no tile, then
+    /// aisles, seats, entrances, galleys, lavatories, bulkheads, exit rows,
+    /// cross-aisles and the rest of wide seats from 1 to 9.
     fn get_values(&mut self) -> PyResult<Vec<Vec<u8>>> {
         if self.aircraft.is_some() {
             let mut values = Vec::<Vec<u8>>::new();
@@ -320,6 +490,12 @@ impl PyAircraft {
                         Variant::Aisle => 1,
                         Variant::Seat => 2,
                         Variant::Entrance => 3,
+                        Variant::Galley => 4,
+                        Variant::Lavatory => 5,
+                        Variant::Bulkhead => 6,
+                        Variant::ExitRow => 7,
+                        Variant::CrossAisle => 8,
+                        Variant::SeatSpan => 9,
                     });
                 }
                 values.push(row);
@@ -383,6 +559,263 @@ impl PyAircraft {
         Ok(self.aircraft.as_ref().unwrap().is_complete())
     }
 
+    /// Switches the aircraft into deplaning mode.
+    ///
+    /// Seats every passenger that hasn't yet boarded, who then leave through
+    /// the nearest entrance following the given strategy: "row_by_row",
+    /// "free_for_all" or "aisle_first".
+    fn start_deplaning(&mut self, strategy: &str) -> PyResult<()> {
+        let strategy = str_to_deplaning(strategy)
+            .ok_or_else(|| PyTypeError::new_err("Invalid strategy"))?;
+        match self.aircraft.as_mut() {
+            Some(aircraft) => {
+                aircraft.deplane(strategy);
+                Ok(())
+            },
+            None => Err(PyTypeError::new_err("Aircraft not initialised")),
+        }
+    }
+
+    /// Sets up the gate passengers board through.
+    ///
+    /// The gate agent checks in one passenger every `service_time` steps,
+    /// after which they take `walk_time` steps to walk down a jet bridge
+    /// holding at most `bridge_length` passengers.
+    fn set_gate(&mut self, service_time: u16, bridge_length: usize,
+                walk_time: u16) -> PyResult<()> {
+        match self.aircraft.as_mut() {
+            Some(aircraft) => {
+                aircraft.set_gate(Gate::new(service_time,
+                                            bridge_length,
+                                            walk_time));
+                Ok(())
+            },
+            None => Err(PyTypeError::new_err("Aircraft not initialised")),
+        }
+    }
+
+    /// Returns the aircraft drawn as text, one row per line.
+    ///
+    /// Empty seats are drawn as `#`, aisles as `.` and entrances as `E`.
+    /// Passengers are drawn as `@` while carrying baggage and `o` otherwise,
+    /// and a passenger making their way past another as `%`. `axes` labels
+    /// each row and column with its coordinate, or with `labels` by its row
+    /// number or seat letter.
+    #[args(axes = "true", labels = "false")]
+    fn render(&self, axes: bool, labels: bool) -> PyResult<String> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        let mut out = Vec::<u8>::new();
+        aircraft.render(&mut out, &RenderOptions {
+            glyphs: Glyphs::default(),
+            axes,
+            labels,
+        }).map_err(|_| PyTypeError::new_err("Couldn't render aircraft"))?;
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    /// Draws the aircraft's current step to `path` as an SVG or PNG image,
+    /// chosen by the file's extension.
+    ///
+    /// `step` draws the step number in the corner, and `zones` colours
+    /// passengers by their boarding zone.
+    #[args(step = "false", zones = "false")]
+    fn save_frame(&self, path: &str, step: bool, zones: bool)
+        -> PyResult<()> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        let overlays = export::Overlays {
+            step,
+            zones,
+            ..export::Overlays::default()
+        };
+        export::write_frame(aircraft, Path::new(path),
+                            &export::Sprites::bundled(), &overlays)
+            .map_err(PyTypeError::new_err)
+    }
+
+    /// Returns one of the aircraft's congestion counters for every tile, using
+    /// the same nested vector arrangement as `PyAircraft.get_values()`.
+    ///
+    /// `counter` is "occupied" for the steps each tile held a passenger,
+    /// "waited" for the steps passengers were held up there, or "passes" for
+    /// the number of times a passenger squeezed past another into it.
+    #[args(counter = "\"waited\"")]
+    fn get_heatmap(&self, counter: &str) -> PyResult<Vec<Vec<f32>>> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        Ok(rows(aircraft.get_heatmap().get(py_counter(counter)?)))
+    }
+
+    /// Writes the aircraft's congestion counters to `path`.
+    ///
+    /// A .csv file holds every counter for each tile, while a .png or .svg
+    /// file draws `counter` over the aircraft's layout.
+    #[args(counter = "\"waited\"")]
+    fn save_heatmap(&self, path: &str, counter: &str) -> PyResult<()> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        let path = Path::new(path);
+        if path.extension().and_then(|e| e.to_str()) == Some("csv") {
+            write_heatmap(aircraft.get_heatmap(), path)
+        } else {
+            export::write_heatmap(aircraft, aircraft.get_heatmap(),
+                                  py_counter(counter)?, path,
+                                  &export::Sprites::bundled())
+        }.map_err(PyTypeError::new_err)
+    }
+
+    /// Writes the aircraft's layout to `path`, as a csv file or, for .json and
+    /// .toml files, an aircraft definition.
+    fn save_layout(&self, path: &str) -> PyResult<()> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        write_layout(aircraft, Path::new(path)).map_err(PyTypeError::new_err)
+    }
+
+    /// Writes every passenger the aircraft was filled with to a csv file, in
+    /// their original boarding order, so that they can be read back with
+    /// `init_from_file`.
+    ///
+    /// `labels` writes seats by their labels, such as "23C", instead of by
+    /// their coordinates.
+    #[args(labels = "false")]
+    fn save_passengers(&self, path: &str, labels: bool) -> PyResult<()> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        let path = Path::new(path);
+        if labels {
+            write_labelled_passengers(aircraft, aircraft.get_manifest(), path)
+        } else {
+            write_passengers(aircraft.get_manifest(), path)
+        }.map_err(PyTypeError::new_err)
+    }
+
+    /// Returns the cabin class of the seat at `x`,`y`, such as "business", or
+    /// `None` if the tile isn't a seat or its row has no class.
+    fn get_class(&self, x: u16, y: u16) -> PyResult<Option<&'static str>> {
+        Ok(self.aircraft_at(x, y)?.get_class(x, y).map(Class::as_str))
+    }
+
+    /// Returns the legroom of the seat at `x`,`y`: "tight", "standard",
+    /// "extra" or "premium".
+    fn get_legroom(&self, x: u16, y: u16) -> PyResult<&'static str> {
+        Ok(self.aircraft_at(x, y)?.get_legroom(x, y).as_str())
+    }
+
+    /// Sets the legroom of the seat at `x`,`y`, which sets how long it takes
+    /// other passengers to get past whoever sits there.
+    fn set_legroom(&mut self, x: u16, y: u16, legroom: &str) -> PyResult<()> {
+        let legroom = str_to_legroom(legroom)
+            .ok_or_else(|| PyTypeError::new_err("Invalid legroom"))?;
+        self.aircraft_at_mut(x, y)?.set_legroom(x, y, legroom);
+        Ok(())
+    }
+
+    /// Sets who passengers may squeeze past in an aisle: "never" for nobody or
+    /// "wide" for anybody where the aisle is wide enough for two. To have
+    /// passengers step into an empty seat to let others by, use
+    /// `set_yield_steps`.
+    fn set_overtaking(&mut self, overtaking: &str) -> PyResult<()> {
+        let overtaking = str_to_overtaking(overtaking)
+            .ok_or_else(|| PyTypeError::new_err("Invalid overtaking rules"))?;
+        let aircraft = self.aircraft.as_mut()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        aircraft.set_overtaking(overtaking);
+        Ok(())
+    }
+
+    /// Lets passengers held up in the aisle step into an empty seat to let
+    /// somebody reach their row, staying there for `steps` steps, or with
+    /// `None`, stops them.
+    fn set_yield_steps(&mut self, steps: Option<u8>) -> PyResult<()> {
+        let aircraft = self.aircraft.as_mut()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        aircraft.set_yield_steps(steps);
+        Ok(())
+    }
+
+    /// Has passengers without a policy of their own decide how to move by the
+    /// policy registered as `policy`, such as "greedy".
+    fn set_policy(&mut self, policy: &str) -> PyResult<()> {
+        let policy = named_policy(policy)
+            .ok_or_else(|| PyTypeError::new_err("Unknown policy"))?;
+        let aircraft = self.aircraft.as_mut()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        aircraft.set_policy(policy);
+        Ok(())
+    }
+
+    /// Boards passengers only through the door nearest their seat, rather
+    /// than through whichever door is free.
+    fn set_nearest_door(&mut self, nearest_door: bool) -> PyResult<()> {
+        let aircraft = self.aircraft.as_mut()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        aircraft.set_nearest_door(nearest_door);
+        Ok(())
+    }
+
+    /// Sets the width of every aisle on row `y`, in passengers, or with
+    /// `None`, leaves it to the layout.
+    fn set_aisle_width(&mut self, y: u16, width: Option<u16>) -> PyResult<()> {
+        self.aircraft_at_mut(0, y)?.set_aisle_width(y, width)
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Adds a crew member described as "patrol X,Y X,Y", "assist X,Y" or
+    /// "stand X,Y", starting on the first tile given.
+    ///
+    /// Patrolling crew walk back and forth between their two tiles, assisting
+    /// crew stow the baggage of passengers beside them, and standing crew stay
+    /// on their tile, such as beside a door.
+    fn add_crew(&mut self, crew: &str) -> PyResult<()> {
+        let ((x, y), duty) = parse_crew(crew).map_err(PyTypeError::new_err)?;
+        let aircraft = self.aircraft.as_mut()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        aircraft.add_crew(x, y, duty).map_err(PyTypeError::new_err)
+    }
+
+    /// Returns how long each cabin class has taken to be seated so far, as a
+    /// list of `(class, passengers, seated, mean_step, last_step)` tuples.
+    ///
+    /// `class` is `None` for seats on rows without a class, and `mean_step`
+    /// and `last_step` are `None` until one of the class's passengers sits
+    /// down.
+    fn class_metrics(&self) -> PyResult<Vec<PyClassMetrics>> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        Ok(aircraft.class_metrics().iter()
+           .map(|m| (m.class.map(Class::as_str), m.passengers, m.seated,
+                     m.mean_step, m.last_step))
+           .collect())
+    }
+
+    /// Returns every passenger found in the wrong seat so far, as a list of
+    /// `((x, y), (own_x, own_y), found, resolved)` tuples.
+    ///
+    /// `resolved` is `None` until the passenger sits down in their own seat.
+    fn conflicts(&self) -> PyResult<Vec<PyConflict>> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        Ok(aircraft.get_conflicts().iter()
+           .map(|c| (c.seat, c.own_seat, c.found, c.resolved))
+           .collect())
+    }
+
+    /// Returns the label of the seat at `x`,`y`, such as "23C", or `None` if
+    /// the tile isn't a seat.
+    fn seat_label(&self, x: u16, y: u16) -> PyResult<Option<String>> {
+        Ok(self.aircraft_at(x, y)?.seat_label(x, y))
+    }
+
+    /// Returns the `(x, y)` coordinates of the seat labelled `label`, or
+    /// `None` if there is no such seat.
+    fn find_seat(&self, label: &str) -> PyResult<Option<(u16, u16)>> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        Ok(aircraft.find_seat(label))
+    }
+
     fn get_size_x(&self) -> PyResult<u16> {
         Ok(self.size.0)
     }
@@ -398,57 +831,108 @@ impl PyAircraft {
 /// Takes two vectors of filepaths, one for layout files and one for passenger
 /// files, and simulates each pair. Every aircraft is simulated in parallel with
 /// one another and the resulting times taken are returned as a vector in the
-/// same order they were passed in.
-#[pyfunction]
-fn mass_sim(layouts:Vec<&str>,passenger_lists:Vec<&str>) -> PyResult<Vec<u16>> {
-    // If a different number of files are passed in for each argument, the input
-    // is invalid.
-    if layouts.len() != passenger_lists.len() {
-        return Err(PyTypeError::new_err("Invalid input lengths"));
-    }
+/// same order they were passed in, with 0 marking a failed simulation.
+///
+/// If `deplaning` names a deplaning strategy ("row_by_row", "free_for_all" or
+/// "aisle_first") the passengers instead start in their seats and the time
+/// taken for all of them to leave is returned. `threads` limits how many
+/// aircraft are simulated at once, defaulting to all of them.
+#[pyfunction(deplaning = "None", threads = "None")]
+fn mass_sim(layouts: Vec<&str>,
+            passenger_lists: Vec<&str>,
+            deplaning: Option<&str>,
+            threads: Option<usize>) -> PyResult<Vec<u16>> {
+    core_mass_sim(layouts, passenger_lists, deplaning, threads)
+        .map(|records| records.iter()
+             .map(|record| record.steps.unwrap_or(0))
+             .collect())
+        .map_err(PyTypeError::new_err)
+}
 
-    let mut results = Vec::<u16>::new();
+/// Simulates a number of aircraft in parallel, exactly like `mass_sim`, but
+/// returns a full record of each simulation as a JSON string.
+///
+/// Each record holds the files used, the deplaning strategy (if any), the
+/// number of passengers, either the number of steps taken or the reason the
+/// simulation failed, how many passengers were found in the wrong seat and
+/// how long each cabin class took to be seated.
+///
+/// # Examples
+///
+/// ```python
+/// # Python code
+/// import json
+/// import aircraft_sim
+///
+/// records = aircraft_sim.mass_sim_records(["./layout.csv"],
+///                                         ["./passengers.csv"])
+/// print(json.loads(records[0])["steps"])
+/// ```
+#[pyfunction(deplaning = "None", threads = "None")]
+fn mass_sim_records(layouts: Vec<&str>,
+                    passenger_lists: Vec<&str>,
+                    deplaning: Option<&str>,
+                    threads: Option<usize>) -> PyResult<Vec<String>> {
+    core_mass_sim(layouts, passenger_lists, deplaning, threads)
+        .map(|records| records.iter().map(Record::to_json).collect())
+        .map_err(PyTypeError::new_err)
+}
 
-    // This vector holds the handles for each thread, allowing them to be joined
-    // after completion.
-    let mut jobs:Vec<thread::JoinHandle<Result<u16,&'static str>>> = Vec::new();
+/// Simulates a number of aircraft in parallel, exactly like `mass_sim`, and
+/// returns one of their congestion counters averaged over every successful
+/// simulation.
+///
+/// The counter is returned using the same nested vector arrangement as
+/// `PyAircraft.get_heatmap()`, and every layout must be the same size.
+///
+/// # Examples
+///
+/// ```python
+/// # Python code
+/// import aircraft_sim
+///
+/// waited = aircraft_sim.mass_sim_heatmap(["./layout.csv"] * 2,
+///                                        ["./a.csv", "./b.csv"])
+/// busiest = max(range(len(waited)), key=lambda y: sum(waited[y]))
+/// print("Row", busiest, "is the worst bottleneck")
+/// ```
+#[pyfunction(counter = "\"waited\"", deplaning = "None", threads = "None")]
+fn mass_sim_heatmap(layouts: Vec<&str>,
+                    passenger_lists: Vec<&str>,
+                    counter: &str,
+                    deplaning: Option<&str>,
+                    threads: Option<usize>) -> PyResult<Vec<Vec<f32>>> {
+    let counter = py_counter(counter)?;
+    core_mass_heatmap(layouts, passenger_lists, deplaning, threads)
+        .map(|heatmap| rows(heatmap.get(counter)))
+        .map_err(PyTypeError::new_err)
+}
 
-    for i in 0..layouts.len() {
-        let aircraft = read_layout(Path::new(layouts.get(i).unwrap()));
-        let passengers = read_passengers(Path::new(passenger_lists
-                                                   .get(i)
-                                                   .unwrap()));
-        
-        if aircraft.is_some() && passengers.is_some() {
-            let mut aircraft = aircraft.unwrap();
-            for i in passengers.unwrap() {
-                aircraft.add_passenger(i);
-            }
+/// Returns the names of the aircraft bundled with the simulator, which can be
+/// given in place of a layout file.
+#[pyfunction]
+fn bundled_aircraft() -> Vec<String> {
+    seat_map::BUNDLED.iter().map(|(name, _)| name.to_string()).collect()
+}
 
-            // A new thread for each aircraft simulation is created.
-            jobs.push(thread::spawn(move || {
-                aircraft.run_to_completion()
-            }));
+impl PyAircraft {
+    /// Returns the aircraft, failing if it hasn't been initialised or if
+    /// `x`,`y` lies outside it.
+    fn aircraft_at(&self, x: u16, y: u16) -> PyResult<&Aircraft> {
+        let aircraft = self.aircraft.as_ref()
+            .ok_or_else(|| PyTypeError::new_err("Aircraft not initialised"))?;
+        let (size_x, size_y) = aircraft.get_size();
+        if x >= size_x || y >= size_y {
+            return Err(PyValueError::new_err("Tile is outside the aircraft"));
         }
+        Ok(aircraft)
     }
-    
-    // All threads are joined here; errors are logged and a 0 value returned.
-    for i in jobs {
-        match i.join() {
-            Ok(x) => {
-                match x {
-                    Ok(x) => results.push(x),
-                    Err(e) => {
-                        log::error!("Simulation failed: {}", e);
-                        results.push(0);
-                    },
-                }
-            },
-            Err(e) => log::error!("Simulation failed: {:?}", e),
-        }
+
+    /// As `aircraft_at`, borrowing the aircraft mutably.
+    fn aircraft_at_mut(&mut self, x: u16, y: u16) -> PyResult<&mut Aircraft> {
+        self.aircraft_at(x, y)?;
+        Ok(self.aircraft.as_mut().unwrap())
     }
-    
-    return Ok(results);
 }
 
 #[pymodule]
@@ -456,60 +940,191 @@ fn aircraft_sim(py: Python, m: &PyModule) -> PyResult<()> {
     m.add("CustomError", py.get_type::<CustomError>())?;
     m.add_class::<PyAircraft>()?;
     m.add_function(wrap_pyfunction!(mass_sim, m)?)?;
+    m.add_function(wrap_pyfunction!(mass_sim_records, m)?)?;
+    m.add_function(wrap_pyfunction!(mass_sim_heatmap, m)?)?;
+    m.add_function(wrap_pyfunction!(bundled_aircraft, m)?)?;
+
+    Ok(())
+}
+
+
+/// Reads the changes to make to a generated boarding order from the keyword
+/// arguments given to an `init_*` method, named after the fields of
+/// `PassengerOptions`.
+fn passenger_options(kwargs: Option<&PyDict>) -> PyResult<PassengerOptions> {
+    let mut options = PassengerOptions::default();
+    if let Some(kwargs) = kwargs {
+        for (key, value) in kwargs.iter() {
+            passenger_option(&mut options, key.extract()?, value)?;
+        }
+    }
+    Ok(options)
+}
 
+/// Sets one of the `PassengerOptions` from a Python keyword argument.
+fn passenger_option(options: &mut PassengerOptions, key: &str, value: &PyAny)
+    -> PyResult<()> {
+    match key {
+        "late" => options.late = value.extract()?,
+        "max_shift" => options.max_shift = value.extract()?,
+        "max_delay" => options.max_delay = value.extract()?,
+        "arrival_rate" => options.arrival_rate = value.extract()?,
+        "parties" => options.parties = value.extract()?,
+        "max_party" => options.max_party = value.extract()?,
+        key => return Err(PyTypeError::new_err(
+            format!("Unexpected keyword argument '{}'", key))),
+    }
     Ok(())
 }
 
+/// Keyword arguments taken by `PyAircraft.init_classed`.
+struct ClassedOptions {
+    /// Number of passengers boarding in aisle chairs.
+    aisle_chairs: usize,
+    /// Whether passengers in aisle chairs board after everybody else.
+    chairs_last: bool,
+    /// Share of passengers who head for the seat beside their own.
+    wrong_seats: f32,
+    /// Changes shared with `PyAircraft.init_random`.
+    passengers: PassengerOptions,
+}
+
+/// Reads `PyAircraft.init_classed`'s keyword arguments.
+fn classed_options(kwargs: Option<&PyDict>) -> PyResult<ClassedOptions> {
+    let mut options = ClassedOptions {
+        aisle_chairs: 0,
+        chairs_last: false,
+        wrong_seats: 0.0,
+        passengers: PassengerOptions::default(),
+    };
+    if let Some(kwargs) = kwargs {
+        for (key, value) in kwargs.iter() {
+            match key.extract::<&str>()? {
+                "aisle_chairs" => options.aisle_chairs = value.extract()?,
+                "chairs_last" => options.chairs_last = value.extract()?,
+                "wrong_seats" => options.wrong_seats = value.extract()?,
+                key => passenger_option(&mut options.passengers, key, value)?,
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Converts a counter's name into a `Counter`, for Python.
+fn py_counter(counter: &str) -> PyResult<Counter> {
+    str_to_counter(counter)
+        .ok_or_else(|| PyTypeError::new_err("Invalid heatmap counter"))
+}
+
+/// Rearranges a grid indexed by column then row into a list of rows, like
+/// `PyAircraft.get_values()`.
+fn rows(grid: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    let size_y = grid.first().map_or(0, |column| column.len());
+    (0..size_y).map(|y| grid.iter().map(|column| column[y]).collect())
+        .collect()
+}
+
+fn core_mass_sim(layouts: Vec<&str>,
+                 passenger_lists: Vec<&str>,
+                 deplaning: Option<&str>,
+                 threads: Option<usize>) -> Result<Vec<Record>, &'static str> {
+    let (mut records, indices, jobs) = load_jobs(layouts, passenger_lists,
+                                                 deplaning)?;
+
+    // Failed simulations are logged by `batch::run_with` and kept in their
+    // records.
+    let threads = threads.unwrap_or(jobs.len());
+    let results = batch::run_with(jobs, threads, |aircraft, result| {
+        (result, aircraft.class_metrics(), aircraft.get_conflicts().to_vec())
+    });
+    for (i, (result, classes, conflicts)) in indices.into_iter().zip(results) {
+        records[i].set_result(result);
+        records[i].set_conflicts(&conflicts);
+        records[i].classes = classes;
+    }
+    Ok(records)
+}
+
+fn core_mass_heatmap(layouts: Vec<&str>,
+                     passenger_lists: Vec<&str>,
+                     deplaning: Option<&str>,
+                     threads: Option<usize>) -> Result<Heatmap, &'static str> {
+    let (_, _, jobs) = load_jobs(layouts, passenger_lists, deplaning)?;
+
+    let threads = threads.unwrap_or(jobs.len());
+    let heatmaps: Vec<Heatmap> = batch::run_heatmaps(jobs, threads)
+        .into_iter()
+        .filter(|(result, _)| result.is_ok())
+        .map(|(_, heatmap)| heatmap)
+        .collect();
+    if heatmaps.is_empty() {
+        return Err("No simulations succeeded");
+    }
+    Heatmap::average(&heatmaps).ok_or("Layouts must all be the same size")
+}
+
+/// A record for every simulation, the record each loaded aircraft belongs to,
+/// and the aircraft themselves.
+type Jobs = (Vec<Record>, Vec<usize>, Vec<Aircraft>);
 
-fn core_mass_sim(layouts:Vec<&str>,passenger_lists:Vec<&str>) -> Result<Vec<u16>, &'static str> {
+/// Reads each pair of files into an aircraft ready to be simulated.
+///
+/// Records for pairs that couldn't be loaded already hold their error.
+fn load_jobs(layouts: Vec<&str>,
+             passenger_lists: Vec<&str>,
+             deplaning: Option<&str>) -> Result<Jobs, &'static str> {
     // If a different number of files are passed in for each argument, the input
     // is invalid.
     if layouts.len() != passenger_lists.len() {
         return Err("Invalid input lengths");
     }
+    let strategy = match deplaning {
+        Some(strategy) => Some(str_to_deplaning(strategy)
+                               .ok_or("Invalid deplaning strategy")?),
+        None => None,
+    };
 
-    let mut results = Vec::<u16>::new();
+    let mut records = Vec::<Record>::new();
+    // The record each simulated aircraft belongs to
+    let mut indices = Vec::<usize>::new();
+    let mut jobs = Vec::<Aircraft>::new();
 
-    // This vector holds the handles for each thread, allowing them to be joined
-    // after completion.
-    let mut jobs:Vec<thread::JoinHandle<Result<u16,&'static str>>> = Vec::new();
+    for (layout, passenger_list) in layouts.iter().zip(passenger_lists.iter()) {
+        let mut record = Record::new(layout, passenger_list);
+        record.deplaning = deplaning.map(str::to_string);
 
-    for i in 0..layouts.len() {
-        let aircraft = read_layout(Path::new(layouts.get(i).unwrap()));
-        let passengers = read_passengers(Path::new(passenger_lists
-                                                   .get(i)
-                                                   .unwrap()));
+        let aircraft = match load_layout(layout) {
+            Ok(aircraft) if Path::new(passenger_list).is_file() => aircraft,
+            result => {
+                record.set_result(result.and(Err("File not found")));
+                records.push(record);
+                continue;
+            },
+        };
+        let passengers = read_passengers(Path::new(passenger_list));
         
-        if aircraft.is_some() && passengers.is_some() {
-            let mut aircraft = aircraft.unwrap();
-            for i in passengers.unwrap() {
-                aircraft.add_passenger(i);
+        if let Some(passengers) = passengers {
+            let mut aircraft = aircraft;
+            record.passenger_count = passengers.len();
+            let added: Result<(), &'static str> = passengers.into_iter()
+                .try_for_each(|i| aircraft.try_add_passenger(i));
+            if let Err(e) = added {
+                record.set_result(Err(e));
+                records.push(record);
+                continue;
             }
-
-            // A new thread for each aircraft simulation is created.
-            jobs.push(thread::spawn(move || {
-                aircraft.run_to_completion()
-            }));
-        }
-    }
-    
-    // All threads are joined here; errors are logged and a 0 value returned.
-    for i in jobs {
-        match i.join() {
-            Ok(x) => {
-                match x {
-                    Ok(x) => results.push(x),
-                    Err(e) => {
-                        log::error!("Simulation failed: {}", e);
-                        results.push(0);
-                    },
-                }
-            },
-            Err(e) => log::error!("Simulation failed: {:?}", e),
+            if let Some(strategy) = strategy {
+                aircraft.deplane(strategy);
+            }
+            record.set_aircraft(&aircraft);
+            indices.push(records.len());
+            jobs.push(aircraft);
+        } else {
+            record.set_result(Err("Invalid input files"));
         }
+        records.push(record);
     }
-    
-    return Ok(results);
+    Ok((records, indices, jobs))
 }
 
 #[cfg(test)]
@@ -554,6 +1169,46 @@ mod tests {
         layouts.push("./config/standard_layout.csv");
         layouts.push("./config/standard_layout.csv");
 
-        assert!(core_mass_sim(layouts, passenger_lists).is_ok());
+        assert!(core_mass_sim(layouts.clone(),
+                              passenger_lists.clone(),
+                              None, None).is_ok());
+        assert!(core_mass_sim(layouts, passenger_lists,
+                              Some("aisle_first"), Some(1)).is_ok());
+    }
+
+    #[test]
+    fn mass_simulation_records() {
+        let records = core_mass_sim(vec!["./config/standard_layout.csv",
+                                         "./config/missing.csv"],
+                                    vec!["./config/steffen.csv",
+                                         "./config/steffen.csv"],
+                                    None, None).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].passenger_count, 72);
+        assert!(records[0].steps.is_some());
+        assert_eq!(records[1].steps, None);
+        assert_eq!(records[1].error,
+                   Some("Layout file not found".to_string()));
+    }
+
+    #[test]
+    fn mass_simulation_heatmap() {
+        let heatmap = core_mass_heatmap(vec!["./config/standard_layout.csv",
+                                             "./config/standard_layout.csv"],
+                                        vec!["./config/steffen.csv",
+                                             "./config/block1.csv"],
+                                        None, Some(2)).unwrap();
+        let aircraft = read_layout(Path::new("./config/standard_layout.csv"))
+            .unwrap();
+        assert_eq!(heatmap.get_size(), aircraft.get_size());
+
+        let occupied = rows(heatmap.get(Counter::Occupied));
+        assert_eq!(occupied.len(), aircraft.get_size().1 as usize);
+        assert!(occupied.iter().flatten().any(|v| *v > 0.0));
+
+        assert!(core_mass_heatmap(vec!["./config/missing.csv"],
+                                  vec!["./config/steffen.csv"],
+                                  None, None).is_err());
     }
 }
@@ -13,6 +13,12 @@ from tkinter import messagebox
 DEFAULT_SIZE = 11 # This value is used if an invalid size is passed when
                   # initialising interactive mode.
 
+# Colours of the tiles drawn as plain rectangles, keyed by the values returned
+# by `PyAircraft.get_values()`: galleys, lavatories, bulkheads, exit rows and
+# cross-aisles.
+TILE_COLOURS = {4: "#969696", 5: "#5a5aaa", 6: "#000000", 7: "#006400",
+                8: "#0000c8"}
+
 class Application(tk.Frame):
     # This constructor initialises all widgets and variables which will be used
     # across multiple functions.
@@ -388,6 +394,13 @@ class Application(tk.Frame):
                                              y,
                                              anchor=tk.NW,
                                              image=self.seatImage)
+                elif tile in TILE_COLOURS:
+                    self.canvas.create_rectangle(x,
+                                                 y,
+                                                 x + 25,
+                                                 y + 25,
+                                                 fill=TILE_COLOURS[tile],
+                                                 outline="")
                 x += 25
             x = 0
             y += 25
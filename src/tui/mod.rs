@@ -0,0 +1,373 @@
+//! Provides an interactive terminal viewer for playing back simulations.
+//!
+//! Boarding is animated in place, and can be paused, stepped through in either
+//! direction and sped up or slowed down. Passengers are coloured by their
+//! boarding zone, and drawn differently while they are still carrying baggage.
+
+use std::io::{stdout, Write};
+use std::time::Duration;
+
+use crossterm::{cursor, execute, queue, terminal};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute,
+                       SetForegroundColor};
+
+use super::aircraft::{Aircraft, MAX_ITERATIONS};
+use super::aircraft::cabin::SeatMap;
+use super::aircraft::tile::Variant;
+use super::aircraft::render::Glyphs;
+use super::aircraft::person::Person;
+
+/// Colours given to passengers, cycling through by boarding zone.
+const ZONE_COLOURS: [Color; 6] = [Color::Cyan,
+                                  Color::Yellow,
+                                  Color::Magenta,
+                                  Color::Green,
+                                  Color::Blue,
+                                  Color::Red];
+
+/// The shortest and longest delays between steps when playing, in
+/// milliseconds.
+const MIN_DELAY: u64 = 10;
+const MAX_DELAY: u64 = 5000;
+
+/// A snapshot of what a single passenger looked like at one step.
+#[derive(PartialEq, Debug, Clone, Copy)]
+struct Occupant {
+    zone: u16,
+    baggage: bool,
+}
+
+impl Occupant {
+    fn new(person: &Person) -> Occupant {
+        Occupant {
+            zone: person.get_zone(),
+            baggage: person.has_baggage(),
+        }
+    }
+}
+
+/// A snapshot of a single tile at one step.
+#[derive(PartialEq, Debug, Clone, Copy)]
+struct Cell {
+    variant: Variant,
+    occupier: Option<Occupant>,
+    passer: Option<Occupant>,
+}
+
+/// A snapshot of the whole aircraft at one step, stored by column like the
+/// aircraft's own layout.
+#[derive(PartialEq, Debug, Clone)]
+struct Frame {
+    step: u16,
+    complete: bool,
+    cells: Vec<Vec<Cell>>,
+}
+
+impl Frame {
+    fn capture(aircraft: &Aircraft) -> Frame {
+        let (size_x, size_y) = aircraft.get_size();
+        Frame {
+            step: aircraft.get_step(),
+            complete: aircraft.is_complete(),
+            cells: (0..size_x).map(|x| (0..size_y).map(|y| Cell {
+                variant: aircraft.get_tile_variant(x, y),
+                occupier: aircraft.get_occupier(x, y).map(Occupant::new),
+                passer: aircraft.get_passer(x, y).map(Occupant::new),
+            }).collect()).collect(),
+        }
+    }
+}
+
+/// Seat labels drawn along the edges of the cabin: a letter above each seat
+/// column and a number beside each row of seats.
+struct Labels {
+    columns: String,
+    rows: Vec<String>,
+}
+
+impl Labels {
+    fn new(aircraft: &Aircraft) -> Labels {
+        let (size_x, size_y) = aircraft.get_size();
+        let seat_map = SeatMap::new(aircraft);
+        Labels {
+            columns: (0..size_x).map(|x| seat_map.column(x).unwrap_or(' '))
+                .collect(),
+            rows: (0..size_y).map(|y| seat_map.row(y)
+                .map_or(String::new(), |n| n.to_string())).collect(),
+        }
+    }
+}
+
+/// Holds a running simulation along with every step it has been through, so
+/// that playback can step backwards as well as forwards.
+///
+/// The simulation itself can't be rewound, so earlier steps are replayed from
+/// `history` until playback catches up with the live aircraft again.
+struct Playback {
+    aircraft: Aircraft,
+    history: Vec<Frame>,
+    position: usize,
+    labels: Labels,
+}
+
+impl Playback {
+    fn new(aircraft: Aircraft) -> Playback {
+        let first = Frame::capture(&aircraft);
+        let labels = Labels::new(&aircraft);
+        Playback {
+            aircraft,
+            history: vec![first],
+            position: 0,
+            labels,
+        }
+    }
+
+    fn current(&self) -> &Frame {
+        &self.history[self.position]
+    }
+
+    /// Checks whether there are no more steps to show.
+    fn finished(&self) -> bool {
+        let frame = self.current();
+        self.position + 1 == self.history.len()
+            && (frame.complete || frame.step >= MAX_ITERATIONS)
+    }
+
+    /// Moves forward a step, simulating a new one if needed.
+    fn forward(&mut self) {
+        if self.position + 1 < self.history.len() {
+            self.position += 1;
+        } else if !self.finished() {
+            self.aircraft.update();
+            self.history.push(Frame::capture(&self.aircraft));
+            self.position += 1;
+        }
+    }
+
+    /// Moves back a step, if there is one.
+    fn back(&mut self) {
+        if self.position > 0 {
+            self.position -= 1;
+        }
+    }
+}
+
+/// Plays back a simulation in the terminal until the user quits.
+///
+/// `delay` is the initial number of milliseconds between steps while playing.
+/// Playback starts paused.
+pub fn play(aircraft: Aircraft, delay: u64) -> Result<(), String> {
+    let mut out = stdout();
+    terminal::enable_raw_mode().map_err(|e| e.to_string())?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)
+        .map_err(|e| e.to_string())?;
+
+    let result = run(&mut Playback::new(aircraft), delay);
+
+    // The terminal is restored even if playback failed.
+    let restored = execute!(out, cursor::Show, terminal::LeaveAlternateScreen)
+        .and_then(|_| terminal::disable_raw_mode());
+    result.and(restored.map_err(|e| e.to_string()))
+}
+
+/// Draws frames and handles key presses until the user quits.
+fn run(playback: &mut Playback, mut delay: u64) -> Result<(), String> {
+    let mut playing = false;
+    loop {
+        draw(playback.current(), &playback.labels, playing, delay)
+            .map_err(|e| e.to_string())?;
+
+        let timeout = if playing {
+            Duration::from_millis(delay)
+        } else {
+            Duration::from_secs(3600)
+        };
+        if !event::poll(timeout).map_err(|e| e.to_string())? {
+            playback.forward();
+            if playback.finished() {
+                playing = false;
+            }
+            continue;
+        }
+
+        if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char(' ') => {
+                    playing = !playing && !playback.finished();
+                },
+                KeyCode::Right | KeyCode::Char('n') => {
+                    playing = false;
+                    playback.forward();
+                },
+                KeyCode::Left | KeyCode::Char('b') => {
+                    playing = false;
+                    playback.back();
+                },
+                KeyCode::Home => playback.position = 0,
+                KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => {
+                    delay = (delay / 2).max(MIN_DELAY);
+                },
+                KeyCode::Char('-') | KeyCode::Down => {
+                    delay = (delay * 2).min(MAX_DELAY);
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+/// Draws a single frame, along with the playback status and controls.
+fn draw(frame: &Frame, labels: &Labels, playing: bool, delay: u64)
+    -> std::io::Result<()> {
+    let mut out = stdout();
+    queue!(out,
+           cursor::MoveTo(0, 0),
+           terminal::Clear(terminal::ClearType::All),
+           Print(format!("Step {:<6}{:<10}{}ms/step\r\n",
+                         frame.step,
+                         if playing { "Playing" } else { "Paused" },
+                         delay)))?;
+    if frame.complete {
+        queue!(out, Print("Complete\r\n"))?;
+    } else {
+        queue!(out, Print("\r\n"))?;
+    }
+
+    let glyphs = Glyphs::default();
+    let size_y = frame.cells.first().map_or(0, |column| column.len());
+    queue!(out, Print(format!("{:>4} {}\r\n", "", labels.columns)))?;
+    for y in 0..size_y {
+        queue!(out, Print(format!("{:>4} ", labels.rows[y])))?;
+        for column in &frame.cells {
+            draw_cell(&mut out, &column[y], &glyphs)?;
+        }
+        queue!(out, Print("\r\n"))?;
+    }
+
+    queue!(out,
+           Print(format!("\r\n{} carrying baggage  {} stowed  {} seat  \
+                          {} aisle  {} entrance\r\n",
+                         glyphs.baggage,
+                         glyphs.passenger,
+                         glyphs.seat,
+                         glyphs.aisle,
+                         glyphs.entrance)),
+           Print("space play/pause  \u{2190}/\u{2192} step  +/- speed  \
+                  home restart  q quit\r\n"))?;
+    out.flush()
+}
+
+fn draw_cell<W: Write>(out: &mut W, cell: &Cell, glyphs: &Glyphs)
+    -> std::io::Result<()> {
+    match cell.occupier.or(cell.passer) {
+        Some(occupant) => {
+            let colour = ZONE_COLOURS[occupant.zone as usize
+                                      % ZONE_COLOURS.len()];
+            queue!(out, SetForegroundColor(colour))?;
+            // A second passenger squeezing past is shown in reverse video.
+            if cell.passer.is_some() {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(out,
+                   Print(if occupant.baggage {
+                       glyphs.baggage
+                   } else {
+                       glyphs.passenger
+                   }),
+                   SetAttribute(Attribute::Reset),
+                   ResetColor)
+        },
+        None => queue!(out, Print(glyphs.empty(cell.variant))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::config;
+
+    fn aircraft() -> Aircraft {
+        let mut aircraft = Aircraft::new(3, 4);
+        for y in 0..4 {
+            aircraft.set_tile(0, y, Variant::Seat);
+        }
+        aircraft.set_tile(1, 3, Variant::Entrance);
+        let mut person = Person::new("DEFAULT");
+        person.target_seat(0, 0);
+        person.set_baggage(true);
+        person.set_zone(2);
+        aircraft.add_passenger(person);
+        aircraft
+    }
+
+    #[test]
+    fn capture() {
+        let mut aircraft = aircraft();
+        aircraft.update();
+        let frame = Frame::capture(&aircraft);
+
+        assert_eq!(frame.step, 1);
+        assert_eq!(frame.cells[0][0].variant, Variant::Seat);
+        assert_eq!(frame.cells[1][3].occupier,
+                   Some(Occupant { zone: 2, baggage: true }));
+        assert_eq!(frame.cells[1][3].passer, None);
+    }
+
+    #[test]
+    fn seat_labels() {
+        let labels = Labels::new(&config::standard_layout(5, 2).unwrap());
+        assert_eq!(labels.columns, "AB CD");
+        assert_eq!(labels.rows, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn step_back_and_forward() {
+        let mut playback = Playback::new(aircraft());
+        for _ in 0..3 {
+            playback.forward();
+        }
+        let third = playback.current().clone();
+
+        playback.back();
+        playback.back();
+        assert_eq!(playback.current().step, 1);
+        assert_eq!(playback.history.len(), 4);
+
+        // Stepping forward again replays history instead of simulating.
+        playback.forward();
+        playback.forward();
+        assert_eq!(playback.current(), &third);
+        assert_eq!(playback.aircraft.get_step(), 3);
+
+        while !playback.finished() {
+            playback.forward();
+        }
+        assert!(playback.current().complete);
+    }
+
+    #[test]
+    fn zones_from_file() {
+        let path = std::env::temp_dir().join("aircraft_sim_tui_zones.csv");
+        let list = config::random_back_first(7, 10, 1.0).unwrap();
+        config::write_passengers(&list, &path).unwrap();
+        let mut aircraft = config::standard_layout(7, 10).unwrap();
+        for person in config::read_passengers(&path).unwrap() {
+            aircraft.add_passenger(person);
+        }
+        aircraft.run_to_completion().unwrap();
+
+        let mut zones: Vec<u16> = Frame::capture(&aircraft).cells.iter()
+            .flatten()
+            .filter_map(|cell| cell.occupier.map(|o| o.zone))
+            .collect();
+        zones.sort_unstable();
+        zones.dedup();
+        assert_eq!(zones.len(), 10,
+                   "Passengers read from a file lost their zones");
+    }
+}